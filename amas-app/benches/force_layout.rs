@@ -0,0 +1,57 @@
+use amas_app::force_layout::{ForceLayout, LayoutSettings, layout_size};
+use criterion::{
+    BenchmarkId, Criterion, black_box, criterion_group, criterion_main,
+};
+
+/// Builds a graph shaped like a codebase: files mostly import a few files
+/// near them, plus some widely imported ones.
+fn make_layout(file_count: usize, settings: LayoutSettings) -> ForceLayout {
+    let mut edges = Vec::new();
+    for file in 1..file_count {
+        edges.push((file, file / 2));
+        edges.push((file, (file * 7919) % file_count));
+        if file % 10 == 0 {
+            edges.push((file, 0));
+        }
+    }
+    let (width, height) = layout_size(file_count);
+    ForceLayout::new(vec![None; file_count], edges, width, height, settings)
+}
+
+fn force_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("force layout iteration");
+    for file_count in [1_000, 10_000, 50_000] {
+        group.bench_with_input(
+            BenchmarkId::new("barnes-hut", file_count),
+            &file_count,
+            |b, &file_count| {
+                let mut layout = make_layout(file_count, LayoutSettings::default());
+                b.iter(|| {
+                    layout.iterate();
+                    black_box(layout.positions());
+                });
+            },
+        );
+    }
+
+    // Computing every pair of files, for comparison
+    let exact = LayoutSettings {
+        theta: 0.0,
+        ..Default::default()
+    };
+    group.bench_with_input(
+        BenchmarkId::new("exact", 1_000),
+        &1_000,
+        |b, &file_count| {
+            let mut layout = make_layout(file_count, exact);
+            b.iter(|| {
+                layout.iterate();
+                black_box(layout.positions());
+            });
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(benches, force_layout);
+criterion_main!(benches);
@@ -1,30 +1,209 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc::channel},
+};
+
+use clap::Parser;
 use floem::{
     Application, IntoView,
+    ext_event::{create_ext_action, create_signal_from_channel},
+    reactive::{
+        RwSignal, Scope, SignalGet, SignalUpdate, SignalWith, create_effect,
+    },
     views::{Decorators, dyn_container, stack},
     window::{WindowConfig, WindowId},
 };
 
+use lapce_app::workspace::{LapceWorkspace, LapceWorkspaceType};
+
 use crate::{
+    app_temp::db::LapceDb,
     editor::Editor,
+    start_screen::StartScreen,
     workspace_graph::{
-        WorkspaceGraph, feeder::typescript::feed_workspace_graph_with_ts_project,
+        WorkspaceGraph,
+        feeder::{FeederRegistry, FeederSettings},
+        index_workspace, update_workspace, watch_workspace,
     },
     workspace_layout::workspace_layout::WorkspaceLayout,
 };
 
+#[derive(Parser, Debug)]
+#[clap(name = "amas")]
+#[clap(version)]
+struct Cli {
+    /// Folder of the workspace to open. Without it, a start screen lets you
+    /// pick one.
+    #[clap(value_hint = clap::ValueHint::DirPath)]
+    path: Option<PathBuf>,
+}
+
 pub fn launch() {
+    let cli = Cli::parse();
+    let db = match LapceDb::new() {
+        Ok(db) => Some(db),
+        Err(e) => {
+            eprintln!("Failed to open the database: {}", e);
+            None
+        }
+    };
+
     Application::new()
-        .window(app_view, Some(WindowConfig::default()))
+        .window(
+            move |window_id| app_view(window_id, cli.path, db),
+            Some(WindowConfig::default()),
+        )
         .run();
 }
 
-fn app_view(window_id: WindowId) -> impl IntoView {
+fn app_view(
+    window_id: WindowId,
+    path: Option<PathBuf>,
+    db: Option<LapceDb>,
+) -> impl IntoView {
     let editor = Editor::new(window_id);
 
-    let mut graph = WorkspaceGraph::new();
-    feed_workspace_graph_with_ts_project(&mut graph, "/Users/arthur-fontaine/Developer/code/github.com/arthur-fontaine/mitosis-import-plugin").unwrap();
+    let path = path.filter(|path| {
+        let is_dir = path.is_dir();
+        if !is_dir {
+            eprintln!("{} is not a folder", path.display());
+        }
+        is_dir
+    });
+    let workspace_path = RwSignal::new(path);
 
-    let layout = WorkspaceLayout::new(graph, editor.clone());
+    dyn_container(
+        move || workspace_path.get(),
+        move |path| match path {
+            Some(path) => {
+                // Nodes are named after canonical paths, which file events must
+                // match
+                let path = path.canonicalize().unwrap_or(path);
+                if let Some(db) = &db {
+                    add_recent_workspace(db, &path);
+                }
+                workspace_view(editor.clone(), path).into_any()
+            }
+            None => StartScreen::new(db.as_ref(), workspace_path).into_any(),
+        },
+    )
+    .style(|s| s.size_full())
+}
+
+fn add_recent_workspace(db: &LapceDb, workspace_path: &Path) {
+    let workspace = LapceWorkspace {
+        kind: LapceWorkspaceType::Local,
+        path: Some(workspace_path.to_path_buf()),
+        last_open: 0,
+    };
+    if let Err(e) = db.update_recent_workspace(&workspace) {
+        eprintln!("Failed to save the recent workspace: {}", e);
+    }
+}
+
+/// Applies the changes of the workspace files to the graph on a background
+/// thread, one batch at a time.
+#[derive(Clone)]
+struct GraphUpdater {
+    scope: Scope,
+    registry: Arc<FeederRegistry>,
+    workspace_path: PathBuf,
+    workspace_graph: RwSignal<WorkspaceGraph>,
+    /// Files changed since the last update started, applied by the next one.
+    pending_files: RwSignal<Vec<PathBuf>>,
+    is_updating: RwSignal<bool>,
+}
+
+impl GraphUpdater {
+    fn queue(&self, file_paths: Vec<PathBuf>) {
+        self.pending_files.update(|files| files.extend(file_paths));
+    }
+
+    /// Updates the graph with the queued files. While an update runs, the
+    /// files are left queued and applied once it ends.
+    fn start(&self) {
+        if self.is_updating.get_untracked() {
+            return;
+        }
+        let file_paths = self.pending_files.with_untracked(|files| files.clone());
+        if file_paths.is_empty() {
+            return;
+        }
+        self.pending_files.set(Vec::new());
+        self.is_updating.set(true);
+
+        let on_updated = create_ext_action(self.scope, {
+            let updater = self.clone();
+            move |graph: WorkspaceGraph| {
+                updater.workspace_graph.set(graph);
+                updater.is_updating.set(false);
+                updater.start();
+            }
+        });
+        update_workspace(
+            self.registry.clone(),
+            self.workspace_path.clone(),
+            self.workspace_graph.get_untracked(),
+            file_paths,
+            on_updated,
+        );
+    }
+}
+
+fn workspace_view(editor: Editor, workspace_path: PathBuf) -> impl IntoView {
+    let registry = Arc::new(FeederRegistry::with_settings(&FeederSettings::load()));
+    let workspace_graph = RwSignal::new(WorkspaceGraph::new());
+    let layout = WorkspaceLayout::new(
+        workspace_graph,
+        editor.clone(),
+        workspace_path.clone(),
+    );
+
+    let updater = GraphUpdater {
+        scope: Scope::current(),
+        registry: registry.clone(),
+        workspace_path: workspace_path.clone(),
+        workspace_graph,
+        pending_files: RwSignal::new(Vec::new()),
+        is_updating: RwSignal::new(false),
+    };
+
+    let (progress_tx, progress_rx) = channel();
+    let progress = create_signal_from_channel(progress_rx);
+    create_effect({
+        let layout = layout.clone();
+        move |_| {
+            if let Some(progress) = progress.get() {
+                layout.set_indexing_progress(progress);
+            }
+        }
+    });
+
+    let on_indexed = create_ext_action(Scope::current(), {
+        let layout = layout.clone();
+        let updater = updater.clone();
+        move |graph: WorkspaceGraph| {
+            workspace_graph.set(graph);
+            layout.end_indexing();
+            // Apply the files changed while indexing
+            updater.start();
+        }
+    });
+    index_workspace(registry, workspace_path.clone(), progress_tx, on_indexed);
+
+    // Update the graph as the files of the workspace change
+    let changed_files = create_signal_from_channel(watch_workspace(&workspace_path));
+    create_effect({
+        let layout = layout.clone();
+        move |_| {
+            if let Some(file_paths) = changed_files.get() {
+                updater.queue(file_paths);
+                if !layout.is_indexing() {
+                    updater.start();
+                }
+            }
+        }
+    });
 
     dyn_container(
         {
@@ -77,7 +77,7 @@
     workspace::LapceWorkspace,
 };
 
-mod grammars;
+pub(crate) mod grammars;
 mod logging;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -225,7 +225,13 @@ fn app_view(
             let cur_window_tab = window_data.active.get_untracked();
             let (_, window_tab) =
                 &window_data.window_tabs.get_untracked()[cur_window_tab];
-            for file in files {
+            for (index, file) in files.into_iter().enumerate() {
+                // Every file after the first opens in a split of its own
+                let split = if index > 0 {
+                    split_active_editor_tab(window_tab)
+                } else {
+                    None
+                };
                 let position = file.linecol.map(|pos| {
                     EditorPosition::Position(lsp_types::Position {
                         line: pos.line.saturating_sub(1) as u32,
@@ -244,6 +250,15 @@ fn app_view(
                         same_editor_tab: false,
                     },
                 });
+                // The split starts with a copy of the editor it was split from
+                if let Some((editor_tab_id, child)) = split {
+                    window_tab.run_internal_command(
+                        InternalCommand::EditorTabChildClose {
+                            editor_tab_id,
+                            child,
+                        },
+                    );
+                }
             }
         }
 
@@ -328,6 +343,35 @@ fn app_view(
     }
 }
 
+/// Splits the active editor tab to the right and focuses the new one,
+/// returning it with the editor it was given, which copies the split one.
+fn split_active_editor_tab(
+    window_tab: &WindowTabData,
+) -> Option<(EditorTabId, EditorTabChild)> {
+    let main_split = &window_tab.main_split;
+    let editor_tab_id = main_split.active_editor_tab.get_untracked()?;
+    let editor_tabs = main_split.editor_tabs.get_untracked();
+    window_tab.run_internal_command(InternalCommand::Split {
+        direction: SplitDirection::Vertical,
+        editor_tab_id,
+    });
+    let (new_editor_tab_id, new_editor_tab) = main_split
+        .editor_tabs
+        .get_untracked()
+        .into_iter()
+        .find(|(editor_tab_id, _)| !editor_tabs.contains_key(editor_tab_id))?;
+    let child = new_editor_tab.with_untracked(|editor_tab| {
+        editor_tab
+            .children
+            .first()
+            .map(|(_, _, child)| child.clone())
+    })?;
+    window_tab.run_internal_command(InternalCommand::FocusEditorTab {
+        editor_tab_id: new_editor_tab_id,
+    });
+    Some((new_editor_tab_id, child))
+}
+
 fn editor_tab_content(
     window_tab_data: Rc<WindowTabData>,
     plugin: PluginData,
@@ -1618,7 +1662,7 @@ fn window(window_data: WindowData) -> impl View {
 
 pub fn into_view(
     window_id: WindowId,
-    path: &str,
+    paths: &[String],
     linecol: Option<LineCol>,
 ) -> impl IntoView {
     trace!(TraceLevel::INFO, "Starting up Amas..");
@@ -1700,11 +1744,14 @@ pub fn into_view(
     let app_view = app_data.into_view(
         window_id,
         db.clone(),
-        vec![PathObject {
-            path: std::path::PathBuf::from(path),
-            is_dir: false,
-            linecol,
-        }],
+        paths
+            .iter()
+            .map(|path| PathObject {
+                path: std::path::PathBuf::from(path),
+                is_dir: false,
+                linecol,
+            })
+            .collect(),
     );
 
     // Updates grammars and refreshes syntax highlighting if needed
@@ -1733,39 +1780,7 @@ pub fn into_view(
         std::thread::Builder::new()
             .name("FindGrammar".to_owned())
             .spawn(move || {
-                use self::grammars::*;
-                let updated = match find_grammar_release() {
-                    Ok(release) => {
-                        let mut updated = false;
-                        match fetch_grammars(&release) {
-                            Err(e) => {
-                                trace!(
-                                    TraceLevel::ERROR,
-                                    "failed to fetch grammars: {e}"
-                                );
-                            }
-                            Ok(u) => updated |= u,
-                        }
-                        match fetch_queries(&release) {
-                            Err(e) => {
-                                trace!(
-                                    TraceLevel::ERROR,
-                                    "failed to fetch grammars: {e}"
-                                );
-                            }
-                            Ok(u) => updated |= u,
-                        }
-                        updated
-                    }
-                    Err(e) => {
-                        trace!(
-                            TraceLevel::ERROR,
-                            "failed to obtain release info: {e}"
-                        );
-                        false
-                    }
-                };
-                send(updated);
+                send(grammars::update_grammars());
             })
             .unwrap();
     }
@@ -87,6 +87,43 @@ pub fn fetch_queries(release: &ReleaseInfo) -> Result<bool> {
     Ok(updated)
 }
 
+/// Downloads the grammars and queries of the release matching this version,
+/// returning whether they were updated.
+pub fn update_grammars() -> bool {
+    let release = match find_grammar_release() {
+        Ok(release) => release,
+        Err(e) => {
+            trace!(TraceLevel::ERROR, "failed to obtain release info: {e}");
+            return false;
+        }
+    };
+    let mut updated = false;
+    match fetch_grammars(&release) {
+        Err(e) => {
+            trace!(TraceLevel::ERROR, "failed to fetch grammars: {e}");
+        }
+        Ok(u) => updated |= u,
+    }
+    match fetch_queries(&release) {
+        Err(e) => {
+            trace!(TraceLevel::ERROR, "failed to fetch queries: {e}");
+        }
+        Ok(u) => updated |= u,
+    }
+    updated
+}
+
+/// Whether the grammars and queries were downloaded, by the editor or by
+/// [`update_grammars`].
+pub fn grammars_installed() -> bool {
+    [
+        Directory::grammars_directory(),
+        Directory::queries_directory(),
+    ]
+    .into_iter()
+    .all(|dir| dir.is_some_and(|dir| dir.join("version").is_file()))
+}
+
 fn download_release(
     dir: PathBuf,
     release: &ReleaseInfo,
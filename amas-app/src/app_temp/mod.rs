@@ -1,2 +1,2 @@
 pub mod app;
-mod db;
+pub(crate) mod db;
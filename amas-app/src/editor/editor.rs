@@ -1,34 +1,56 @@
-use floem::{prelude::{
-    create_rw_signal, RwSignal, SignalGet as _, SignalUpdate as _
-}, window::WindowId};
+use std::sync::{Arc, OnceLock};
+
+use floem::{
+    prelude::{RwSignal, SignalGet as _, SignalUpdate as _, create_rw_signal},
+    window::WindowId,
+};
+use lapce_rpc::proxy::ProxyRpcHandler;
+
+use super::proxy::FileProxy;
 
 #[derive(Debug, Clone)]
 pub struct Editor {
     opened_files: RwSignal<Vec<String>>,
     pub(super) window_id: WindowId,
+    /// The proxy the graph moves files through, shared by the workspaces of
+    /// the window. It is only started on the first use, and shut down along
+    /// with the window.
+    proxy: Arc<OnceLock<FileProxy>>,
 }
 
 impl Editor {
     pub fn new(window_id: WindowId) -> Self {
         let opened_files = create_rw_signal(Vec::new());
-        Self { opened_files, window_id }
+        Self {
+            opened_files,
+            window_id,
+            proxy: Arc::new(OnceLock::new()),
+        }
+    }
+
+    pub fn proxy(&self) -> &ProxyRpcHandler {
+        &self.proxy.get_or_init(FileProxy::start).proxy_rpc
     }
 
     pub fn open_file(&self, file_name: &str) {
-        // For now, we just support opening a single file. Maybe later it will be useful to support multiple opened files.
-        // opened_files is a vector in case we want to support multiple opened files in the future.
         self.opened_files.set(vec![file_name.to_string()]);
     }
 
-    pub fn close_file(&self, file_name: &str) {
-        let mut files = self.opened_files.get();
-        if let Some(pos) = files.iter().position(|f| f == file_name) {
-            files.remove(pos);
-            self.opened_files.set(files);
-        }
+    /// Opens `file_names` side by side, each in a split of its own.
+    pub fn open_files(&self, file_names: Vec<String>) {
+        self.opened_files.set(file_names);
+    }
+
+    /// Closes every opened file, back to the graph.
+    pub fn close_files(&self) {
+        self.opened_files.set(Vec::new());
     }
 
     pub fn get_opened_file(&self) -> Option<String> {
         self.opened_files.get().first().cloned()
     }
+
+    pub fn get_opened_files(&self) -> Vec<String> {
+        self.opened_files.get()
+    }
 }
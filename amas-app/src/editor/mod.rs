@@ -1,4 +1,5 @@
 mod editor;
+mod proxy;
 mod view;
 
 pub use editor::Editor;
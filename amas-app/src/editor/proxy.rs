@@ -0,0 +1,71 @@
+use std::fmt;
+
+use lapce_proxy::dispatch::Dispatcher;
+use lapce_rpc::RequestId;
+use lapce_rpc::core::{CoreHandler, CoreNotification, CoreRequest, CoreRpcHandler};
+use lapce_rpc::proxy::ProxyRpcHandler;
+
+/// A proxy running on threads of its own, along with the handler of the
+/// messages it sends back, to move the files of the workspace around while
+/// the graph is shown.
+pub(super) struct FileProxy {
+    pub(super) proxy_rpc: ProxyRpcHandler,
+    core_rpc: CoreRpcHandler,
+}
+
+impl FileProxy {
+    pub(super) fn start() -> Self {
+        let proxy_rpc = ProxyRpcHandler::new();
+        let core_rpc = CoreRpcHandler::new();
+        let spawned = std::thread::Builder::new()
+            .name("ProxyRpcHandler".to_owned())
+            .spawn({
+                let proxy_rpc = proxy_rpc.clone();
+                let core_rpc = core_rpc.clone();
+                move || {
+                    let mut dispatcher =
+                        Dispatcher::new(core_rpc, proxy_rpc.clone());
+                    proxy_rpc.mainloop(&mut dispatcher);
+                }
+            });
+        if let Err(e) = spawned {
+            eprintln!("Failed to start the proxy: {}", e);
+        }
+        // Nothing the proxy tells the editor matters here, but its messages
+        // must still be drained
+        let spawned = std::thread::Builder::new()
+            .name("CoreRpcHandler".to_owned())
+            .spawn({
+                let core_rpc = core_rpc.clone();
+                move || core_rpc.mainloop(&mut IgnoredCoreMessages)
+            });
+        if let Err(e) = spawned {
+            eprintln!("Failed to start the handler of the proxy messages: {}", e);
+        }
+        Self {
+            proxy_rpc,
+            core_rpc,
+        }
+    }
+}
+
+impl Drop for FileProxy {
+    fn drop(&mut self) {
+        self.proxy_rpc.shutdown();
+        self.core_rpc.shutdown();
+    }
+}
+
+impl fmt::Debug for FileProxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileProxy").finish_non_exhaustive()
+    }
+}
+
+struct IgnoredCoreMessages;
+
+impl CoreHandler for IgnoredCoreMessages {
+    fn handle_notification(&mut self, _rpc: CoreNotification) {}
+
+    fn handle_request(&mut self, _id: RequestId, _rpc: CoreRequest) {}
+}
@@ -16,15 +16,15 @@ fn into_view(self) -> Self::V {
         dyn_container(
             {
                 let editor = self.clone();
-                move || editor.get_opened_file()
+                move || editor.get_opened_files()
             },
             {
                 let editor = self.clone();
-                move |file_name| {
-                    if let Some(file_name) = file_name {
+                move |file_names| {
+                    if !file_names.is_empty() {
                         app_temp::app::into_view(
                             self.window_id,
-                            &file_name,
+                            &file_names,
                             Some(LineCol { line: 0, column: 0 }),
                         )
                         .style(|s| {
@@ -48,10 +48,28 @@ fn into_view(self) -> Self::V {
                             let editor = editor.clone();
                             move |event| {
                                 if let Event::PinchGesture(pinch_event) = event {
-                                    // When we zoomed at maximum zoom level, we can open the file we are hovering over
+                                    // Zooming out closes the files, back to the
+                                    // graph they were opened from
                                     if pinch_event.delta < 0.0 {
-                                        let editor = editor.clone();
-                                        editor.close_file(&file_name);
+                                        editor.close_files();
+                                    }
+                                }
+                                EventPropagation::Continue
+                            }
+                        })
+                        .on_event(EventListener::PointerWheel, {
+                            let editor = editor.clone();
+                            move |event| {
+                                if let Event::PointerWheel(pointer_wheel_event) =
+                                    event
+                                {
+                                    // Same as pinching out, with Ctrl held
+                                    let modifiers = pointer_wheel_event.modifiers;
+                                    if (modifiers.control() || modifiers.meta())
+                                        && pointer_wheel_event.delta.y > 0.0
+                                    {
+                                        editor.close_files();
+                                        return EventPropagation::Stop;
                                     }
                                 }
                                 EventPropagation::Continue
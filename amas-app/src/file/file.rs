@@ -1,10 +1,67 @@
+use super::Symbol;
+
 #[derive(Debug, Clone)]
 pub struct File {
     pub name: String,
+    /// Whether the node stands for a whole package outside of the workspace
+    /// (e.g. in `node_modules`) rather than a file of the workspace.
+    pub external: bool,
+    /// The exported symbol the node stands for, when it is a child of a
+    /// file rather than a file.
+    pub symbol: Option<Symbol>,
+    /// Whether the node stands for a collapsed directory of the workspace,
+    /// in place of the files under it.
+    pub directory: bool,
 }
 
 impl File {
     pub fn new(name: String) -> Self {
-        Self { name }
+        Self {
+            name,
+            external: false,
+            symbol: None,
+            directory: false,
+        }
+    }
+
+    pub fn new_external(name: String) -> Self {
+        Self {
+            name,
+            external: true,
+            symbol: None,
+            directory: false,
+        }
+    }
+
+    /// A node for `symbol`, named `<file>#<symbol>`.
+    pub fn new_symbol(symbol: Symbol) -> Self {
+        Self {
+            name: Self::symbol_node_name(&symbol.file, &symbol.name),
+            external: false,
+            symbol: Some(symbol),
+            directory: false,
+        }
+    }
+
+    /// A node for the collapsed directory `name`.
+    pub fn new_directory(name: String) -> Self {
+        Self {
+            name,
+            external: false,
+            symbol: None,
+            directory: true,
+        }
+    }
+
+    pub fn symbol_node_name(file_name: &str, symbol_name: &str) -> String {
+        format!("{}#{}", file_name, symbol_name)
+    }
+
+    /// Name of the file to open for this node.
+    pub fn file_name(&self) -> &str {
+        match &self.symbol {
+            Some(symbol) => &symbol.file,
+            None => &self.name,
+        }
     }
 }
@@ -1,2 +1,4 @@
 mod file;
+mod symbol;
 pub use file::File;
+pub use symbol::{Symbol, SymbolKind};
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SymbolKind {
+    Function,
+    Class,
+    Interface,
+    TypeAlias,
+    Enum,
+    Variable,
+}
+
+/// A symbol exported by a file, e.g. a function or a type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    /// Name of the file exporting the symbol.
+    pub file: String,
+    /// Exported name of the symbol, `default` for the default export.
+    pub name: String,
+    pub kind: SymbolKind,
+}
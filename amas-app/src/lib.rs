@@ -1,8 +1,13 @@
 pub mod app;
 
-pub(crate) mod workspace_graph;
+pub(crate) mod editor;
 pub(crate) mod file;
+pub(crate) mod settings;
+pub(crate) mod start_screen;
+pub(crate) mod workspace_graph;
 pub(crate) mod workspace_layout;
-pub(crate) mod editor;
 
 pub mod app_temp;
+
+// Exposed for the benchmarks
+pub use workspace_layout::force_layout;
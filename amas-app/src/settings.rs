@@ -0,0 +1,34 @@
+use lapce_app::config::LapceConfig;
+use serde::de::DeserializeOwned;
+
+/// Reads the `[amas.<table>]` table of the settings file of Lapce, which
+/// holds the preferences of the user, as opposed to the layout of a
+/// workspace that is shared with the team.
+///
+/// Returns `None` when the table is missing or cannot be read, leaving the
+/// caller to fall back to its defaults.
+pub fn read_settings<T: DeserializeOwned>(table: &str) -> Option<T> {
+    let path = LapceConfig::settings_file()?;
+    let settings = match std::fs::read_to_string(&path) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let settings: toml::Value = match toml::from_str(&settings) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let table = settings.get("amas")?.get(table)?.clone();
+    match table.try_into() {
+        Ok(table) => Some(table),
+        Err(e) => {
+            eprintln!("Failed to read the amas settings: {}", e);
+            None
+        }
+    }
+}
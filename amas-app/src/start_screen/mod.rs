@@ -0,0 +1,4 @@
+mod start_screen;
+mod view;
+
+pub use start_screen::StartScreen;
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use floem::{
+    action::open_file,
+    file::FileDialogOptions,
+    prelude::{RwSignal, SignalUpdate as _},
+};
+
+use crate::app_temp::db::LapceDb;
+
+/// The screen shown when amas is launched without a workspace, to pick one.
+#[derive(Clone)]
+pub struct StartScreen {
+    workspace_path: RwSignal<Option<PathBuf>>,
+    pub(super) recent_workspaces: Vec<PathBuf>,
+}
+
+impl StartScreen {
+    /// Picking a workspace sets `workspace_path`.
+    pub fn new(
+        db: Option<&LapceDb>,
+        workspace_path: RwSignal<Option<PathBuf>>,
+    ) -> Self {
+        let recent_workspaces = db
+            .and_then(|db| db.recent_workspaces().ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|workspace| workspace.kind.is_local())
+            .filter_map(|workspace| workspace.path)
+            .filter(|path| path.is_dir())
+            .collect();
+        Self {
+            workspace_path,
+            recent_workspaces,
+        }
+    }
+
+    pub fn open_workspace(&self, path: PathBuf) {
+        self.workspace_path.set(Some(path));
+    }
+
+    /// Asks for a folder to open as the workspace.
+    pub fn pick_workspace(&self) {
+        let start_screen = self.clone();
+        let options = FileDialogOptions::new()
+            .title("Choose a folder")
+            .select_directories();
+        open_file(options, move |file| {
+            if let Some(path) = file.and_then(|mut file| file.path.pop()) {
+                start_screen.open_workspace(path);
+            }
+        });
+    }
+}
@@ -0,0 +1,73 @@
+use super::start_screen::StartScreen;
+use floem::{
+    AnyView, IntoView,
+    prelude::palette::css,
+    style::CursorStyle,
+    views::{Decorators as _, label, stack, stack_from_iter},
+};
+
+fn recent_workspaces(start_screen: &StartScreen) -> impl IntoView {
+    let recent_workspaces = start_screen.recent_workspaces.clone();
+    let is_empty = recent_workspaces.is_empty();
+    stack((
+        label(|| "Recent").style(|s| s.color(css::GRAY)),
+        stack_from_iter(recent_workspaces.into_iter().map(|path| {
+            let start_screen = start_screen.clone();
+            let name = path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .to_string();
+            let full_path = path.to_string_lossy().to_string();
+            stack((
+                label(move || name.clone()),
+                label(move || full_path.clone())
+                    .style(|s| s.color(css::DIM_GRAY).font_size(11.0)),
+            ))
+            .on_click_stop(move |_| start_screen.open_workspace(path.clone()))
+            .style(|s| {
+                s.flex_col()
+                    .padding(6.0)
+                    .border_radius(4.0)
+                    .cursor(CursorStyle::Pointer)
+                    .hover(|s| s.background(css::WHITE.with_alpha(0.1)))
+            })
+        }))
+        .style(|s| s.flex_col().gap(2.0)),
+    ))
+    .style(move |s| s.flex_col().gap(6.0).apply_if(is_empty, |s| s.hide()))
+}
+
+impl IntoView for StartScreen {
+    type V = AnyView;
+
+    fn into_view(self) -> Self::V {
+        let start_screen = self.clone();
+
+        stack((
+            label(|| "amas").style(|s| s.font_size(32.0)),
+            label(|| "Open Folder...")
+                .on_click_stop({
+                    let start_screen = start_screen.clone();
+                    move |_| start_screen.pick_workspace()
+                })
+                .style(|s| {
+                    s.padding_vert(6.0)
+                        .padding_horiz(12.0)
+                        .border_radius(4.0)
+                        .color(css::WHITE)
+                        .background(css::CORNFLOWER_BLUE)
+                        .cursor(CursorStyle::Pointer)
+                }),
+            recent_workspaces(&start_screen),
+        ))
+        .style(|s| {
+            s.size_full()
+                .flex_col()
+                .items_center()
+                .justify_center()
+                .gap(16.0)
+        })
+        .into_any()
+    }
+}
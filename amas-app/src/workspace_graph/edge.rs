@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// How a file depends on another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EdgeKind {
+    /// `import x from "./a"`, `use crate::a`, `import a`
+    StaticImport,
+    /// `import type { A } from "./a"`, erased at runtime.
+    TypeImport,
+    /// `import("./a")`
+    DynamicImport,
+    /// `require("./a")`
+    Require,
+    /// `export * from "./a"`, `pub use crate::a`
+    ReExport,
+    /// `mod a;`
+    ModuleDeclaration,
+}
+
+impl EdgeKind {
+    pub const ALL: [EdgeKind; 6] = [
+        EdgeKind::StaticImport,
+        EdgeKind::TypeImport,
+        EdgeKind::DynamicImport,
+        EdgeKind::Require,
+        EdgeKind::ReExport,
+        EdgeKind::ModuleDeclaration,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EdgeKind::StaticImport => "import",
+            EdgeKind::TypeImport => "type import",
+            EdgeKind::DynamicImport => "dynamic import",
+            EdgeKind::Require => "require",
+            EdgeKind::ReExport => "re-export",
+            EdgeKind::ModuleDeclaration => "mod",
+        }
+    }
+}
+
+/// A dependency from the source file of the edge to its target file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    /// Number of import statements of this kind between the two files.
+    pub weight: f64,
+}
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lapce_core::directory::Directory;
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+/// Bumped whenever the format of the cached results changes.
+const CACHE_VERSION: u32 = 2;
+
+pub(super) type ParseError = Box<dyn std::error::Error + Send + Sync>;
+
+/// An on-disk cache of the result of parsing each file of a workspace, keyed
+/// by the hash of the file content and extension, so that unchanged files are
+/// not parsed again on the next start.
+pub(super) struct ParseCache {
+    directory: Option<PathBuf>,
+    /// The entries read or written so far, which are kept when pruning.
+    touched: Mutex<HashSet<PathBuf>>,
+}
+
+impl ParseCache {
+    /// `namespace` separates the results of different parsers, or of a
+    /// parser run with different options.
+    pub(super) fn new(workspace_path: &Path, namespace: &str) -> Self {
+        let workspace_hash = format!(
+            "{:x}",
+            Sha256::digest(workspace_path.to_string_lossy().as_bytes())
+        );
+        let directory = Directory::cache_directory().map(|dir| {
+            dir.join("amas")
+                .join(format!("v{}", CACHE_VERSION))
+                .join(&workspace_hash[..16])
+                .join(namespace)
+        });
+        Self {
+            directory,
+            touched: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// A cache that keeps nothing, so that tests do not write to the cache
+    /// directory of the user.
+    #[cfg(test)]
+    pub(super) fn disabled() -> Self {
+        Self {
+            directory: None,
+            touched: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Returns the cached result for the current content of `file_path`, or
+    /// parses the content with `parse` and caches the result.
+    pub(super) fn get_or_parse<T: Serialize + DeserializeOwned>(
+        &self,
+        file_path: &Path,
+        parse: impl FnOnce(&str) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        let source_code = fs::read_to_string(file_path)?;
+        let Some(directory) = &self.directory else {
+            return parse(&source_code);
+        };
+
+        let hash = entry_hash(file_path, &source_code);
+        let entry_path = directory.join(&hash[..2]).join(&hash[2..]);
+        if let Ok(mut touched) = self.touched.lock() {
+            touched.insert(entry_path.clone());
+        }
+        if let Some(cached) = fs::read(&entry_path)
+            .ok()
+            .and_then(|entry| serde_json::from_slice(&entry).ok())
+        {
+            return Ok(cached);
+        }
+
+        let parsed = parse(&source_code)?;
+        if let Err(e) = write_entry(&entry_path, &parsed) {
+            eprintln!("Failed to cache {}: {}", file_path.display(), e);
+        }
+        Ok(parsed)
+    }
+
+    /// Removes the entries that were not read or written, once every file of
+    /// the workspace went through the cache.
+    pub(super) fn prune(&self) {
+        let Some(directory) = &self.directory else {
+            return;
+        };
+        let Ok(touched) = self.touched.lock() else {
+            return;
+        };
+        let stale_entries = WalkDir::new(directory)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .filter(|entry_path| !touched.contains(entry_path));
+        for entry_path in stale_entries {
+            if let Err(e) = fs::remove_file(&entry_path) {
+                eprintln!("Failed to prune {}: {}", entry_path.display(), e);
+            }
+        }
+    }
+}
+
+/// Hashes the content of the file along with its extension, which decides
+/// how it is parsed, and the version of amas, which decides the parsers.
+fn entry_hash(file_path: &Path, source_code: &str) -> String {
+    let extension = file_path
+        .extension()
+        .map(|extension| extension.to_string_lossy())
+        .unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.update([0u8]);
+    hasher.update(extension.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(source_code.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn write_entry(
+    entry_path: &Path,
+    parsed: &impl Serialize,
+) -> Result<(), ParseError> {
+    if let Some(parent) = entry_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(entry_path, serde_json::to_vec(parsed)?)?;
+    Ok(())
+}
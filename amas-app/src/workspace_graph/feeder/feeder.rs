@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::workspace_graph::{Progress, WorkspaceGraph};
+
+/// Directories that never contain source files worth showing in the graph.
+const IGNORED_DIRECTORIES: &[&str] = &[
+    "node_modules",
+    ".git",
+    "dist",
+    "build",
+    "coverage",
+    "target",
+    "__pycache__",
+    ".venv",
+    "venv",
+    // The layout saved by amas
+    ".amas",
+];
+
+/// A language plug-in filling a [`WorkspaceGraph`] with the files it handles
+/// and the imports between them.
+///
+/// Feeders run on a background thread while the workspace is indexed.
+pub trait Feeder: Send + Sync {
+    /// Name of the feeder, used when reporting errors.
+    fn name(&self) -> &'static str;
+
+    /// Whether `file_path` is a file this feeder adds to the graph.
+    fn handles(&self, file_path: &Path) -> bool;
+
+    /// Whether this feeder applies to the workspace at `workspace_path`.
+    ///
+    /// By default, a feeder applies as soon as the workspace contains a file
+    /// it handles.
+    fn detect(&self, workspace_path: &Path) -> bool {
+        workspace_files(workspace_path).any(|file_path| self.handles(&file_path))
+    }
+
+    /// Whether this feeder parses files with the tree-sitter grammars of the
+    /// editor, which must be downloaded before the workspace is indexed.
+    fn uses_grammars(&self) -> bool {
+        false
+    }
+
+    /// Adds the handled files of the workspace and their imports to `graph`,
+    /// counting the parsed files in `progress`.
+    fn feed(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        progress: &Progress,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// File names of the manifests the feeder reads the projects of the
+    /// workspace from, such as `Cargo.toml`. The workspace is fed again when
+    /// one of them changes.
+    fn manifests(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Updates `graph` after the handled `file_paths` were created, modified
+    /// or removed.
+    ///
+    /// By default, the whole workspace is fed again, for languages whose
+    /// imports cannot be resolved one file at a time.
+    fn update(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        file_paths: &[PathBuf],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        feed_again(self, graph, workspace_path, file_paths)
+    }
+}
+
+/// Drops the removed `file_paths` from `graph` and feeds the whole workspace
+/// again with `feeder`, replacing the imports of every file it handles.
+pub(super) fn feed_again(
+    feeder: &(impl Feeder + ?Sized),
+    graph: &mut WorkspaceGraph,
+    workspace_path: &Path,
+    file_paths: &[PathBuf],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for file_path in file_paths {
+        if !file_path.exists() {
+            graph.remove_file(&file_path.to_string_lossy());
+        }
+    }
+    let handled_nodes: Vec<_> = graph
+        .graph
+        .node_indices()
+        .filter(|node_idx| feeder.handles(Path::new(&graph.graph[*node_idx].name)))
+        .collect();
+    for node_idx in handled_nodes {
+        graph.remove_imports_from(node_idx);
+    }
+    feeder.feed(graph, workspace_path, &Progress::default())
+}
+
+/// Whether `path` goes through one of the [`IGNORED_DIRECTORIES`].
+pub fn is_ignored(path: &Path) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .map(|name| IGNORED_DIRECTORIES.contains(&name))
+            .unwrap_or(false)
+    })
+}
+
+/// Walks every file of the workspace, skipping [`IGNORED_DIRECTORIES`].
+pub fn workspace_files(workspace_path: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkDir::new(workspace_path)
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.depth() > 0
+                && entry.file_type().is_dir()
+                && entry
+                    .file_name()
+                    .to_str()
+                    .map(|name| IGNORED_DIRECTORIES.contains(&name))
+                    .unwrap_or(false))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.path().to_path_buf())
+}
@@ -1 +1,16 @@
+mod cache;
+mod feeder;
+mod packages;
+mod projects;
+mod registry;
+#[cfg(test)]
+mod test_utils;
+mod tsconfig;
+mod unresolved;
+
+pub mod python;
+pub mod rust;
 pub mod typescript;
+
+pub use feeder::{Feeder, is_ignored, workspace_files};
+pub use registry::{FeederRegistry, FeederSettings};
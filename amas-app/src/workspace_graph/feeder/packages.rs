@@ -0,0 +1,390 @@
+use super::typescript::resolve_module_file;
+use super::{is_ignored, workspace_files};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Conditions of `exports` to try, most source-like first.
+const EXPORT_CONDITIONS: &[&str] = &[
+    "source",
+    "development",
+    "types",
+    "import",
+    "module",
+    "default",
+    "require",
+    "node",
+    "browser",
+];
+
+/// `package.json` fields pointing to the entry point of a package, most
+/// source-like first.
+const ENTRY_FIELDS: &[&str] = &["source", "types", "typings", "module", "main"];
+
+/// Directories build outputs are usually written to, next to a `src/`.
+const BUILD_DIRECTORIES: &[&str] = &["dist", "build", "out", "lib"];
+
+/// A package of a pnpm/yarn/npm workspace.
+#[derive(Debug)]
+struct WorkspacePackage {
+    name: String,
+    dir: PathBuf,
+    manifest: Value,
+}
+
+/// The packages declared by the `workspaces` field of the root
+/// `package.json`, or by `pnpm-workspace.yaml`.
+#[derive(Debug, Default)]
+pub(super) struct WorkspacePackages {
+    packages: Vec<WorkspacePackage>,
+}
+
+impl WorkspacePackages {
+    pub(super) fn discover(workspace_path: &Path) -> Self {
+        let root_manifest = read_manifest(&workspace_path.join("package.json"));
+        let mut patterns = root_manifest
+            .as_ref()
+            .map(workspaces_patterns)
+            .unwrap_or_default();
+        if let Ok(pnpm_workspace) =
+            fs::read_to_string(workspace_path.join("pnpm-workspace.yaml"))
+        {
+            patterns.extend(pnpm_workspace_patterns(&pnpm_workspace));
+        }
+
+        let mut packages = Vec::new();
+        // The root package can import itself by name
+        if let Some(root_manifest) = root_manifest {
+            packages.extend(WorkspacePackage::new(workspace_path, root_manifest));
+        }
+        if !patterns.is_empty() {
+            let (includes, excludes) = build_glob_sets(&patterns);
+            for manifest_path in workspace_files(workspace_path).filter(|path| {
+                path.file_name().and_then(|s| s.to_str()) == Some("package.json")
+            }) {
+                let Some(dir) = manifest_path.parent() else {
+                    continue;
+                };
+                let Ok(relative_dir) = dir.strip_prefix(workspace_path) else {
+                    continue;
+                };
+                if relative_dir.as_os_str().is_empty()
+                    || !includes.is_match(relative_dir)
+                    || excludes.is_match(relative_dir)
+                {
+                    continue;
+                }
+                if let Some(manifest) = read_manifest(&manifest_path) {
+                    packages.extend(WorkspacePackage::new(dir, manifest));
+                }
+            }
+        }
+
+        Self { packages }
+    }
+
+    /// Resolves a bare specifier (`@acme/ui`, `@acme/ui/button`) importing a
+    /// package of the workspace to its source file.
+    pub(super) fn resolve(&self, specifier: &str) -> Option<PathBuf> {
+        let (package_name, subpath) = split_specifier(specifier)?;
+        let package = self
+            .packages
+            .iter()
+            .find(|package| package.name == package_name)?;
+        package.resolve(&subpath)
+    }
+}
+
+impl WorkspacePackage {
+    fn new(dir: &Path, manifest: Value) -> Option<Self> {
+        let name = manifest.get("name")?.as_str()?.to_string();
+        Some(Self {
+            name,
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Resolves `subpath` (`.` or `./button`) of the package.
+    fn resolve(&self, subpath: &str) -> Option<PathBuf> {
+        if let Some(exports) = self.manifest.get("exports") {
+            let mut targets = Vec::new();
+            collect_export_targets(exports, subpath, &mut targets);
+            if let Some(resolved) = targets
+                .iter()
+                .find_map(|target| self.resolve_target(target))
+            {
+                return Some(resolved);
+            }
+        }
+
+        if subpath == "." {
+            ENTRY_FIELDS
+                .iter()
+                .filter_map(|field| self.manifest.get(field)?.as_str())
+                .chain(["./src/index", "./index"])
+                .find_map(|target| self.resolve_target(target))
+        } else {
+            self.resolve_target(subpath)
+        }
+    }
+
+    /// Resolves a path of the package to a source file of the workspace.
+    ///
+    /// Packages often point to their build output (`./dist/index.js`), which
+    /// is not part of the graph, so such targets are mapped back to `src/`.
+    fn resolve_target(&self, target: &str) -> Option<PathBuf> {
+        let target = target.trim_start_matches("./");
+        let target = strip_declaration_extension(target);
+
+        let mut candidates = vec![self.dir.join(target)];
+        if let Some((_, rest)) = target
+            .split_once('/')
+            .filter(|(first, _)| BUILD_DIRECTORIES.contains(first))
+        {
+            candidates.push(self.dir.join("src").join(rest));
+        }
+
+        candidates
+            .into_iter()
+            .filter_map(|candidate| resolve_module_file(&candidate))
+            .find(|resolved| {
+                resolved
+                    .strip_prefix(&self.dir)
+                    .map(|relative| !is_ignored(relative))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// Collects the targets `exports` maps `subpath` to, in preference order.
+fn collect_export_targets(
+    exports: &Value,
+    subpath: &str,
+    targets: &mut Vec<String>,
+) {
+    match exports {
+        // `"exports": "./index.js"` only exports the root of the package
+        Value::String(_) | Value::Array(_) if subpath == "." => {
+            collect_condition_targets(exports, "", targets);
+        }
+        Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => {
+            if let Some(value) = map.get(subpath) {
+                collect_condition_targets(value, "", targets);
+                return;
+            }
+            // Subpath patterns, e.g. `"./utils/*": "./src/utils/*.ts"`
+            let mut patterns: Vec<(&String, &str, &Value)> = map
+                .iter()
+                .filter_map(|(key, value)| {
+                    let (prefix, suffix) = key.split_once('*')?;
+                    if subpath.len() < prefix.len() + suffix.len() {
+                        return None;
+                    }
+                    let wildcard =
+                        subpath.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                    Some((key, wildcard, value))
+                })
+                .collect();
+            patterns.sort_by_key(|(key, _, _)| std::cmp::Reverse(key.len()));
+            for (_, wildcard, value) in patterns {
+                collect_condition_targets(value, wildcard, targets);
+            }
+        }
+        // Conditions of the root export, e.g. `{ "import": ..., "require": ... }`
+        Value::Object(_) if subpath == "." => {
+            collect_condition_targets(exports, "", targets);
+        }
+        _ => {}
+    }
+}
+
+fn collect_condition_targets(
+    value: &Value,
+    wildcard: &str,
+    targets: &mut Vec<String>,
+) {
+    match value {
+        Value::String(target) => targets.push(target.replace('*', wildcard)),
+        Value::Array(values) => {
+            for value in values {
+                collect_condition_targets(value, wildcard, targets);
+            }
+        }
+        Value::Object(conditions) => {
+            for condition in EXPORT_CONDITIONS {
+                if let Some(value) = conditions.get(*condition) {
+                    collect_condition_targets(value, wildcard, targets);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `types` fields point to declaration files (`index.d.ts`), whose source is
+/// the module with the same stem.
+fn strip_declaration_extension(target: &str) -> &str {
+    [".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .find_map(|extension| target.strip_suffix(extension))
+        .unwrap_or(target)
+}
+
+/// Splits a bare specifier into the package name and the subpath imported
+/// from it (`.` for the package root).
+pub(super) fn split_specifier(specifier: &str) -> Option<(String, String)> {
+    if specifier.starts_with('.') || specifier.starts_with('/') {
+        return None;
+    }
+    let mut parts = specifier.splitn(3, '/');
+    let first = parts.next()?;
+    let (name, rest) = if first.starts_with('@') {
+        let scope_name = parts.next()?;
+        (format!("{first}/{scope_name}"), parts.next())
+    } else {
+        let rest = specifier.split_once('/').map(|(_, rest)| rest);
+        (first.to_string(), rest)
+    };
+    let subpath = match rest {
+        Some(rest) if !rest.is_empty() => format!("./{rest}"),
+        _ => ".".to_string(),
+    };
+    Some((name, subpath))
+}
+
+fn read_manifest(manifest_path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(manifest_path).ok()?).ok()
+}
+
+/// Reads the `workspaces` field of a `package.json`, either a list of
+/// patterns (npm, yarn) or an object with a `packages` list (yarn classic).
+fn workspaces_patterns(manifest: &Value) -> Vec<String> {
+    let workspaces = match manifest.get("workspaces") {
+        Some(Value::Object(workspaces)) => workspaces.get("packages"),
+        workspaces => workspaces,
+    };
+    workspaces
+        .and_then(Value::as_array)
+        .map(|patterns| {
+            patterns
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the `packages` list of a `pnpm-workspace.yaml`.
+fn pnpm_workspace_patterns(content: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if let Some(pattern) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+            let pattern = pattern.split(" #").next().unwrap_or("").trim();
+            patterns
+                .push(pattern.trim_matches(|c| c == '"' || c == '\'').to_string());
+        }
+    }
+    patterns
+}
+
+/// Builds the globs of included and excluded (`!`-prefixed) patterns.
+fn build_glob_sets(patterns: &[String]) -> (GlobSet, GlobSet) {
+    let mut includes = GlobSetBuilder::new();
+    let mut excludes = GlobSetBuilder::new();
+    for pattern in patterns {
+        let (builder, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (&mut excludes, pattern),
+            None => (&mut includes, pattern.as_str()),
+        };
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => eprintln!("Invalid workspace pattern {}: {}", pattern, e),
+        }
+    }
+    (
+        includes.build().unwrap_or_else(|_| GlobSet::empty()),
+        excludes.build().unwrap_or_else(|_| GlobSet::empty()),
+    )
+}
+
+/// Whether `package_name` is installed in a `node_modules` directory above
+/// `dir`, which tells external packages apart from Node.js builtins.
+pub(super) fn is_installed_package(dir: &Path, package_name: &str) -> bool {
+    dir.ancestors()
+        .any(|dir| dir.join("node_modules").join(package_name).is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace_graph::feeder::test_utils::write;
+
+    #[test]
+    fn test_split_specifier() {
+        assert_eq!(
+            split_specifier("@acme/ui/button"),
+            Some(("@acme/ui".to_string(), "./button".to_string()))
+        );
+        assert_eq!(
+            split_specifier("react"),
+            Some(("react".to_string(), ".".to_string()))
+        );
+        assert_eq!(split_specifier("./button"), None);
+    }
+
+    #[test]
+    fn test_pnpm_workspace_patterns() {
+        let content = "packages:\n  - 'packages/*'\n  - \"apps/**\" # apps\n  - '!**/test/**'\ncatalog:\n  - nope\n";
+        assert_eq!(
+            pnpm_workspace_patterns(content),
+            vec!["packages/*", "apps/**", "!**/test/**"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_workspace_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "package.json", r#"{ "workspaces": ["packages/*"] }"#);
+        write(
+            root,
+            "packages/ui/package.json",
+            r#"{
+                "name": "@acme/ui",
+                "exports": {
+                    ".": { "types": "./dist/index.d.ts", "import": "./dist/index.js" },
+                    "./components/*": "./src/components/*.tsx"
+                }
+            }"#,
+        );
+        let ui_index = write(root, "packages/ui/src/index.ts", "");
+        let button = write(root, "packages/ui/src/components/button.tsx", "");
+        write(
+            root,
+            "packages/utils/package.json",
+            r#"{ "name": "utils", "main": "lib/index.js" }"#,
+        );
+        let utils_index = write(root, "packages/utils/lib/index.js", "");
+
+        let packages = WorkspacePackages::discover(root);
+        assert_eq!(packages.resolve("@acme/ui"), Some(ui_index));
+        assert_eq!(packages.resolve("@acme/ui/components/button"), Some(button));
+        assert_eq!(packages.resolve("utils"), Some(utils_index));
+        assert_eq!(packages.resolve("react"), None);
+    }
+}
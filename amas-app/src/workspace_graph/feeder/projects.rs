@@ -0,0 +1,37 @@
+use std::sync::{Arc, Mutex};
+
+/// What a feeder reads from the manifests of the workspace, such as its
+/// crates or packages, read once when the workspace is fed and kept for the
+/// updates that follow.
+pub(super) struct Projects<T> {
+    read: Mutex<Option<Arc<T>>>,
+}
+
+impl<T> Projects<T> {
+    /// Reads the projects again with `read`, when the workspace is fed.
+    pub(super) fn read(&self, read: impl FnOnce() -> T) -> Arc<T> {
+        let projects = Arc::new(read());
+        if let Ok(mut kept) = self.read.lock() {
+            *kept = Some(projects.clone());
+        }
+        projects
+    }
+
+    /// The projects read when the workspace was last fed, or read with `read`
+    /// if it was not fed yet.
+    pub(super) fn get(&self, read: impl FnOnce() -> T) -> Arc<T> {
+        let kept = self.read.lock().ok().and_then(|kept| kept.clone());
+        match kept {
+            Some(projects) => projects,
+            None => self.read(read),
+        }
+    }
+}
+
+impl<T> Default for Projects<T> {
+    fn default() -> Self {
+        Self {
+            read: Mutex::new(None),
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use super::cache::{ParseCache, ParseError};
+use super::projects::Projects;
+use super::unresolved::UnresolvedImports;
+use super::{Feeder, workspace_files};
+use crate::file::File;
+use crate::workspace_graph::{EdgeKind, Progress, WorkspaceGraph};
+use lapce_core::syntax::Syntax;
+use lapce_xi_rope::Rope;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tree_sitter::Node;
+
+/// Files marking the directory of a Python project.
+const PROJECT_MANIFESTS: &[&str] = &["pyproject.toml", "setup.py", "setup.cfg"];
+
+/// An `import a.b` or `from ..a import b, c` statement.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PythonImport {
+    /// Number of leading dots of a relative import, 0 for absolute imports.
+    level: usize,
+    module: Vec<String>,
+    /// Names imported by a `from` import, empty for `import a.b` and
+    /// wildcard imports.
+    names: Vec<String>,
+}
+
+fn node_text(node: Node, source: &str) -> String {
+    node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
+}
+
+fn dotted_name(node: Node, source: &str) -> Vec<String> {
+    // `import a.b as c` imports `a.b`
+    let node = if node.kind() == "aliased_import" {
+        match node.child_by_field_name("name") {
+            Some(name) => name,
+            None => return Vec::new(),
+        }
+    } else {
+        node
+    };
+    node_text(node, source)
+        .split('.')
+        .map(|part| part.trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn collect_imports(node: Node, source: &str, imports: &mut Vec<PythonImport>) {
+    match node.kind() {
+        "import_statement" => {
+            let mut cursor = node.walk();
+            for name in node.children_by_field_name("name", &mut cursor) {
+                imports.push(PythonImport {
+                    level: 0,
+                    module: dotted_name(name, source),
+                    names: Vec::new(),
+                });
+            }
+        }
+        "import_from_statement" => {
+            let Some(module_name) = node.child_by_field_name("module_name") else {
+                return;
+            };
+            let (level, module) = if module_name.kind() == "relative_import" {
+                let text = node_text(module_name, source);
+                let level = text.chars().take_while(|c| *c == '.').count();
+                let module = text[level..]
+                    .split('.')
+                    .map(|part| part.trim().to_string())
+                    .filter(|part| !part.is_empty())
+                    .collect();
+                (level, module)
+            } else {
+                (0, dotted_name(module_name, source))
+            };
+
+            let mut cursor = node.walk();
+            let names = node
+                .children_by_field_name("name", &mut cursor)
+                .map(|name| dotted_name(name, source).join("."))
+                .collect();
+            imports.push(PythonImport {
+                level,
+                module,
+                names,
+            });
+        }
+        _ => {
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                collect_imports(child, source, imports);
+            }
+        }
+    }
+}
+
+fn parse_python_file(
+    file_path: &Path,
+    source_code: &str,
+) -> Result<Vec<PythonImport>, ParseError> {
+    // Reuse the tree-sitter grammar loaded by the editor
+    let mut syntax = Syntax::init(file_path);
+    syntax.parse(0, Rope::from(source_code), None);
+    let tree = syntax
+        .layers
+        .as_ref()
+        .and_then(|layers| layers.try_tree())
+        .ok_or("the Python tree-sitter grammar is not available")?;
+
+    let mut imports = Vec::new();
+    collect_imports(tree.root_node(), source_code, &mut imports);
+    Ok(imports)
+}
+
+/// Parses `file_paths` in parallel, reusing the results cached for the files
+/// that did not change.
+fn parse_python_files(
+    file_paths: &[PathBuf],
+    cache: &ParseCache,
+    progress: &Progress,
+) -> Vec<(PathBuf, Vec<PythonImport>)> {
+    progress.add_files(file_paths.len());
+    file_paths
+        .par_iter()
+        .filter_map(|file_path| {
+            let imports = cache.get_or_parse(file_path, |source_code| {
+                parse_python_file(file_path, source_code)
+            });
+            progress.file_indexed();
+            match imports {
+                Ok(imports) => Some((file_path.clone(), imports)),
+                Err(e) => {
+                    eprintln!("Failed to parse {}: {}", file_path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn is_python_file(file_path: &Path) -> bool {
+    file_path.extension().and_then(|s| s.to_str()) == Some("py")
+}
+
+/// Directories absolute imports are resolved from: the workspace itself, the
+/// directories of Python projects inside it, and their `src/` layouts.
+fn find_source_roots(workspace_path: &Path) -> Vec<PathBuf> {
+    let mut project_dirs = vec![workspace_path.to_path_buf()];
+    project_dirs.extend(
+        workspace_files(workspace_path)
+            .filter(|file_path| {
+                file_path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|name| PROJECT_MANIFESTS.contains(&name))
+            })
+            .filter_map(|file_path| file_path.parent().map(Path::to_path_buf)),
+    );
+
+    let mut source_roots = Vec::new();
+    for project_dir in project_dirs {
+        for source_root in [project_dir.join("src"), project_dir] {
+            if source_root.is_dir() && !source_roots.contains(&source_root) {
+                source_roots.push(source_root);
+            }
+        }
+    }
+    source_roots
+}
+
+/// Finds the file of module `parts` under `base`: either a module file, or
+/// the `__init__.py` of a regular package.
+///
+/// Namespace packages have no file of their own, but their submodules are
+/// still found since every directory is looked up.
+fn find_module_file(base: &Path, parts: &[String]) -> Option<PathBuf> {
+    let (last, parents) = parts.split_last()?;
+    let mut dir = base.to_path_buf();
+    dir.extend(parents);
+
+    [
+        dir.join(format!("{last}.py")),
+        dir.join(last).join("__init__.py"),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+/// Resolves an import to the workspace files it loads. Imports of modules
+/// outside of the workspace resolve to nothing.
+fn resolve_import(
+    import: &PythonImport,
+    file_path: &Path,
+    source_roots: &[PathBuf],
+) -> Vec<PathBuf> {
+    let bases = if import.level > 0 {
+        // `from . import x` is relative to the package of the file
+        let mut base = file_path.parent();
+        for _ in 1..import.level {
+            base = base.and_then(Path::parent);
+        }
+        match base {
+            Some(base) => vec![base.to_path_buf()],
+            None => return Vec::new(),
+        }
+    } else {
+        source_roots.to_vec()
+    };
+
+    for base in bases {
+        // A `from` import names either submodules or symbols of the module
+        let mut resolved: Vec<PathBuf> = import
+            .names
+            .iter()
+            .filter_map(|name| {
+                let mut parts = import.module.clone();
+                parts.extend(name.split('.').map(str::to_string));
+                find_module_file(&base, &parts)
+            })
+            .collect();
+        if resolved.len() < import.names.len() || import.names.is_empty() {
+            let module_file = if import.module.is_empty() {
+                Some(base.join("__init__.py")).filter(|path| path.is_file())
+            } else {
+                find_module_file(&base, &import.module)
+            };
+            resolved.extend(module_file);
+        }
+
+        if !resolved.is_empty() {
+            return resolved;
+        }
+    }
+
+    Vec::new()
+}
+
+#[derive(Default)]
+pub struct PythonFeeder {
+    /// Canonical directories absolute imports are resolved from.
+    source_roots: Projects<Vec<PathBuf>>,
+    unresolved: UnresolvedImports,
+}
+
+impl Feeder for PythonFeeder {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn handles(&self, file_path: &Path) -> bool {
+        is_python_file(file_path)
+    }
+
+    fn uses_grammars(&self) -> bool {
+        true
+    }
+
+    fn manifests(&self) -> &'static [&'static str] {
+        PROJECT_MANIFESTS
+    }
+
+    fn feed(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        progress: &Progress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let source_roots = self
+            .source_roots
+            .read(|| canonical_source_roots(workspace_path));
+        feed_workspace_graph_with_python_project(
+            graph,
+            workspace_path,
+            &source_roots,
+            &self.unresolved,
+            progress,
+        )
+    }
+
+    fn update(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        file_paths: &[PathBuf],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let source_roots = self
+            .source_roots
+            .get(|| canonical_source_roots(workspace_path));
+        update_workspace_graph_with_python_files(
+            graph,
+            workspace_path,
+            &source_roots,
+            &self.unresolved,
+            file_paths,
+        )
+    }
+}
+
+fn feed_workspace_graph_with_python_project(
+    graph: &mut WorkspaceGraph,
+    project_path: &Path,
+    source_roots: &[PathBuf],
+    unresolved: &UnresolvedImports,
+    progress: &Progress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let python_files: Vec<PathBuf> = workspace_files(project_path)
+        .filter(|file_path| is_python_file(file_path))
+        .map(|file_path| file_path.canonicalize().unwrap_or(file_path))
+        .collect();
+
+    // First pass: Add all files as nodes
+    for file_path in &python_files {
+        graph.add_file(File::new(file_path.to_string_lossy().to_string()));
+    }
+
+    // Second pass: Parse imports and add edges
+    let cache = ParseCache::new(project_path, "python");
+    unresolved.clear();
+    for (file_path, imports) in parse_python_files(&python_files, &cache, progress) {
+        let unresolved_names =
+            add_python_imports(graph, &file_path, &imports, source_roots);
+        unresolved.set(file_path, unresolved_names);
+    }
+    cache.prune();
+
+    Ok(())
+}
+
+/// Updates the graph after `file_paths` were created, modified or removed,
+/// parsing only the files that still exist.
+///
+/// Files whose imports did not resolve to a module named like a created file
+/// are resolved again, since they may import it now.
+fn update_workspace_graph_with_python_files(
+    graph: &mut WorkspaceGraph,
+    project_path: &Path,
+    source_roots: &[PathBuf],
+    unresolved: &UnresolvedImports,
+    file_paths: &[PathBuf],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut changed_files = Vec::new();
+    let mut created_files = Vec::new();
+    for file_path in file_paths {
+        if !file_path.is_file() {
+            graph.remove_file(&file_path.to_string_lossy());
+            unresolved.remove(file_path);
+            continue;
+        }
+        let file_path = file_path
+            .canonicalize()
+            .unwrap_or_else(|_| file_path.clone());
+        let file_name = file_path.to_string_lossy().to_string();
+        if graph.find_file(&file_name).is_none() {
+            created_files.push(file_path.clone());
+        }
+        graph.add_file(File::new(file_name));
+        changed_files.push(file_path);
+    }
+    changed_files
+        .extend(unresolved.importers_of(created_files.iter().map(PathBuf::as_path)));
+    changed_files.sort();
+    changed_files.dedup();
+
+    for file_path in &changed_files {
+        if let Some(node_idx) = graph.find_file(&file_path.to_string_lossy()) {
+            graph.remove_imports_from(node_idx);
+        }
+    }
+    let cache = ParseCache::new(project_path, "python");
+    for (file_path, imports) in
+        parse_python_files(&changed_files, &cache, &Progress::default())
+    {
+        let unresolved_names =
+            add_python_imports(graph, &file_path, &imports, source_roots);
+        unresolved.set(file_path, unresolved_names);
+    }
+
+    Ok(())
+}
+
+fn canonical_source_roots(project_path: &Path) -> Vec<PathBuf> {
+    find_source_roots(project_path)
+        .into_iter()
+        .map(|root| root.canonicalize().unwrap_or(root))
+        .collect()
+}
+
+/// Adds the imports of the file at `file_path` to the graph, and returns the
+/// names of the modules it imports that did not resolve to a workspace file.
+fn add_python_imports(
+    graph: &mut WorkspaceGraph,
+    file_path: &Path,
+    imports: &[PythonImport],
+    source_roots: &[PathBuf],
+) -> HashSet<String> {
+    let mut unresolved_names = HashSet::new();
+    let Some(current_node) = graph.find_file(&file_path.to_string_lossy()) else {
+        return unresolved_names;
+    };
+
+    let mut imported_files = HashSet::new();
+    for import in imports {
+        let resolved = resolve_import(import, file_path, source_roots);
+        // The names of a `from` import may be submodules not created yet
+        if resolved.len() < import.names.len().max(1) {
+            unresolved_names.extend(import.module.iter().cloned());
+            unresolved_names.extend(
+                import
+                    .names
+                    .iter()
+                    .flat_map(|name| name.split('.'))
+                    .map(str::to_string),
+            );
+        }
+        imported_files.extend(resolved);
+    }
+    for imported_file in imported_files {
+        if imported_file == file_path {
+            continue;
+        }
+        if let Some(imported_node) =
+            graph.find_file(&imported_file.to_string_lossy())
+        {
+            graph.add_import(current_node, imported_node, EdgeKind::StaticImport);
+        }
+    }
+    unresolved_names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace_graph::feeder::test_utils::write;
+
+    fn import(level: usize, module: &str, names: &[&str]) -> PythonImport {
+        PythonImport {
+            level,
+            module: module
+                .split('.')
+                .filter(|part| !part.is_empty())
+                .map(str::to_string)
+                .collect(),
+            names: names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_absolute_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let main = write(root, "main.py", "");
+        let init = write(root, "src/pkg/__init__.py", "");
+        let module = write(root, "src/pkg/module.py", "");
+        // `ns` is a namespace package without `__init__.py`
+        let leaf = write(root, "src/ns/leaf.py", "");
+        let roots = find_source_roots(root);
+
+        assert_eq!(
+            resolve_import(&import(0, "pkg.module", &[]), &main, &roots),
+            vec![module.clone()]
+        );
+        assert_eq!(
+            resolve_import(&import(0, "pkg", &["module", "symbol"]), &main, &roots),
+            vec![module, init]
+        );
+        assert_eq!(
+            resolve_import(&import(0, "ns", &["leaf"]), &main, &roots),
+            vec![leaf]
+        );
+        assert!(resolve_import(&import(0, "numpy", &[]), &main, &roots).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_relative_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let init = write(root, "pkg/__init__.py", "");
+        let sibling = write(root, "pkg/sub/sibling.py", "");
+        let current = write(root, "pkg/sub/current.py", "");
+        let other = write(root, "pkg/other/module.py", "");
+        let roots = find_source_roots(root);
+
+        assert_eq!(
+            resolve_import(&import(1, "", &["sibling"]), &current, &roots),
+            vec![sibling]
+        );
+        assert_eq!(
+            resolve_import(&import(2, "other", &["module"]), &current, &roots),
+            vec![other]
+        );
+        assert_eq!(
+            resolve_import(&import(2, "", &["symbol"]), &current, &roots),
+            vec![init]
+        );
+        assert!(
+            resolve_import(&import(1, "", &["missing"]), &current, &roots)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::feeder::feed_again;
+use super::{
+    Feeder, is_ignored, python::PythonFeeder, rust::RustFeeder,
+    typescript::TypescriptFeeder, workspace_files,
+};
+use crate::app_temp::app::grammars;
+use crate::settings::read_settings;
+use crate::workspace_graph::{Progress, WorkspaceGraph};
+
+/// What the feeders add to the graph besides the files of the workspace,
+/// read from the `[amas.graph]` table of the settings file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FeederSettings {
+    /// Whether imported `node_modules` packages show up in the graph.
+    pub external_packages: bool,
+    /// Whether exported symbols show up in the graph, which takes a semantic
+    /// analysis of every file.
+    pub symbols: bool,
+}
+
+impl FeederSettings {
+    pub fn load() -> Self {
+        read_settings("graph").unwrap_or_default()
+    }
+}
+
+/// The set of feeders amas knows about.
+///
+/// Feeding a workspace runs every feeder that detects itself in it, so a
+/// workspace mixing several languages ends up in a single graph.
+pub struct FeederRegistry {
+    feeders: Vec<Box<dyn Feeder>>,
+}
+
+impl FeederRegistry {
+    pub fn new() -> Self {
+        Self {
+            feeders: Vec::new(),
+        }
+    }
+
+    /// The feeders amas knows about, configured with `settings`.
+    pub fn with_settings(settings: &FeederSettings) -> Self {
+        let mut registry = Self::new();
+        registry.register(TypescriptFeeder {
+            external_packages: settings.external_packages,
+            symbols: settings.symbols,
+            ..TypescriptFeeder::default()
+        });
+        registry.register(RustFeeder::default());
+        registry.register(PythonFeeder::default());
+        registry
+    }
+
+    pub fn register(&mut self, feeder: impl Feeder + 'static) {
+        self.feeders.push(Box::new(feeder));
+    }
+
+    /// Returns the feeders that apply to the workspace at `workspace_path`.
+    pub fn detect(&self, workspace_path: &Path) -> Vec<&dyn Feeder> {
+        self.feeders
+            .iter()
+            .filter(|feeder| feeder.detect(workspace_path))
+            .map(|feeder| feeder.as_ref())
+            .collect()
+    }
+
+    /// Feeds `graph` with every feeder that applies to the workspace.
+    ///
+    /// A failing feeder is reported and skipped, so that the other languages
+    /// of the workspace still show up.
+    pub fn feed(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        progress: &Progress,
+    ) {
+        let feeders = self.detect(workspace_path);
+        // The editor only downloads the grammars once a file is opened
+        if feeders.iter().any(|feeder| feeder.uses_grammars())
+            && !grammars::grammars_installed()
+        {
+            grammars::update_grammars();
+        }
+        for feeder in feeders {
+            if let Err(e) = feeder.feed(graph, workspace_path, progress) {
+                eprintln!(
+                    "Feeder {} failed on {}: {}",
+                    feeder.name(),
+                    workspace_path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Updates `graph` after `file_paths` were created, modified or removed,
+    /// letting each feeder handle its own files.
+    ///
+    /// A feeder whose manifests changed feeds the whole workspace again,
+    /// since the projects its imports resolve against may have changed.
+    pub fn update(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        file_paths: &[PathBuf],
+    ) {
+        let file_paths: Vec<PathBuf> = expand_directories(graph, file_paths)
+            .into_iter()
+            .filter(|file_path| {
+                file_path
+                    .strip_prefix(workspace_path)
+                    .is_ok_and(|relative_path| !is_ignored(relative_path))
+            })
+            .collect();
+        for feeder in &self.feeders {
+            let handled_paths: Vec<PathBuf> = file_paths
+                .iter()
+                .filter(|file_path| feeder.handles(file_path))
+                .cloned()
+                .collect();
+            let manifest_changed = file_paths.iter().any(|file_path| {
+                file_path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|name| feeder.manifests().contains(&name))
+            });
+            let result = if manifest_changed {
+                feed_again(feeder.as_ref(), graph, workspace_path, &handled_paths)
+            } else if !handled_paths.is_empty() {
+                feeder.update(graph, workspace_path, &handled_paths)
+            } else {
+                continue;
+            };
+            if let Err(e) = result {
+                eprintln!(
+                    "Feeder {} failed to update {}: {}",
+                    feeder.name(),
+                    workspace_path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Replaces the directories of `file_paths` by their files, since file system
+/// events may only report a moved or removed directory.
+fn expand_directories(
+    graph: &WorkspaceGraph,
+    file_paths: &[PathBuf],
+) -> Vec<PathBuf> {
+    let mut expanded_paths = Vec::new();
+    for file_path in file_paths {
+        if file_path.is_dir() {
+            expanded_paths.extend(workspace_files(file_path));
+        } else if !file_path.exists() {
+            expanded_paths.push(file_path.clone());
+            expanded_paths.extend(
+                graph
+                    .graph
+                    .node_weights()
+                    .filter(|file| file.symbol.is_none() && !file.external)
+                    .map(|file| PathBuf::from(&file.name))
+                    .filter(|path| path != file_path && path.starts_with(file_path)),
+            );
+        } else {
+            expanded_paths.push(file_path.clone());
+        }
+    }
+    expanded_paths
+}
+
+impl Default for FeederRegistry {
+    fn default() -> Self {
+        Self::with_settings(&FeederSettings::default())
+    }
+}
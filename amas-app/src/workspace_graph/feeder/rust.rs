@@ -0,0 +1,1307 @@
+use super::cache::{ParseCache, ParseError};
+use super::projects::Projects;
+use super::unresolved::UnresolvedImports;
+use super::{Feeder, workspace_files};
+use crate::file::File;
+use crate::workspace_graph::{EdgeKind, Progress, WorkspaceGraph};
+use lapce_core::syntax::Syntax;
+use lapce_xi_rope::Rope;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tree_sitter::Node;
+
+/// A `mod foo;` declaration without body.
+#[derive(Serialize, Deserialize)]
+struct ModDeclaration {
+    name: String,
+    /// Value of the `#[path = "..."]` attribute, if any.
+    path_attribute: Option<String>,
+    /// Inline modules (`mod a { ... }`) the declaration is nested in.
+    inline_modules: Vec<String>,
+}
+
+/// A path imported by a `use` declaration, e.g. `crate::a::b`.
+#[derive(Serialize, Deserialize)]
+struct UsePath {
+    segments: Vec<String>,
+    /// Whether the path is re-exported with `pub use`.
+    public: bool,
+    /// Inline modules (`mod a { ... }`) the declaration is nested in.
+    inline_modules: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ParsedRustFile {
+    mods: Vec<ModDeclaration>,
+    uses: Vec<UsePath>,
+}
+
+impl ParsedRustFile {
+    fn visit(&mut self, node: Node, source: &str, inline_modules: &mut Vec<String>) {
+        match node.kind() {
+            "mod_item" => {
+                let Some(name) = node.child_by_field_name("name") else {
+                    return;
+                };
+                let name = node_text(name, source);
+                if let Some(body) = node.child_by_field_name("body") {
+                    inline_modules.push(name);
+                    self.visit_children(body, source, inline_modules);
+                    inline_modules.pop();
+                } else {
+                    self.mods.push(ModDeclaration {
+                        name,
+                        path_attribute: path_attribute(node, source),
+                        inline_modules: inline_modules.clone(),
+                    });
+                }
+            }
+            "use_declaration" => {
+                if let Some(argument) = node.child_by_field_name("argument") {
+                    let mut cursor = node.walk();
+                    let public = node
+                        .children(&mut cursor)
+                        .any(|child| child.kind() == "visibility_modifier");
+                    let mut paths = Vec::new();
+                    collect_use_paths(argument, source, &[], &mut paths);
+                    for segments in paths {
+                        self.uses.push(UsePath {
+                            segments,
+                            public,
+                            inline_modules: inline_modules.clone(),
+                        });
+                    }
+                }
+            }
+            _ => self.visit_children(node, source, inline_modules),
+        }
+    }
+
+    fn visit_children(
+        &mut self,
+        node: Node,
+        source: &str,
+        inline_modules: &mut Vec<String>,
+    ) {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.visit(child, source, inline_modules);
+        }
+    }
+}
+
+fn node_text(node: Node, source: &str) -> String {
+    node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
+}
+
+/// Finds the `#[path = "..."]` attribute placed right before a `mod` item.
+fn path_attribute(mod_item: Node, source: &str) -> Option<String> {
+    let mut sibling = mod_item.prev_named_sibling();
+    while let Some(node) = sibling {
+        match node.kind() {
+            "attribute_item" => {
+                let mut cursor = node.walk();
+                let attribute = node
+                    .named_children(&mut cursor)
+                    .find(|child| child.kind() == "attribute");
+                if let Some(attribute) = attribute {
+                    let is_path = attribute
+                        .named_child(0)
+                        .map(|name| node_text(name, source) == "path")
+                        .unwrap_or(false);
+                    let value = attribute
+                        .child_by_field_name("value")
+                        .filter(|value| value.kind() == "string_literal");
+                    if let (true, Some(value)) = (is_path, value) {
+                        return Some(
+                            node_text(value, source).trim_matches('"').to_string(),
+                        );
+                    }
+                }
+            }
+            "line_comment" | "block_comment" => {}
+            _ => break,
+        }
+        sibling = node.prev_named_sibling();
+    }
+    None
+}
+
+/// Flattens a use tree (`a::{b, c::d as e, f::*}`) into the list of paths it
+/// imports.
+fn collect_use_paths(
+    node: Node,
+    source: &str,
+    prefix: &[String],
+    paths: &mut Vec<Vec<String>>,
+) {
+    match node.kind() {
+        "use_as_clause" => {
+            if let Some(path) = node.child_by_field_name("path") {
+                collect_use_paths(path, source, prefix, paths);
+            }
+        }
+        "use_wildcard" => match node.named_child(0) {
+            Some(path) => collect_use_paths(path, source, prefix, paths),
+            None => paths.push(prefix.to_vec()),
+        },
+        "use_list" => {
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                collect_use_paths(child, source, prefix, paths);
+            }
+        }
+        "scoped_use_list" => {
+            let mut prefix = prefix.to_vec();
+            if let Some(path) = node.child_by_field_name("path") {
+                prefix.extend(path_segments(path, source));
+            }
+            if let Some(list) = node.child_by_field_name("list") {
+                collect_use_paths(list, source, &prefix, paths);
+            }
+        }
+        "scoped_identifier" | "identifier" | "crate" | "self" | "super" => {
+            let mut path = prefix.to_vec();
+            path.extend(path_segments(node, source));
+            // `use a::{self, b}` imports `a` itself
+            if path.len() > 1 && path.last().map(String::as_str) == Some("self") {
+                path.pop();
+            }
+            paths.push(path);
+        }
+        _ => {}
+    }
+}
+
+fn path_segments(node: Node, source: &str) -> Vec<String> {
+    if node.kind() == "scoped_identifier" {
+        let mut segments = node
+            .child_by_field_name("path")
+            .map(|path| path_segments(path, source))
+            .unwrap_or_default();
+        if let Some(name) = node.child_by_field_name("name") {
+            segments.push(node_text(name, source));
+        }
+        segments
+    } else {
+        vec![node_text(node, source)]
+    }
+}
+
+fn parse_rust_file(
+    file_path: &Path,
+    source_code: &str,
+) -> Result<ParsedRustFile, ParseError> {
+    // Reuse the tree-sitter grammar loaded by the editor
+    let mut syntax = Syntax::init(file_path);
+    syntax.parse(0, Rope::from(source_code), None);
+    let tree = syntax
+        .layers
+        .as_ref()
+        .and_then(|layers| layers.try_tree())
+        .ok_or("the Rust tree-sitter grammar is not available")?;
+
+    let mut parsed = ParsedRustFile::default();
+    parsed.visit(tree.root_node(), source_code, &mut Vec::new());
+    Ok(parsed)
+}
+
+/// Parses `file_paths` in parallel, reusing the results cached for the files
+/// that did not change.
+fn parse_rust_files(
+    file_paths: &[PathBuf],
+    cache: &ParseCache,
+    progress: &Progress,
+) -> HashMap<PathBuf, ParsedRustFile> {
+    progress.add_files(file_paths.len());
+    file_paths
+        .par_iter()
+        .filter_map(|file_path| {
+            let parsed = cache.get_or_parse(file_path, |source_code| {
+                parse_rust_file(file_path, source_code)
+            });
+            progress.file_indexed();
+            match parsed {
+                Ok(parsed) => Some((file_path.clone(), parsed)),
+                Err(e) => {
+                    eprintln!("Failed to parse {}: {}", file_path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn is_rust_file(file_path: &Path) -> bool {
+    file_path.extension().and_then(|s| s.to_str()) == Some("rs")
+}
+
+/// A crate of the workspace, found from its `Cargo.toml`.
+struct RustCrate {
+    /// Name of the crate as written in paths, i.e. with `-` replaced by `_`.
+    name: String,
+    /// Directory of the `Cargo.toml`.
+    dir: PathBuf,
+    /// Names of the dependencies as written in paths, which may be crates of
+    /// the workspace.
+    dependencies: HashSet<String>,
+    /// Root of the library target, if the crate has one.
+    lib: Option<PathBuf>,
+    /// Roots of the other targets (binaries, examples, tests, benches).
+    others: Vec<PathBuf>,
+}
+
+fn find_crates(workspace_path: &Path) -> Vec<RustCrate> {
+    workspace_files(workspace_path)
+        .filter(|file_path| {
+            file_path.file_name().and_then(|s| s.to_str()) == Some("Cargo.toml")
+        })
+        .filter_map(|manifest_path| read_crate(&manifest_path))
+        .collect()
+}
+
+fn read_crate(manifest_path: &Path) -> Option<RustCrate> {
+    let manifest: toml::Value =
+        toml::from_str(&fs::read_to_string(manifest_path).ok()?).ok()?;
+    let package_name = manifest.get("package")?.get("name")?.as_str()?;
+    let crate_dir = manifest_path.parent()?;
+    let target_path = |target: &toml::Value| {
+        target
+            .get("path")
+            .and_then(|path| path.as_str())
+            .map(|path| crate_dir.join(path))
+    };
+
+    let lib_target = manifest.get("lib");
+    let name = lib_target
+        .and_then(|lib| lib.get("name"))
+        .and_then(|name| name.as_str())
+        .unwrap_or(package_name)
+        .replace('-', "_");
+    let lib = lib_target
+        .and_then(target_path)
+        .or_else(|| Some(crate_dir.join("src/lib.rs")))
+        .filter(|path| path.is_file());
+
+    let mut others = vec![crate_dir.join("src/main.rs")];
+    for section in ["bin", "example", "test", "bench"] {
+        if let Some(targets) = manifest.get(section).and_then(|t| t.as_array()) {
+            others.extend(targets.iter().filter_map(target_path));
+        }
+    }
+    for dir in ["src/bin", "examples", "tests", "benches"] {
+        let Ok(entries) = fs::read_dir(crate_dir.join(dir)) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                others.push(path.join("main.rs"));
+            } else if is_rust_file(&path) {
+                others.push(path);
+            }
+        }
+    }
+    others.retain(|path| path.is_file());
+    others.sort();
+    others.dedup();
+
+    let dependencies = ["dependencies", "dev-dependencies", "build-dependencies"]
+        .iter()
+        .filter_map(|section| manifest.get(section)?.as_table())
+        .flat_map(|dependencies| dependencies.keys())
+        .map(|name| name.replace('-', "_"))
+        .collect();
+
+    Some(RustCrate {
+        name,
+        dir: canonical(crate_dir),
+        dependencies,
+        lib,
+        others,
+    })
+}
+
+/// The crate `file_path` belongs to, i.e. the innermost crate whose directory
+/// contains it.
+fn crate_of<'c>(crates: &'c [RustCrate], file_path: &Path) -> Option<&'c RustCrate> {
+    crates
+        .iter()
+        .filter(|rust_crate| file_path.starts_with(&rust_crate.dir))
+        .max_by_key(|rust_crate| rust_crate.dir.components().count())
+}
+
+/// Names of the crates whose imports may change after `file_paths` changed:
+/// the crates of the files, and the crates depending on them.
+fn affected_crates(crates: &[RustCrate], file_paths: &[PathBuf]) -> HashSet<String> {
+    let changed: HashSet<&str> = file_paths
+        .iter()
+        .filter_map(|file_path| crate_of(crates, file_path))
+        .map(|rust_crate| rust_crate.name.as_str())
+        .collect();
+    crates
+        .iter()
+        .filter(|rust_crate| {
+            changed.contains(rust_crate.name.as_str())
+                || changed
+                    .iter()
+                    .any(|name| rust_crate.dependencies.contains(*name))
+        })
+        .map(|rust_crate| rust_crate.name.clone())
+        .collect()
+}
+
+/// The module tree of a crate target, from its root file.
+struct ModuleTree {
+    /// Module path (without the crate name) to the file defining it.
+    modules: HashMap<Vec<String>, PathBuf>,
+    /// File to its module path, and the directory its child modules live in.
+    files: HashMap<PathBuf, (Vec<String>, PathBuf)>,
+    /// `mod foo;` declarations, from the declaring file to the module file.
+    declarations: Vec<(PathBuf, PathBuf)>,
+    /// `mod foo;` declarations whose file does not exist, from the declaring
+    /// file to the name of the missing module file.
+    missing_modules: Vec<(PathBuf, String)>,
+}
+
+impl ModuleTree {
+    fn build(
+        root: &Path,
+        cache: &ParseCache,
+        parsed_files: &mut HashMap<PathBuf, ParsedRustFile>,
+    ) -> Self {
+        let mut tree = ModuleTree {
+            modules: HashMap::new(),
+            files: HashMap::new(),
+            declarations: Vec::new(),
+            missing_modules: Vec::new(),
+        };
+
+        let root = canonical(root);
+        let root_dir = root.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut queue = VecDeque::from([(root, Vec::new(), root_dir)]);
+
+        while let Some((file_path, module_path, children_dir)) = queue.pop_front() {
+            if tree.files.contains_key(&file_path) {
+                continue;
+            }
+            tree.modules.insert(module_path.clone(), file_path.clone());
+            tree.files.insert(
+                file_path.clone(),
+                (module_path.clone(), children_dir.clone()),
+            );
+
+            // Files outside of the workspace, reached through `#[path]`, and
+            // files left unchanged by an update were not parsed beforehand
+            if !parsed_files.contains_key(&file_path) {
+                let parsed = cache.get_or_parse(&file_path, |source_code| {
+                    parse_rust_file(&file_path, source_code)
+                });
+                match parsed {
+                    Ok(parsed) => {
+                        parsed_files.insert(file_path.clone(), parsed);
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to parse {}: {}", file_path.display(), e);
+                        continue;
+                    }
+                }
+            }
+
+            for declaration in &parsed_files[&file_path].mods {
+                let mut dir = children_dir.clone();
+                dir.extend(&declaration.inline_modules);
+
+                let candidates = match &declaration.path_attribute {
+                    // Outside of inline modules, `#[path]` is relative to the
+                    // directory of the declaring file
+                    Some(path) if declaration.inline_modules.is_empty() => {
+                        vec![file_path.parent().unwrap_or(Path::new("")).join(path)]
+                    }
+                    Some(path) => vec![dir.join(path)],
+                    None => vec![
+                        dir.join(format!("{}.rs", declaration.name)),
+                        dir.join(&declaration.name).join("mod.rs"),
+                    ],
+                };
+                let Some(module_file) =
+                    candidates.iter().find(|path| path.is_file())
+                else {
+                    let name = candidates[0].file_stem().and_then(|s| s.to_str());
+                    tree.missing_modules.push((
+                        file_path.clone(),
+                        name.unwrap_or(declaration.name.as_str()).to_string(),
+                    ));
+                    continue;
+                };
+                let module_file = canonical(module_file);
+
+                // Files loaded through `#[path]` and `mod.rs` files own their
+                // directory, other files own a directory named after them
+                let is_mod_rs = declaration.path_attribute.is_some()
+                    || module_file.file_name().and_then(|s| s.to_str())
+                        == Some("mod.rs");
+                let module_children_dir = if is_mod_rs {
+                    module_file.parent().unwrap_or(Path::new("")).to_path_buf()
+                } else {
+                    module_file.with_extension("")
+                };
+
+                let mut child_module_path = module_path.clone();
+                child_module_path.extend(declaration.inline_modules.iter().cloned());
+                child_module_path.push(declaration.name.clone());
+
+                tree.declarations
+                    .push((file_path.clone(), module_file.clone()));
+                queue.push_back((
+                    module_file,
+                    child_module_path,
+                    module_children_dir,
+                ));
+            }
+        }
+
+        tree
+    }
+
+    /// Finds the file of the deepest module of `segments` under `module_path`,
+    /// along with the segments left after it.
+    fn deepest_module<'s>(
+        &self,
+        mut module_path: Vec<String>,
+        segments: &'s [String],
+    ) -> Option<(&PathBuf, &'s [String])> {
+        let mut deepest = self
+            .modules
+            .get(&module_path)
+            .map(|file_path| (file_path, segments));
+        for (index, segment) in segments.iter().enumerate() {
+            module_path.push(segment.clone());
+            match self.modules.get(&module_path) {
+                Some(file_path) => {
+                    deepest = Some((file_path, &segments[index + 1..]))
+                }
+                None => break,
+            }
+        }
+        deepest
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Resolves a `use` path found in a module of `tree` to the file defining the
+/// deepest module it names, along with the segments left after it.
+fn resolve_use_path<'a, 's>(
+    segments: &'s [String],
+    current_module: &[String],
+    tree: &'a ModuleTree,
+    libs: &'a HashMap<String, ModuleTree>,
+) -> Option<(&'a PathBuf, &'s [String])> {
+    let (first, rest) = segments.split_first()?;
+    match first.as_str() {
+        "crate" => tree.deepest_module(Vec::new(), rest),
+        "self" => tree.deepest_module(current_module.to_vec(), rest),
+        "super" => {
+            let mut module_path = current_module.to_vec();
+            module_path.pop();
+            let supers = rest.iter().take_while(|s| s.as_str() == "super").count();
+            for _ in 0..supers {
+                module_path.pop();
+            }
+            tree.deepest_module(module_path, &rest[supers..])
+        }
+        name if libs.contains_key(name) => {
+            libs[name].deepest_module(Vec::new(), rest)
+        }
+        _ => {
+            // A path relative to the current module, e.g. `use foo::Bar` where
+            // `foo` is a child module. Anything else is an external crate.
+            let mut module_path = current_module.to_vec();
+            module_path.push(first.clone());
+            tree.modules
+                .get(&module_path)
+                .and_then(|_| tree.deepest_module(module_path, rest))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RustFeeder {
+    /// The crates of the workspace, read from their `Cargo.toml`.
+    crates: Projects<Vec<RustCrate>>,
+    /// Files reached by the module trees of the crates in the last pass.
+    modules: Mutex<HashSet<PathBuf>>,
+    unresolved: UnresolvedImports,
+}
+
+impl Feeder for RustFeeder {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn handles(&self, file_path: &Path) -> bool {
+        is_rust_file(file_path)
+    }
+
+    fn detect(&self, workspace_path: &Path) -> bool {
+        workspace_files(workspace_path).any(|file_path| {
+            file_path.file_name().and_then(|s| s.to_str()) == Some("Cargo.toml")
+        })
+    }
+
+    fn uses_grammars(&self) -> bool {
+        true
+    }
+
+    fn manifests(&self) -> &'static [&'static str] {
+        &["Cargo.toml"]
+    }
+
+    fn feed(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        progress: &Progress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let crates = self.crates.read(|| find_crates(workspace_path));
+        let mut modules =
+            self.modules.lock().unwrap_or_else(PoisonError::into_inner);
+        feed_workspace_graph_with_rust_project(
+            graph,
+            workspace_path,
+            &crates,
+            &mut modules,
+            &self.unresolved,
+            progress,
+        )
+    }
+
+    fn update(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        file_paths: &[PathBuf],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let crates = self.crates.get(|| find_crates(workspace_path));
+        let mut modules =
+            self.modules.lock().unwrap_or_else(PoisonError::into_inner);
+        update_workspace_graph_with_rust_files(
+            graph,
+            workspace_path,
+            &crates,
+            &mut modules,
+            &self.unresolved,
+            file_paths,
+        )
+    }
+}
+
+fn feed_workspace_graph_with_rust_project(
+    graph: &mut WorkspaceGraph,
+    project_path: &Path,
+    crates: &[RustCrate],
+    modules: &mut HashSet<PathBuf>,
+    unresolved: &UnresolvedImports,
+    progress: &Progress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Add all Rust files as nodes, even the ones no target reaches
+    let rust_files: Vec<PathBuf> = workspace_files(project_path)
+        .filter(|p| is_rust_file(p))
+        .map(|file_path| canonical(&file_path))
+        .collect();
+    for file_path in &rust_files {
+        graph.add_file(File::new(file_path.to_string_lossy().to_string()));
+    }
+
+    let cache = ParseCache::new(project_path, "rust");
+    let mut parsed_files = parse_rust_files(&rust_files, &cache, progress);
+
+    let mut libs = HashMap::new();
+    for rust_crate in crates {
+        if let Some(lib) = &rust_crate.lib {
+            libs.insert(
+                rust_crate.name.clone(),
+                ModuleTree::build(lib, &cache, &mut parsed_files),
+            );
+        }
+    }
+    let mut trees = Vec::new();
+    for rust_crate in crates {
+        for root in &rust_crate.others {
+            trees.push(ModuleTree::build(root, &cache, &mut parsed_files));
+        }
+    }
+
+    let trees: Vec<&ModuleTree> = libs.values().chain(trees.iter()).collect();
+    *modules = trees
+        .iter()
+        .flat_map(|tree| tree.files.keys().cloned())
+        .collect();
+    unresolved.clear();
+    add_rust_imports(
+        graph,
+        trees.into_iter(),
+        &libs,
+        &parsed_files,
+        |_| true,
+        unresolved,
+    );
+    cache.prune();
+
+    Ok(())
+}
+
+/// Updates the graph after `file_paths` were created, modified or removed.
+///
+/// Only the changed files are parsed again, and only the module trees of
+/// their crates and of the crates depending on them are built again, since
+/// the imports of the other crates cannot have changed. The imports are then
+/// added again for the changed files, the files importing a module that left
+/// the trees, and the files whose paths did not resolve to a module named
+/// like one that joined them.
+fn update_workspace_graph_with_rust_files(
+    graph: &mut WorkspaceGraph,
+    project_path: &Path,
+    crates: &[RustCrate],
+    modules: &mut HashSet<PathBuf>,
+    unresolved: &UnresolvedImports,
+    file_paths: &[PathBuf],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut changed_files = Vec::new();
+    let mut existing_files = Vec::new();
+    let mut relinked_files = HashSet::new();
+    for file_path in file_paths {
+        if !file_path.is_file() {
+            // The paths to the removed file now resolve to its parent module
+            relinked_files.extend(importers_of_file(graph, file_path));
+            graph.remove_file(&file_path.to_string_lossy());
+            unresolved.remove(file_path);
+            changed_files.push(file_path.clone());
+            continue;
+        }
+        let file_path = canonical(file_path);
+        graph.add_file(File::new(file_path.to_string_lossy().to_string()));
+        changed_files.push(file_path.clone());
+        relinked_files.insert(file_path.clone());
+        existing_files.push(file_path);
+    }
+
+    let affected = affected_crates(crates, &changed_files);
+    if affected.is_empty() {
+        return Ok(());
+    }
+    let is_affected = |file_path: &Path| {
+        crate_of(crates, file_path)
+            .is_some_and(|rust_crate| affected.contains(&rust_crate.name))
+    };
+
+    let cache = ParseCache::new(project_path, "rust");
+    let mut parsed_files =
+        parse_rust_files(&existing_files, &cache, &Progress::default());
+
+    // The libraries the affected crates may import from
+    let mut libs = HashMap::new();
+    for rust_crate in crates {
+        let is_imported = affected.contains(&rust_crate.name)
+            || crates.iter().any(|dependent| {
+                affected.contains(&dependent.name)
+                    && dependent.dependencies.contains(&rust_crate.name)
+            });
+        if !is_imported {
+            continue;
+        }
+        if let Some(lib) = &rust_crate.lib {
+            libs.insert(
+                rust_crate.name.clone(),
+                ModuleTree::build(lib, &cache, &mut parsed_files),
+            );
+        }
+    }
+    let mut trees = Vec::new();
+    for rust_crate in crates {
+        if !affected.contains(&rust_crate.name) {
+            continue;
+        }
+        for root in &rust_crate.others {
+            trees.push(ModuleTree::build(root, &cache, &mut parsed_files));
+        }
+    }
+    let trees: Vec<&ModuleTree> = libs
+        .iter()
+        .filter(|(name, _)| affected.contains(*name))
+        .map(|(_, tree)| tree)
+        .chain(trees.iter())
+        .collect();
+
+    // The paths to the files that left the module trees now resolve to their
+    // parent module, while the files that joined them may resolve the paths
+    // that did not resolve before
+    let tree_files: HashSet<&PathBuf> =
+        trees.iter().flat_map(|tree| tree.files.keys()).collect();
+    let left_files: Vec<PathBuf> = modules
+        .iter()
+        .filter(|file_path| {
+            is_affected(file_path) && !tree_files.contains(file_path)
+        })
+        .cloned()
+        .collect();
+    for file_path in &left_files {
+        relinked_files.extend(importers_of_file(graph, file_path));
+        modules.remove(file_path);
+    }
+    let joined_files: Vec<PathBuf> = tree_files
+        .into_iter()
+        .filter(|file_path| !modules.contains(*file_path))
+        .cloned()
+        .collect();
+    relinked_files
+        .extend(unresolved.importers_of(joined_files.iter().map(PathBuf::as_path)));
+    modules.extend(joined_files);
+
+    // Only the trees of the affected crates are walked again
+    relinked_files.retain(|file_path| is_affected(file_path));
+    for file_path in &relinked_files {
+        if let Some(node_idx) = graph.find_file(&file_path.to_string_lossy()) {
+            graph.remove_imports_from(node_idx);
+        }
+        unresolved.remove(file_path);
+    }
+    add_rust_imports(
+        graph,
+        trees.into_iter(),
+        &libs,
+        &parsed_files,
+        |file_path| relinked_files.contains(file_path),
+        unresolved,
+    );
+
+    Ok(())
+}
+
+/// The files of the graph importing the file at `file_path`.
+fn importers_of_file(graph: &WorkspaceGraph, file_path: &Path) -> Vec<PathBuf> {
+    let Some(node_idx) = graph.find_file(&file_path.to_string_lossy()) else {
+        return Vec::new();
+    };
+    graph
+        .importers_of(node_idx)
+        .into_iter()
+        .map(|importer| &graph.graph[importer])
+        .filter(|importer| importer.symbol.is_none() && !importer.external)
+        .map(|importer| PathBuf::from(&importer.name))
+        .collect()
+}
+
+/// Adds the `mod` declarations and the `use` imports of the files of `trees`
+/// accepted by `relink` to the graph, resolving the paths to other crates
+/// against `libs`, and keeps the names of the modules they import without
+/// resolving them in `unresolved`.
+fn add_rust_imports<'t>(
+    graph: &mut WorkspaceGraph,
+    trees: impl Iterator<Item = &'t ModuleTree>,
+    libs: &HashMap<String, ModuleTree>,
+    parsed_files: &HashMap<PathBuf, ParsedRustFile>,
+    relink: impl Fn(&Path) -> bool,
+    unresolved: &UnresolvedImports,
+) {
+    let mut imports = HashSet::new();
+    let mut unresolved_names: HashMap<&PathBuf, HashSet<String>> = HashMap::new();
+    for tree in trees {
+        for (declaring_file, module_file) in &tree.declarations {
+            if relink(declaring_file) {
+                imports.insert((
+                    declaring_file,
+                    module_file,
+                    EdgeKind::ModuleDeclaration,
+                ));
+            }
+        }
+        for (declaring_file, name) in &tree.missing_modules {
+            if relink(declaring_file) {
+                unresolved_names
+                    .entry(declaring_file)
+                    .or_default()
+                    .insert(name.clone());
+            }
+        }
+
+        for (file_path, (module_path, _)) in &tree.files {
+            if !relink(file_path) {
+                continue;
+            }
+            let Some(parsed) = parsed_files.get(file_path) else {
+                continue;
+            };
+            let names = unresolved_names.entry(file_path).or_default();
+            for use_path in &parsed.uses {
+                let mut current_module = module_path.clone();
+                current_module.extend(use_path.inline_modules.iter().cloned());
+                match resolve_use_path(
+                    &use_path.segments,
+                    &current_module,
+                    tree,
+                    libs,
+                ) {
+                    Some((imported_file, rest)) => {
+                        let kind = if use_path.public {
+                            EdgeKind::ReExport
+                        } else {
+                            EdgeKind::StaticImport
+                        };
+                        imports.insert((file_path, imported_file, kind));
+                        names.extend(rest.iter().cloned());
+                    }
+                    None => names.extend(
+                        use_path
+                            .segments
+                            .iter()
+                            .filter(|segment| {
+                                !matches!(
+                                    segment.as_str(),
+                                    "crate" | "self" | "super"
+                                )
+                            })
+                            .cloned(),
+                    ),
+                }
+            }
+        }
+    }
+    for (file_path, names) in unresolved_names {
+        unresolved.set(file_path.clone(), names);
+    }
+
+    for (from, to, kind) in imports {
+        if from == to {
+            continue;
+        }
+        let from = graph.find_file(&from.to_string_lossy());
+        let to = graph.find_file(&to.to_string_lossy());
+        if let (Some(from), Some(to)) = (from, to) {
+            graph.add_import(from, to, kind);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace_graph::feeder::test_utils::write;
+
+    fn mod_item(name: &str) -> ModDeclaration {
+        ModDeclaration {
+            name: name.to_string(),
+            path_attribute: None,
+            inline_modules: Vec::new(),
+        }
+    }
+
+    fn use_item(path: &str) -> UsePath {
+        UsePath {
+            segments: path.split("::").map(str::to_string).collect(),
+            public: false,
+            inline_modules: Vec::new(),
+        }
+    }
+
+    fn parsed(mods: Vec<ModDeclaration>, uses: Vec<UsePath>) -> ParsedRustFile {
+        ParsedRustFile { mods, uses }
+    }
+
+    fn module_path(path: &str) -> Vec<String> {
+        path.split("::")
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Adds the imports of `trees` to a graph of the parsed files, and
+    /// returns them with the paths relative to `root`.
+    fn imports<'t>(
+        root: &Path,
+        trees: impl Iterator<Item = &'t ModuleTree>,
+        libs: &HashMap<String, ModuleTree>,
+        parsed_files: &HashMap<PathBuf, ParsedRustFile>,
+    ) -> HashSet<(String, String, EdgeKind)> {
+        let mut graph = WorkspaceGraph::new();
+        for file_path in parsed_files.keys() {
+            graph.add_file(File::new(file_path.to_string_lossy().to_string()));
+        }
+        let unresolved = UnresolvedImports::default();
+        add_rust_imports(
+            &mut graph,
+            trees,
+            libs,
+            parsed_files,
+            |_| true,
+            &unresolved,
+        );
+
+        let relative = |node_idx: petgraph::graph::NodeIndex| {
+            let file_path = Path::new(&graph.graph[node_idx].name);
+            let file_path = file_path.strip_prefix(root).unwrap();
+            file_path.to_string_lossy().to_string()
+        };
+        graph
+            .graph
+            .edge_indices()
+            .map(|edge| {
+                let (from, to) = graph.graph.edge_endpoints(edge).unwrap();
+                (relative(from), relative(to), graph.graph[edge].kind)
+            })
+            .collect()
+    }
+
+    fn import(from: &str, to: &str, kind: EdgeKind) -> (String, String, EdgeKind) {
+        (from.to_string(), to.to_string(), kind)
+    }
+
+    #[test]
+    fn test_unresolved_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let lib = write(&root, "src/lib.rs", "");
+        let foo = write(&root, "src/foo.rs", "");
+        let mut parsed_files = HashMap::from([
+            (
+                lib.clone(),
+                parsed(
+                    vec![mod_item("foo"), mod_item("missing")],
+                    vec![
+                        use_item("crate::foo::Bar"),
+                        use_item("serde::Deserialize"),
+                    ],
+                ),
+            ),
+            (foo.clone(), ParsedRustFile::default()),
+        ]);
+        let tree =
+            ModuleTree::build(&lib, &ParseCache::disabled(), &mut parsed_files);
+        let mut graph = WorkspaceGraph::new();
+        let unresolved = UnresolvedImports::default();
+        add_rust_imports(
+            &mut graph,
+            [&tree].into_iter(),
+            &HashMap::new(),
+            &parsed_files,
+            |_| true,
+            &unresolved,
+        );
+
+        // A missing module file, and the segments after the deepest module
+        for created in ["src/missing.rs", "src/missing/mod.rs", "src/foo/Bar.rs"] {
+            assert_eq!(
+                unresolved.importers_of([root.join(created).as_path()]),
+                vec![lib.clone()]
+            );
+        }
+        // Paths to other crates resolve to nothing
+        assert_eq!(
+            unresolved.importers_of([root.join("src/serde.rs").as_path()]),
+            vec![lib.clone()]
+        );
+        assert!(unresolved.importers_of([foo.as_path()]).is_empty());
+    }
+
+    #[test]
+    fn test_mod_declarations() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let lib = write(&root, "src/lib.rs", "");
+        let foo = write(&root, "src/foo.rs", "");
+        let child = write(&root, "src/foo/child.rs", "");
+        let bar = write(&root, "src/bar/mod.rs", "");
+        let baz = write(&root, "src/other/baz.rs", "");
+        let nested = write(&root, "src/inline/nested.rs", "");
+        let mut parsed_files = HashMap::from([
+            (
+                lib.clone(),
+                parsed(
+                    vec![
+                        mod_item("foo"),
+                        mod_item("bar"),
+                        // `#[path = "other/baz.rs"] mod baz;`
+                        ModDeclaration {
+                            path_attribute: Some("other/baz.rs".to_string()),
+                            ..mod_item("baz")
+                        },
+                        // `mod inline { mod nested; }`
+                        ModDeclaration {
+                            inline_modules: vec!["inline".to_string()],
+                            ..mod_item("nested")
+                        },
+                    ],
+                    Vec::new(),
+                ),
+            ),
+            (foo.clone(), parsed(vec![mod_item("child")], Vec::new())),
+            (child.clone(), ParsedRustFile::default()),
+            (bar.clone(), ParsedRustFile::default()),
+            (baz.clone(), ParsedRustFile::default()),
+            (nested.clone(), ParsedRustFile::default()),
+        ]);
+        let tree =
+            ModuleTree::build(&lib, &ParseCache::disabled(), &mut parsed_files);
+
+        assert_eq!(tree.modules.get(&module_path("")), Some(&lib));
+        assert_eq!(tree.modules.get(&module_path("foo")), Some(&foo));
+        assert_eq!(tree.modules.get(&module_path("foo::child")), Some(&child));
+        assert_eq!(tree.modules.get(&module_path("bar")), Some(&bar));
+        assert_eq!(tree.modules.get(&module_path("baz")), Some(&baz));
+        assert_eq!(
+            tree.modules.get(&module_path("inline::nested")),
+            Some(&nested)
+        );
+        assert_eq!(
+            imports(&root, [&tree].into_iter(), &HashMap::new(), &parsed_files),
+            HashSet::from([
+                import("src/lib.rs", "src/foo.rs", EdgeKind::ModuleDeclaration),
+                import("src/lib.rs", "src/bar/mod.rs", EdgeKind::ModuleDeclaration),
+                import(
+                    "src/lib.rs",
+                    "src/other/baz.rs",
+                    EdgeKind::ModuleDeclaration
+                ),
+                import(
+                    "src/lib.rs",
+                    "src/inline/nested.rs",
+                    EdgeKind::ModuleDeclaration
+                ),
+                import(
+                    "src/foo.rs",
+                    "src/foo/child.rs",
+                    EdgeKind::ModuleDeclaration
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_use_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let lib = write(&root, "src/lib.rs", "");
+        let a = write(&root, "src/a.rs", "");
+        let b = write(&root, "src/b.rs", "");
+        let c = write(&root, "src/c.rs", "");
+        let inner = write(&root, "src/a/inner.rs", "");
+        let deep = write(&root, "src/a/inner/deep.rs", "");
+        let mut parsed_files = HashMap::from([
+            (
+                lib.clone(),
+                parsed(
+                    vec![mod_item("a"), mod_item("b"), mod_item("c")],
+                    vec![
+                        use_item("a::Thing"),
+                        use_item("std::fs::File"),
+                        // `mod inline { use super::c::Z; }`
+                        UsePath {
+                            inline_modules: vec!["inline".to_string()],
+                            ..use_item("super::c::Z")
+                        },
+                    ],
+                ),
+            ),
+            (
+                a,
+                parsed(vec![mod_item("inner")], vec![use_item("crate::b::Thing")]),
+            ),
+            (b, ParsedRustFile::default()),
+            (c, ParsedRustFile::default()),
+            (
+                inner,
+                parsed(
+                    vec![mod_item("deep")],
+                    vec![use_item("self::deep::X"), use_item("super::super::c::Y")],
+                ),
+            ),
+            (
+                deep,
+                parsed(
+                    Vec::new(),
+                    // `pub use crate::a::{Thing, inner};`, as parsed
+                    vec![
+                        UsePath {
+                            public: true,
+                            ..use_item("crate::a::Thing")
+                        },
+                        UsePath {
+                            public: true,
+                            ..use_item("crate::a::inner")
+                        },
+                    ],
+                ),
+            ),
+        ]);
+        let tree =
+            ModuleTree::build(&lib, &ParseCache::disabled(), &mut parsed_files);
+
+        let mut imports =
+            imports(&root, [&tree].into_iter(), &HashMap::new(), &parsed_files);
+        imports.retain(|(_, _, kind)| *kind != EdgeKind::ModuleDeclaration);
+        assert_eq!(
+            imports,
+            HashSet::from([
+                import("src/lib.rs", "src/a.rs", EdgeKind::StaticImport),
+                import("src/lib.rs", "src/c.rs", EdgeKind::StaticImport),
+                import("src/a.rs", "src/b.rs", EdgeKind::StaticImport),
+                import(
+                    "src/a/inner.rs",
+                    "src/a/inner/deep.rs",
+                    EdgeKind::StaticImport
+                ),
+                import("src/a/inner.rs", "src/c.rs", EdgeKind::StaticImport),
+                import("src/a/inner/deep.rs", "src/a.rs", EdgeKind::ReExport),
+                import("src/a/inner/deep.rs", "src/a/inner.rs", EdgeKind::ReExport),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_cross_crate_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        write(
+            &root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"app\", \"my-lib\"]\n",
+        );
+        write(
+            &root,
+            "app/Cargo.toml",
+            "[package]\nname = \"app\"\n\n\
+             [dependencies]\nmy-lib = { path = \"../my-lib\" }\n",
+        );
+        let main = write(&root, "app/src/main.rs", "");
+        write(&root, "my-lib/Cargo.toml", "[package]\nname = \"my-lib\"\n");
+        let lib = write(&root, "my-lib/src/lib.rs", "");
+        let util = write(&root, "my-lib/src/util.rs", "");
+        let mut parsed_files = HashMap::from([
+            (
+                main,
+                parsed(
+                    Vec::new(),
+                    vec![
+                        use_item("my_lib::util::helper"),
+                        use_item("my_lib::Thing"),
+                    ],
+                ),
+            ),
+            (lib, parsed(vec![mod_item("util")], Vec::new())),
+            (util.clone(), ParsedRustFile::default()),
+        ]);
+
+        let crates = find_crates(&root);
+        assert_eq!(
+            affected_crates(&crates, &[util]),
+            HashSet::from(["app".to_string(), "my_lib".to_string()])
+        );
+
+        let cache = ParseCache::disabled();
+        let mut libs = HashMap::new();
+        let mut trees = Vec::new();
+        for rust_crate in &crates {
+            if let Some(lib) = &rust_crate.lib {
+                libs.insert(
+                    rust_crate.name.clone(),
+                    ModuleTree::build(lib, &cache, &mut parsed_files),
+                );
+            }
+            for target in &rust_crate.others {
+                trees.push(ModuleTree::build(target, &cache, &mut parsed_files));
+            }
+        }
+
+        let mut imports = imports(
+            &root,
+            libs.values().chain(trees.iter()),
+            &libs,
+            &parsed_files,
+        );
+        imports.retain(|(_, _, kind)| *kind != EdgeKind::ModuleDeclaration);
+        assert_eq!(
+            imports,
+            HashSet::from([
+                import(
+                    "app/src/main.rs",
+                    "my-lib/src/util.rs",
+                    EdgeKind::StaticImport
+                ),
+                import(
+                    "app/src/main.rs",
+                    "my-lib/src/lib.rs",
+                    EdgeKind::StaticImport
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the tree-sitter grammars fetched by the editor"]
+    fn test_parse_rust_file() {
+        let parsed = parse_rust_file(
+            Path::new("lib.rs"),
+            r#"
+            mod foo;
+            #[path = "other/baz.rs"]
+            mod baz;
+            mod inline {
+                mod nested;
+                use super::foo;
+            }
+            use a::{b, c::d};
+            pub use crate::foo::{self, Bar as Baz};
+            "#,
+        )
+        .unwrap();
+
+        let mods: Vec<_> = parsed
+            .mods
+            .iter()
+            .map(|declaration| {
+                (
+                    declaration.name.as_str(),
+                    declaration.path_attribute.as_deref(),
+                    declaration.inline_modules.join("::"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            mods,
+            vec![
+                ("foo", None, String::new()),
+                ("baz", Some("other/baz.rs"), String::new()),
+                ("nested", None, "inline".to_string()),
+            ]
+        );
+
+        let uses: Vec<_> = parsed
+            .uses
+            .iter()
+            .map(|use_path| {
+                (
+                    use_path.segments.join("::"),
+                    use_path.public,
+                    use_path.inline_modules.join("::"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            uses,
+            vec![
+                ("super::foo".to_string(), false, "inline".to_string()),
+                ("a::b".to_string(), false, String::new()),
+                ("a::c::d".to_string(), false, String::new()),
+                ("crate::foo".to_string(), true, String::new()),
+                ("crate::foo::Bar".to_string(), true, String::new()),
+            ]
+        );
+    }
+}
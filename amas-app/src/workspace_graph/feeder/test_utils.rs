@@ -0,0 +1,11 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes `content` to the file at `path` under `root`, creating its
+/// directories, and returns the path of the file.
+pub(super) fn write(root: &Path, path: &str, content: &str) -> PathBuf {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, content).unwrap();
+    path
+}
@@ -0,0 +1,389 @@
+use super::typescript::resolve_module_file;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const CONFIG_FILE_NAMES: &[&str] = &["tsconfig.json", "jsconfig.json"];
+
+/// Guards against `extends` cycles.
+const MAX_EXTENDS_DEPTH: usize = 16;
+
+/// The module resolution options of a `tsconfig.json`/`jsconfig.json`, once
+/// its `extends` chain has been merged. Every path is absolute.
+#[derive(Debug, Default, Clone)]
+pub(super) struct TsConfig {
+    base_url: Option<PathBuf>,
+    paths: Vec<(String, Vec<String>)>,
+    /// Directory of the config defining `paths`, which they are relative to
+    /// when there is no `baseUrl`.
+    paths_dir: Option<PathBuf>,
+    root_dirs: Vec<PathBuf>,
+}
+
+impl TsConfig {
+    pub(super) fn load(config_path: &Path) -> Option<Self> {
+        Self::load_with_depth(config_path, 0)
+    }
+
+    fn load_with_depth(config_path: &Path, depth: usize) -> Option<Self> {
+        if depth > MAX_EXTENDS_DEPTH {
+            return None;
+        }
+        let content = fs::read_to_string(config_path).ok()?;
+        let json: Value = match serde_json::from_str(&strip_jsonc(&content)) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Failed to parse {}: {}", config_path.display(), e);
+                return None;
+            }
+        };
+        let config_dir = config_path.parent().unwrap_or(Path::new(""));
+
+        // `extends` is a single config, or a list of configs since TypeScript 5
+        let extends: Vec<&str> = match json.get("extends") {
+            Some(Value::String(extends)) => vec![extends.as_str()],
+            Some(Value::Array(extends)) => {
+                extends.iter().filter_map(Value::as_str).collect()
+            }
+            _ => Vec::new(),
+        };
+        let mut config = TsConfig::default();
+        for extends in extends {
+            if let Some(base) = find_extended_config(config_dir, extends)
+                .and_then(|base_path| Self::load_with_depth(&base_path, depth + 1))
+            {
+                config.merge(base);
+            }
+        }
+
+        let Some(options) = json.get("compilerOptions") else {
+            return Some(config);
+        };
+        if let Some(base_url) = options.get("baseUrl").and_then(Value::as_str) {
+            config.base_url = Some(normalize_path(&config_dir.join(base_url)));
+        }
+        if let Some(paths) = options.get("paths").and_then(Value::as_object) {
+            config.paths = paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .as_array()
+                        .map(|targets| {
+                            targets
+                                .iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    (pattern.clone(), targets)
+                })
+                .collect();
+            config.paths_dir = Some(config_dir.to_path_buf());
+        }
+        if let Some(root_dirs) = options.get("rootDirs").and_then(Value::as_array) {
+            config.root_dirs = root_dirs
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|root_dir| normalize_path(&config_dir.join(root_dir)))
+                .collect();
+        }
+
+        Some(config)
+    }
+
+    /// Overrides the options of `self` with the ones set in `other`.
+    fn merge(&mut self, other: TsConfig) {
+        if other.base_url.is_some() {
+            self.base_url = other.base_url;
+        }
+        if other.paths_dir.is_some() {
+            self.paths = other.paths;
+            self.paths_dir = other.paths_dir;
+        }
+        if !other.root_dirs.is_empty() {
+            self.root_dirs = other.root_dirs;
+        }
+    }
+
+    /// Resolves a non-relative specifier through `paths`, then `baseUrl`.
+    pub(super) fn resolve_alias(&self, specifier: &str) -> Option<PathBuf> {
+        if let Some(paths_base) = self.base_url.as_ref().or(self.paths_dir.as_ref())
+        {
+            // Like TypeScript, prefer an exact pattern, then the pattern with
+            // the longest prefix before its `*`
+            let mut matches: Vec<(usize, &str, &Vec<String>)> = self
+                .paths
+                .iter()
+                .filter_map(|(pattern, targets)| {
+                    let (prefix_len, wildcard) = match_pattern(pattern, specifier)?;
+                    Some((prefix_len, wildcard, targets))
+                })
+                .collect();
+            matches.sort_by_key(|(prefix_len, _, _)| std::cmp::Reverse(*prefix_len));
+
+            for (_, wildcard, targets) in matches {
+                for target in targets {
+                    let target = target.replacen('*', wildcard, 1);
+                    if let Some(resolved) =
+                        resolve_module_file(&paths_base.join(target))
+                    {
+                        return Some(resolved);
+                    }
+                }
+            }
+        }
+
+        self.base_url
+            .as_ref()
+            .and_then(|base_url| resolve_module_file(&base_url.join(specifier)))
+    }
+
+    /// Resolves a relative import that does not exist on disk by looking it
+    /// up in the other `rootDirs`, which TypeScript merges into one
+    /// virtual directory.
+    pub(super) fn resolve_in_root_dirs(
+        &self,
+        import_path: &Path,
+    ) -> Option<PathBuf> {
+        let import_path = normalize_path(import_path);
+        let (root_dir, relative_path) =
+            self.root_dirs.iter().find_map(|root_dir| {
+                Some((root_dir, import_path.strip_prefix(root_dir).ok()?))
+            })?;
+        self.root_dirs
+            .iter()
+            .filter(|other_root_dir| *other_root_dir != root_dir)
+            .find_map(|other_root_dir| {
+                resolve_module_file(&other_root_dir.join(relative_path))
+            })
+    }
+}
+
+/// Matches `specifier` against a `paths` pattern containing at most one `*`.
+/// Returns the length of the pattern prefix (exact patterns rank first) and
+/// the part of the specifier matched by the `*`.
+fn match_pattern<'s>(pattern: &str, specifier: &'s str) -> Option<(usize, &'s str)> {
+    match pattern.split_once('*') {
+        None => (pattern == specifier).then_some((usize::MAX, "")),
+        Some((prefix, suffix)) => {
+            if specifier.len() < prefix.len() + suffix.len() {
+                return None;
+            }
+            let wildcard = specifier.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            Some((prefix.len(), wildcard))
+        }
+    }
+}
+
+/// Finds the config a `extends` value points to: a path relative to the
+/// extending config, or a config shipped in a package of `node_modules`.
+fn find_extended_config(config_dir: &Path, extends: &str) -> Option<PathBuf> {
+    let with_json_extension = |path: PathBuf| {
+        if path.is_file() {
+            Some(path)
+        } else {
+            let mut with_extension = path.into_os_string();
+            with_extension.push(".json");
+            Some(PathBuf::from(with_extension)).filter(|path| path.is_file())
+        }
+    };
+
+    if extends.starts_with('.') || Path::new(extends).is_absolute() {
+        return with_json_extension(config_dir.join(extends));
+    }
+
+    config_dir.ancestors().find_map(|dir| {
+        let package_path = dir.join("node_modules").join(extends);
+        with_json_extension(package_path.clone())
+            .or_else(|| Some(package_path.join("tsconfig.json")))
+            .filter(|path| path.is_file())
+    })
+}
+
+/// Resolves `.` and `..` components without touching the file system.
+pub(super) fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                result.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => result.push(other),
+        }
+    }
+    result
+}
+
+/// Turns JSON with comments and trailing commas, as accepted in tsconfig
+/// files, into plain JSON.
+fn strip_jsonc(content: &str) -> String {
+    // First pass: remove comments
+    let mut without_comments = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            without_comments.push(c);
+            match c {
+                '\\' => without_comments.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|c| *c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => {
+                in_string = c == '"';
+                without_comments.push(c);
+            }
+        }
+    }
+
+    // Second pass: remove commas directly followed by a closing bracket
+    let mut result = String::with_capacity(without_comments.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in without_comments.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ','
+            && matches!(
+                without_comments[i + 1..].trim_start().chars().next(),
+                Some('}' | ']')
+            )
+        {
+            continue;
+        }
+        result.push(c);
+    }
+
+    result
+}
+
+/// The tsconfig that applies to each directory of the workspace: the nearest
+/// `tsconfig.json` or `jsconfig.json` found going up to the workspace root.
+pub(super) struct TsConfigs {
+    workspace_path: PathBuf,
+    by_dir: HashMap<PathBuf, Option<Arc<TsConfig>>>,
+}
+
+impl TsConfigs {
+    pub(super) fn new(workspace_path: &Path) -> Self {
+        Self {
+            workspace_path: workspace_path.to_path_buf(),
+            by_dir: HashMap::new(),
+        }
+    }
+
+    pub(super) fn for_file(&mut self, file_path: &Path) -> Option<Arc<TsConfig>> {
+        self.for_dir(file_path.parent()?)
+    }
+
+    fn for_dir(&mut self, dir: &Path) -> Option<Arc<TsConfig>> {
+        if let Some(config) = self.by_dir.get(dir) {
+            return config.clone();
+        }
+
+        let config = CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|config_path| config_path.is_file())
+            .and_then(|config_path| TsConfig::load(&config_path))
+            .map(Arc::new)
+            .or_else(|| {
+                if dir == self.workspace_path {
+                    return None;
+                }
+                let parent = dir.parent()?;
+                if !parent.starts_with(&self.workspace_path) {
+                    return None;
+                }
+                self.for_dir(parent)
+            });
+
+        self.by_dir.insert(dir.to_path_buf(), config.clone());
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace_graph::feeder::test_utils::write;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let jsonc = r#"{
+            // comment
+            "a": "http://example.com", /* block */
+            "b": [1, 2,],
+        }"#;
+        let json: Value = serde_json::from_str(&strip_jsonc(jsonc)).unwrap();
+        assert_eq!(json["a"], "http://example.com");
+        assert_eq!(json["b"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_resolve_alias_through_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "tsconfig.base.json",
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@/*": ["src/*"] } } }"#,
+        );
+        write(
+            root,
+            "app/tsconfig.json",
+            r#"{ "extends": "../tsconfig.base" }"#,
+        );
+        let button = write(root, "src/components/button.tsx", "");
+        let utils = write(root, "lib/utils/index.ts", "");
+
+        let mut configs = TsConfigs::new(root);
+        let config = configs.for_file(&root.join("app/main.ts")).unwrap();
+        assert_eq!(config.resolve_alias("@/components/button"), Some(button));
+        assert_eq!(config.resolve_alias("lib/utils"), Some(utils));
+        assert_eq!(config.resolve_alias("react"), None);
+    }
+
+    #[test]
+    fn test_resolve_in_root_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "tsconfig.json",
+            r#"{ "compilerOptions": { "rootDirs": ["src", "generated"] } }"#,
+        );
+        let schema = write(root, "generated/api/schema.ts", "");
+
+        let config = TsConfig::load(&root.join("tsconfig.json")).unwrap();
+        assert_eq!(
+            config.resolve_in_root_dirs(&root.join("src/api/../api/schema")),
+            Some(schema)
+        );
+    }
+}
@@ -1,29 +1,78 @@
-use crate::file::File;
-use crate::workspace_graph::WorkspaceGraph;
+use super::cache::{ParseCache, ParseError};
+use super::packages::{WorkspacePackages, is_installed_package, split_specifier};
+use super::projects::Projects;
+use super::tsconfig::{TsConfig, TsConfigs};
+use super::unresolved::UnresolvedImports;
+use super::{Feeder, workspace_files};
+use crate::file::{File, Symbol, SymbolKind};
+use crate::workspace_graph::{EdgeKind, Progress, WorkspaceGraph};
+use oxc::syntax::module_record::{
+    ExportExportName, ExportImportName, ExportLocalName, ImportImportName,
+    ModuleRecord,
+};
 use oxc_allocator::Allocator;
 use oxc_ast::ast::*;
-use oxc_ast_visit::Visit;
+use oxc_ast_visit::{Visit, walk};
 use oxc_parser::{Parser, ParserReturn};
+use oxc_semantic::{Scoping, SemanticBuilder, SymbolFlags};
 use oxc_span::SourceType;
-use petgraph::graph::NodeIndex;
-use std::collections::HashMap;
-use std::fs;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::Arc;
+
+/// The imports of a file.
+#[derive(Default)]
+struct TypescriptImports {
+    /// Canonical paths of the imported workspace files.
+    files: Vec<(String, EdgeKind)>,
+    /// Names of the imported packages installed in `node_modules`.
+    external_packages: Vec<(String, EdgeKind)>,
+    /// Symbols exported by the file.
+    exports: Vec<(String, SymbolKind)>,
+    /// Canonical paths of the imported workspace files along with the name
+    /// of the symbol used from each of them.
+    symbols: Vec<(String, String, EdgeKind)>,
+    /// Names of the modules imported by specifiers that did not resolve to a
+    /// workspace file.
+    unresolved: HashSet<String>,
+}
 
-struct ImportVisitor {
-    imports: Vec<String>,
+/// The imports and exports of a file as written in its source, before being
+/// resolved. Unlike [`TypescriptImports`], it only depends on the content of
+/// the file, so it can be cached.
+#[derive(Default, Serialize, Deserialize)]
+struct ParsedTypescriptFile {
+    /// Imported module specifiers.
+    imports: Vec<(String, EdgeKind)>,
+    /// Symbols exported by the file.
+    exports: Vec<(String, SymbolKind)>,
+    /// Imported module specifiers along with the name of the symbol used from
+    /// each of them.
+    symbol_imports: Vec<(String, String, EdgeKind)>,
+}
+
+/// Resolves the module specifiers imported by a file.
+struct ImportResolver<'r> {
     current_file_dir: PathBuf,
+    tsconfig: Option<Arc<TsConfig>>,
+    packages: &'r WorkspacePackages,
 }
 
-impl ImportVisitor {
-    fn new(current_file_path: &Path) -> Self {
+impl<'r> ImportResolver<'r> {
+    fn new(
+        current_file_path: &Path,
+        tsconfig: Option<Arc<TsConfig>>,
+        packages: &'r WorkspacePackages,
+    ) -> Self {
         Self {
-            imports: Vec::new(),
             current_file_dir: current_file_path
                 .parent()
                 .unwrap_or(Path::new(""))
                 .to_path_buf(),
+            tsconfig,
+            packages,
         }
     }
 
@@ -41,26 +90,25 @@ fn resolve_import_path(&self, import_path: &str) -> Option<PathBuf> {
                 }
             };
 
-            // Try different extensions
-            for ext in &[".ts", ".tsx", ".js", ".jsx"] {
-                let with_ext = canonical_base.with_extension(&ext[1..]);
-                if with_ext.exists() {
-                    return Some(with_ext);
-                }
-            }
+            return resolve_module_file(&canonical_base).or_else(|| {
+                // The import may point to another directory of `rootDirs`
+                self.tsconfig
+                    .as_ref()?
+                    .resolve_in_root_dirs(&canonical_base)
+            });
+        }
 
-            // Try index files
-            for ext in &[".ts", ".tsx", ".js", ".jsx"] {
-                let index_file = canonical_base.join(format!("index{}", ext));
-                if index_file.exists() {
-                    return Some(index_file);
-                }
-            }
+        // Handle `paths` and `baseUrl` aliases, e.g. `@/components/button`
+        if let Some(resolved) = self
+            .tsconfig
+            .as_ref()
+            .and_then(|tsconfig| tsconfig.resolve_alias(import_path))
+        {
+            return Some(resolved);
         }
 
-        // For absolute imports, you might want to resolve them based on your project structure
-        // This is a simplified version that doesn't handle node_modules or path mapping
-        None
+        // Handle packages of the workspace imported by name, e.g. `@acme/ui`
+        self.packages.resolve(import_path)
     }
 
     fn manual_resolve_path(&self, path: &Path) -> PathBuf {
@@ -92,54 +140,212 @@ fn manual_resolve_path(&self, path: &Path) -> PathBuf {
         result
     }
 
-    fn add_import(&mut self, import_path: &str) {
+    fn resolve(&self, parsed: ParsedTypescriptFile) -> TypescriptImports {
+        let mut imports = TypescriptImports {
+            exports: parsed.exports,
+            ..Default::default()
+        };
+        for (import_path, kind) in parsed.imports {
+            self.add_import(&mut imports, &import_path, kind);
+        }
+        for (import_path, name, kind) in parsed.symbol_imports {
+            if let Some(resolved_path) = self.resolve_import_path(&import_path) {
+                let canonical_path =
+                    resolved_path.canonicalize().unwrap_or(resolved_path);
+                imports.symbols.push((
+                    canonical_path.to_string_lossy().to_string(),
+                    name,
+                    kind,
+                ));
+            } else {
+                imports.unresolved.extend(imported_names(&import_path));
+            }
+        }
+        imports
+    }
+
+    fn add_import(
+        &self,
+        imports: &mut TypescriptImports,
+        import_path: &str,
+        kind: EdgeKind,
+    ) {
         if let Some(resolved_path) = self.resolve_import_path(import_path) {
             // Canonicalize the final resolved path to ensure consistency
-            let canonical_path = resolved_path
-                .canonicalize()
-                .unwrap_or_else(|_| resolved_path);
-            self.imports
-                .push(canonical_path.to_string_lossy().to_string());
+            let canonical_path =
+                resolved_path.canonicalize().unwrap_or(resolved_path);
+            imports
+                .files
+                .push((canonical_path.to_string_lossy().to_string(), kind));
+            return;
+        }
+        imports.unresolved.extend(imported_names(import_path));
+        if let Some((package_name, _)) = split_specifier(import_path) {
+            if is_installed_package(&self.current_file_dir, &package_name) {
+                imports.external_packages.push((package_name, kind));
+            }
         }
     }
 }
 
+/// The names of the module a specifier imports, e.g. `button.styles` and
+/// `button` for `./button.styles`.
+fn imported_names(import_path: &str) -> Vec<String> {
+    let Some(last) = import_path
+        .rsplit('/')
+        .next()
+        .filter(|last| !last.is_empty())
+    else {
+        return Vec::new();
+    };
+    let mut names = vec![last.to_string()];
+    // `./button.js` may load `button.ts`
+    names.extend(
+        Path::new(last)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|stem| *stem != last)
+            .map(str::to_string),
+    );
+    names
+}
+
+#[derive(Default)]
+struct ImportVisitor {
+    parsed: ParsedTypescriptFile,
+}
+
+impl ImportVisitor {
+    fn add_symbol_import(&mut self, import_path: &str, name: &str, kind: EdgeKind) {
+        self.parsed.symbol_imports.push((
+            import_path.to_string(),
+            name.to_string(),
+            kind,
+        ));
+    }
+
+    /// Records the symbols exported by the file, and the symbols it uses from
+    /// the files it imports. Imported bindings that are never referenced are
+    /// left out.
+    fn visit_symbols(&mut self, module_record: &ModuleRecord, scoping: &Scoping) {
+        for entry in &module_record.local_export_entries {
+            let name = match &entry.export_name {
+                ExportExportName::Name(name) => name.name.to_string(),
+                ExportExportName::Default(_) => "default".to_string(),
+                ExportExportName::Null => continue,
+            };
+            let kind = match &entry.local_name {
+                ExportLocalName::Name(local) | ExportLocalName::Default(local) => {
+                    scoping
+                        .get_root_binding(local.name.as_str())
+                        .map(|symbol_id| {
+                            symbol_kind(scoping.symbol_flags(symbol_id))
+                        })
+                }
+                ExportLocalName::Null => None,
+            };
+            self.parsed
+                .exports
+                .push((name, kind.unwrap_or(SymbolKind::Variable)));
+        }
+
+        for entry in &module_record.import_entries {
+            let name = match &entry.import_name {
+                ImportImportName::Name(name) => name.name.as_str(),
+                ImportImportName::Default(_) => "default",
+                // Namespace imports use the whole file
+                ImportImportName::NamespaceObject => continue,
+            };
+            let is_used = scoping
+                .get_root_binding(entry.local_name.name.as_str())
+                .is_some_and(|symbol_id| {
+                    !scoping.get_resolved_reference_ids(symbol_id).is_empty()
+                });
+            if !is_used {
+                continue;
+            }
+            let kind = if entry.is_type {
+                EdgeKind::TypeImport
+            } else {
+                EdgeKind::StaticImport
+            };
+            self.add_symbol_import(entry.module_request.name.as_str(), name, kind);
+        }
+
+        // `export { a } from "./a"`
+        for entry in &module_record.indirect_export_entries {
+            if let (Some(module_request), ExportImportName::Name(name)) =
+                (&entry.module_request, &entry.import_name)
+            {
+                self.add_symbol_import(
+                    module_request.name.as_str(),
+                    name.name.as_str(),
+                    EdgeKind::ReExport,
+                );
+            }
+        }
+    }
+
+    fn add_import(&mut self, import_path: &str, kind: EdgeKind) {
+        self.parsed.imports.push((import_path.to_string(), kind));
+    }
+}
+
 impl<'a> Visit<'a> for ImportVisitor {
     fn visit_import_declaration(&mut self, decl: &ImportDeclaration<'a>) {
+        // `import type { A } from "./a"` and `import { type A } from "./a"`
+        let is_type_only = decl.import_kind.is_type()
+            || decl.specifiers.as_ref().is_some_and(|specifiers| {
+                !specifiers.is_empty()
+                    && specifiers.iter().all(|specifier| {
+                        matches!(
+                            specifier,
+                            ImportDeclarationSpecifier::ImportSpecifier(specifier)
+                                if specifier.import_kind.is_type()
+                        )
+                    })
+            });
+        let kind = if is_type_only {
+            EdgeKind::TypeImport
+        } else {
+            EdgeKind::StaticImport
+        };
         let import_path = decl.source.value.as_str();
-        self.add_import(import_path);
+        self.add_import(import_path, kind);
     }
 
     fn visit_export_all_declaration(&mut self, decl: &ExportAllDeclaration<'a>) {
         let import_path = decl.source.value.as_str();
-        self.add_import(import_path);
+        self.add_import(import_path, EdgeKind::ReExport);
     }
 
     fn visit_export_named_declaration(&mut self, decl: &ExportNamedDeclaration<'a>) {
         if let Some(source) = &decl.source {
             let import_path = source.value.as_str();
-            self.add_import(import_path);
+            self.add_import(import_path, EdgeKind::ReExport);
         }
     }
 
-    fn visit_call_expression(&mut self, expr: &CallExpression<'a>) {
+    fn visit_import_expression(&mut self, expr: &ImportExpression<'a>) {
         // Handle dynamic imports: import("./module")
-        if let Expression::ImportExpression(_) = &expr.callee {
-            if let Some(first_arg) = expr.arguments.first() {
-                if let Argument::StringLiteral(str_lit) = first_arg {
-                    let import_path = str_lit.value.as_str();
-                    self.add_import(import_path);
-                }
-            }
+        if let Expression::StringLiteral(str_lit) = &expr.source {
+            let import_path = str_lit.value.as_str();
+            self.add_import(import_path, EdgeKind::DynamicImport);
         }
 
-        // Handle require calls: require("./module")
+        // Continue visiting child nodes
+        walk::walk_import_expression(self, expr);
+    }
+
+    fn visit_call_expression(&mut self, expr: &CallExpression<'a>) {
+        // Handle require calls: require("./module"), also when followed by
+        // a member access, e.g. require("./module").default
         if let Expression::Identifier(ident) = &expr.callee {
             if ident.name == "require" {
                 if let Some(first_arg) = expr.arguments.first() {
                     if let Argument::StringLiteral(str_lit) = first_arg {
                         let import_path = str_lit.value.as_str();
-                        self.add_import(import_path);
+                        self.add_import(import_path, EdgeKind::Require);
                     }
                 }
             }
@@ -151,44 +357,29 @@ fn visit_call_expression(&mut self, expr: &CallExpression<'a>) {
             self.visit_argument(arg);
         }
     }
+}
 
-    fn visit_member_expression(&mut self, expr: &MemberExpression<'a>) {
-        // Handle require calls through member expressions: require("./module").default
-        if let MemberExpression::StaticMemberExpression(static_expr) = expr {
-            if let Expression::CallExpression(call_expr) = &static_expr.object {
-                if let Expression::Identifier(ident) = &call_expr.callee {
-                    if ident.name == "require" {
-                        if let Some(first_arg) = call_expr.arguments.first() {
-                            if let Argument::StringLiteral(str_lit) = first_arg {
-                                let import_path = str_lit.value.as_str();
-                                self.add_import(import_path);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Continue visiting child nodes
-        match expr {
-            MemberExpression::ComputedMemberExpression(computed) => {
-                self.visit_expression(&computed.object);
-                self.visit_expression(&computed.expression);
-            }
-            MemberExpression::StaticMemberExpression(static_expr) => {
-                self.visit_expression(&static_expr.object);
-            }
-            MemberExpression::PrivateFieldExpression(private) => {
-                self.visit_expression(&private.object);
-            }
-        }
+fn symbol_kind(flags: SymbolFlags) -> SymbolKind {
+    if flags.contains(SymbolFlags::Function) {
+        SymbolKind::Function
+    } else if flags.contains(SymbolFlags::Class) {
+        SymbolKind::Class
+    } else if flags.contains(SymbolFlags::Interface) {
+        SymbolKind::Interface
+    } else if flags.contains(SymbolFlags::TypeAlias) {
+        SymbolKind::TypeAlias
+    } else if flags.intersects(SymbolFlags::Enum) {
+        SymbolKind::Enum
+    } else {
+        SymbolKind::Variable
     }
 }
 
 fn parse_typescript_file(
     file_path: &Path,
-) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let source_code = fs::read_to_string(file_path)?;
+    source_code: &str,
+    symbols: bool,
+) -> Result<ParsedTypescriptFile, ParseError> {
     let allocator = Allocator::default();
 
     // Determine source type based on file extension
@@ -203,8 +394,11 @@ fn parse_typescript_file(
     };
 
     let ParserReturn {
-        program, errors, ..
-    } = Parser::new(&allocator, &source_code, source_type).parse();
+        program,
+        module_record,
+        errors,
+        ..
+    } = Parser::new(&allocator, source_code, source_type).parse();
 
     if !errors.is_empty() {
         // Log errors but continue processing
@@ -213,55 +407,144 @@ fn parse_typescript_file(
         }
     }
 
-    let mut visitor = ImportVisitor::new(file_path);
+    let mut visitor = ImportVisitor::default();
     visitor.visit_program(&program);
 
-    Ok(visitor.imports)
+    if symbols {
+        let semantic = SemanticBuilder::new().build(&program).semantic;
+        visitor.visit_symbols(&module_record, semantic.scoping());
+    }
+
+    Ok(visitor.parsed)
 }
 
-fn find_typescript_files(project_path: &str) -> Vec<PathBuf> {
-    WalkDir::new(project_path)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            entry.file_type().is_file()
-                && entry
-                    .path()
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .map(|ext| {
-                        matches!(ext, "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs")
-                    })
-                    .unwrap_or(false)
-        })
-        .filter(|entry| {
-            // Skip node_modules and other common directories to ignore
-            !entry.path().components().any(|component| {
-                matches!(
-                    component.as_os_str().to_str(),
-                    Some("node_modules")
-                        | Some(".git")
-                        | Some("dist")
-                        | Some("build")
-                        | Some("coverage")
-                )
-            })
-        })
-        .map(|entry| entry.path().to_path_buf())
+fn is_typescript_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|ext| matches!(ext, "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs"))
+        .unwrap_or(false)
+}
+
+/// Finds the file an import of `path` loads: the file itself, the file with
+/// a TypeScript/JavaScript extension, or the index file of the directory.
+pub(super) fn resolve_module_file(path: &Path) -> Option<PathBuf> {
+    if path.is_file() && is_typescript_file(path) {
+        return Some(path.to_path_buf());
+    }
+
+    // Try different extensions, appended (`./button.styles` -> `button.styles.ts`)
+    // or replacing the imported one (`./button.js` -> `button.ts`)
+    for ext in &[".ts", ".tsx", ".js", ".jsx"] {
+        let mut with_ext = path.as_os_str().to_owned();
+        with_ext.push(ext);
+        let with_ext = PathBuf::from(with_ext);
+        if with_ext.is_file() {
+            return Some(with_ext);
+        }
+    }
+    for ext in &[".ts", ".tsx", ".js", ".jsx"] {
+        let with_ext = path.with_extension(&ext[1..]);
+        if with_ext.is_file() {
+            return Some(with_ext);
+        }
+    }
+
+    // Try index files
+    for ext in &[".ts", ".tsx", ".js", ".jsx"] {
+        let index_file = path.join(format!("index{}", ext));
+        if index_file.is_file() {
+            return Some(index_file);
+        }
+    }
+
+    None
+}
+
+fn find_typescript_files(project_path: &Path) -> Vec<PathBuf> {
+    workspace_files(project_path)
+        .filter(|file_path| is_typescript_file(file_path))
         .collect()
 }
 
-pub fn feed_workspace_graph_with_ts_project(
+#[derive(Default)]
+pub struct TypescriptFeeder {
+    /// Whether imported `node_modules` packages show up in the graph, each
+    /// collapsed into a single external node.
+    pub external_packages: bool,
+    /// Whether exported symbols show up in the graph as child nodes of their
+    /// file, linked to the files using them.
+    pub symbols: bool,
+    /// The packages of the pnpm/yarn/npm workspace.
+    pub(super) packages: Projects<WorkspacePackages>,
+    pub(super) unresolved: UnresolvedImports,
+}
+
+impl Feeder for TypescriptFeeder {
+    fn name(&self) -> &'static str {
+        "typescript"
+    }
+
+    fn handles(&self, file_path: &Path) -> bool {
+        is_typescript_file(file_path)
+    }
+
+    fn manifests(&self) -> &'static [&'static str] {
+        &["package.json", "pnpm-workspace.yaml"]
+    }
+
+    fn feed(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        progress: &Progress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let packages = self
+            .packages
+            .read(|| WorkspacePackages::discover(workspace_path));
+        feed_workspace_graph_with_ts_project(
+            graph,
+            workspace_path,
+            &packages,
+            &self.unresolved,
+            self.external_packages,
+            self.symbols,
+            progress,
+        )
+    }
+
+    fn update(
+        &self,
+        graph: &mut WorkspaceGraph,
+        workspace_path: &Path,
+        file_paths: &[PathBuf],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let packages = self
+            .packages
+            .get(|| WorkspacePackages::discover(workspace_path));
+        update_workspace_graph_with_ts_files(
+            graph,
+            workspace_path,
+            &packages,
+            &self.unresolved,
+            file_paths,
+            self.external_packages,
+            self.symbols,
+        )
+    }
+}
+
+fn feed_workspace_graph_with_ts_project(
     graph: &mut WorkspaceGraph,
-    project_path: &str,
+    project_path: &Path,
+    packages: &WorkspacePackages,
+    unresolved: &UnresolvedImports,
+    external_packages: bool,
+    symbols: bool,
+    progress: &Progress,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let project_path = Path::new(project_path);
-
     // Find all TypeScript/JavaScript files
-    let typescript_files = find_typescript_files(project_path.to_str().unwrap());
-
-    // Map to store file paths to node indices
-    let mut file_to_node: HashMap<String, NodeIndex> = HashMap::new();
+    let typescript_files = find_typescript_files(project_path);
 
     // First pass: Add all files as nodes
     for file_path in &typescript_files {
@@ -270,39 +553,396 @@ pub fn feed_workspace_graph_with_ts_project(
             .unwrap_or_else(|_| file_path.clone());
         let file_path_str = canonical_path.to_string_lossy().to_string();
 
-        // Assuming your File struct has a way to be created from a path
-        // You'll need to adjust this based on your File implementation
-        let file = File::new(file_path_str.clone()); // Adjust constructor as needed
+        graph.add_file(File::new(file_path_str));
+    }
+
+    // Second pass: Parse imports
+    let cache = parse_cache(project_path, symbols);
+    let mut parsed_files = parse_typescript_files(
+        &typescript_files,
+        project_path,
+        packages,
+        symbols,
+        &cache,
+        progress,
+    );
+    cache.prune();
+    unresolved.clear();
+    keep_unresolved_imports(unresolved, &mut parsed_files);
+
+    // Third pass: Add exported symbols, which must all be known before
+    // linking them
+    add_exported_symbols(graph, &parsed_files);
+
+    // Fourth pass: Add edges
+    add_imports(graph, parsed_files, external_packages);
+
+    Ok(())
+}
 
-        let node_index = graph.add_file(file);
-        file_to_node.insert(file_path_str, node_index);
+/// Updates the graph after `file_paths` were created, modified or removed.
+///
+/// Only the changed files and the files importing them are parsed again.
+/// Files whose imports did not resolve to a module named like a created file
+/// are resolved again too, since they may import it now.
+fn update_workspace_graph_with_ts_files(
+    graph: &mut WorkspaceGraph,
+    project_path: &Path,
+    packages: &WorkspacePackages,
+    unresolved: &UnresolvedImports,
+    file_paths: &[PathBuf],
+    external_packages: bool,
+    symbols: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (file_paths, removed_paths): (Vec<&PathBuf>, Vec<&PathBuf>) =
+        file_paths.iter().partition(|file_path| file_path.is_file());
+    for file_path in removed_paths {
+        graph.remove_file(&file_path.to_string_lossy());
+        unresolved.remove(file_path);
     }
 
-    // Second pass: Parse imports and add edges
-    for file_path in &typescript_files {
+    let cache = parse_cache(project_path, symbols);
+    let mut affected_files = Vec::new();
+    let mut created_files = Vec::new();
+    for file_path in file_paths {
         let canonical_path = file_path
             .canonicalize()
             .unwrap_or_else(|_| file_path.clone());
-        let file_path_str = canonical_path.to_string_lossy().to_string();
+        let file_name = canonical_path.to_string_lossy().to_string();
+        if graph.find_file(&file_name).is_none() {
+            created_files.push(canonical_path.clone());
+        }
+        let current_node = graph.add_file(File::new(file_name));
 
-        match parse_typescript_file(file_path) {
-            Ok(imports) => {
-                if let Some(&current_node) = file_to_node.get(&file_path_str) {
-                    for import_path in imports {
-                        // file_to_node.get(&import_path)
-                        if let Some(&imported_node) = file_to_node.get(&import_path)
-                        {
-                            graph.add_import(current_node, imported_node);
-                        }
-                    }
+        // The symbols they use from the changed file may have changed
+        for importer in graph.importers_of(current_node) {
+            let importer = &graph.graph[importer];
+            if importer.symbol.is_none() && !importer.external {
+                affected_files.push(PathBuf::from(&importer.name));
+            }
+        }
+        affected_files.push(canonical_path);
+    }
+    affected_files
+        .extend(unresolved.importers_of(created_files.iter().map(PathBuf::as_path)));
+    affected_files.sort();
+    affected_files.dedup();
+
+    for file_path in &affected_files {
+        if let Some(node_idx) = graph.find_file(&file_path.to_string_lossy()) {
+            graph.remove_imports_from(node_idx);
+        }
+    }
+    let mut parsed_files = parse_typescript_files(
+        &affected_files,
+        project_path,
+        packages,
+        symbols,
+        &cache,
+        &Progress::default(),
+    );
+    keep_unresolved_imports(unresolved, &mut parsed_files);
+
+    // Drop the symbols that are not exported anymore
+    for (file_path_str, imports) in &parsed_files {
+        let Some(node_idx) = graph.find_file(file_path_str) else {
+            continue;
+        };
+        let removed_symbols: Vec<String> = graph
+            .symbols_of(node_idx)
+            .iter()
+            .map(|symbol_idx| &graph.graph[*symbol_idx])
+            .filter(|symbol| {
+                symbol.symbol.as_ref().is_some_and(|symbol| {
+                    !imports.exports.iter().any(|(name, _)| *name == symbol.name)
+                })
+            })
+            .map(|symbol| symbol.name.clone())
+            .collect();
+        for symbol_name in removed_symbols {
+            graph.remove_file(&symbol_name);
+        }
+    }
+    add_exported_symbols(graph, &parsed_files);
+    add_imports(graph, parsed_files, external_packages);
+
+    // Drop the packages that are not imported anymore
+    let unused_packages: Vec<String> = graph
+        .graph
+        .node_indices()
+        .filter(|node_idx| {
+            graph.graph[*node_idx].external
+                && graph
+                    .graph
+                    .neighbors_directed(*node_idx, petgraph::Direction::Incoming)
+                    .next()
+                    .is_none()
+        })
+        .map(|node_idx| graph.graph[node_idx].name.clone())
+        .collect();
+    for package_name in unused_packages {
+        graph.remove_file(&package_name);
+    }
+
+    Ok(())
+}
+
+/// Keeps the modules the parsed files import without resolving them, to
+/// resolve the files again once a module of that name is created.
+fn keep_unresolved_imports(
+    unresolved: &UnresolvedImports,
+    parsed_files: &mut [(String, TypescriptImports)],
+) {
+    for (file_path_str, imports) in parsed_files {
+        unresolved.set(
+            PathBuf::from(&*file_path_str),
+            std::mem::take(&mut imports.unresolved),
+        );
+    }
+}
+
+/// The cache of the parsed files, which are parsed differently depending on
+/// whether symbols are collected.
+fn parse_cache(project_path: &Path, symbols: bool) -> ParseCache {
+    ParseCache::new(
+        project_path,
+        if symbols {
+            "typescript-symbols"
+        } else {
+            "typescript"
+        },
+    )
+}
+
+/// Parses `typescript_files` in parallel, reusing the results cached for the
+/// files that did not change, then resolves their imports.
+fn parse_typescript_files(
+    typescript_files: &[PathBuf],
+    project_path: &Path,
+    packages: &WorkspacePackages,
+    symbols: bool,
+    cache: &ParseCache,
+    progress: &Progress,
+) -> Vec<(String, TypescriptImports)> {
+    progress.add_files(typescript_files.len());
+    let parsed_files: Vec<(&PathBuf, ParsedTypescriptFile)> = typescript_files
+        .par_iter()
+        .filter_map(|file_path| {
+            let parsed = cache.get_or_parse(file_path, |source_code| {
+                parse_typescript_file(file_path, source_code, symbols)
+            });
+            progress.file_indexed();
+            match parsed {
+                Ok(parsed) => Some((file_path, parsed)),
+                Err(e) => {
+                    eprintln!("Failed to parse {}: {}", file_path.display(), e);
+                    // Continue with other files even if one fails
+                    None
                 }
             }
-            Err(e) => {
-                eprintln!("Failed to parse {}: {}", file_path_str, e);
-                // Continue with other files even if one fails
+        })
+        .collect();
+
+    // Resolving reads the tsconfig files, which are shared between files
+    let mut tsconfigs = TsConfigs::new(project_path);
+    parsed_files
+        .into_iter()
+        .map(|(file_path, parsed)| {
+            let canonical_path = file_path
+                .canonicalize()
+                .unwrap_or_else(|_| file_path.clone());
+            let resolver = ImportResolver::new(
+                file_path,
+                tsconfigs.for_file(file_path),
+                packages,
+            );
+            (
+                canonical_path.to_string_lossy().to_string(),
+                resolver.resolve(parsed),
+            )
+        })
+        .collect()
+}
+
+fn add_exported_symbols(
+    graph: &mut WorkspaceGraph,
+    parsed_files: &[(String, TypescriptImports)],
+) {
+    for (file_path_str, imports) in parsed_files {
+        for (name, kind) in &imports.exports {
+            graph.add_symbol(Symbol {
+                file: file_path_str.clone(),
+                name: name.clone(),
+                kind: *kind,
+            });
+        }
+    }
+}
+
+fn add_imports(
+    graph: &mut WorkspaceGraph,
+    parsed_files: Vec<(String, TypescriptImports)>,
+    external_packages: bool,
+) {
+    for (file_path_str, imports) in parsed_files {
+        let Some(current_node) = graph.find_file(&file_path_str) else {
+            continue;
+        };
+        for (import_path, kind) in imports.files {
+            if let Some(imported_node) = graph.find_file(&import_path) {
+                graph.add_import(current_node, imported_node, kind);
+            }
+        }
+        for (import_path, name, kind) in imports.symbols {
+            if let Some(symbol_node) = graph.find_symbol(&import_path, &name) {
+                graph.add_import(current_node, symbol_node, kind);
+            }
+        }
+        if external_packages {
+            for (package_name, kind) in imports.external_packages {
+                let package_node = graph.add_file(File::new_external(package_name));
+                graph.add_import(current_node, package_node, kind);
             }
         }
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace_graph::feeder::test_utils::write;
+
+    fn parse(source_code: &str, symbols: bool) -> ParsedTypescriptFile {
+        parse_typescript_file(Path::new("main.ts"), source_code, symbols).unwrap()
+    }
+
+    #[test]
+    fn test_import_edge_kinds() {
+        let parsed = parse(
+            r#"
+            import a from "./static";
+            import type { B } from "./type";
+            import { type C } from "./inline-type";
+            const d = import("./dynamic");
+            const e = require("./require").default;
+            export { f } from "./re-export";
+            export * from "./re-export-all";
+            "#,
+            false,
+        );
+
+        assert_eq!(
+            parsed.imports,
+            vec![
+                ("./static".to_string(), EdgeKind::StaticImport),
+                ("./type".to_string(), EdgeKind::TypeImport),
+                ("./inline-type".to_string(), EdgeKind::TypeImport),
+                ("./dynamic".to_string(), EdgeKind::DynamicImport),
+                ("./require".to_string(), EdgeKind::Require),
+                ("./re-export".to_string(), EdgeKind::ReExport),
+                ("./re-export-all".to_string(), EdgeKind::ReExport),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exported_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let lib = write(
+            &root,
+            "lib.ts",
+            r#"
+            export function f() {}
+            export class C {}
+            export interface I {}
+            export type T = string;
+            export enum E { A }
+            export const v = 1;
+            "#,
+        );
+        let main = write(
+            &root,
+            "main.ts",
+            r#"
+            import { f, v } from "./lib";
+            f();
+            "#,
+        );
+        let packages = WorkspacePackages::discover(&root);
+        let mut graph = WorkspaceGraph::new();
+        let parsed_files: Vec<_> = [&lib, &main]
+            .into_iter()
+            .map(|file_path| {
+                let source_code = std::fs::read_to_string(file_path).unwrap();
+                let parsed = parse_typescript_file(file_path, &source_code, true);
+                let resolver = ImportResolver::new(file_path, None, &packages);
+                let name = file_path.to_string_lossy().to_string();
+                graph.add_file(File::new(name.clone()));
+                (name, resolver.resolve(parsed.unwrap()))
+            })
+            .collect();
+        add_exported_symbols(&mut graph, &parsed_files);
+        add_imports(&mut graph, parsed_files, false);
+
+        let lib_name = lib.to_string_lossy();
+        let lib_index = graph.find_file(&lib_name).unwrap();
+        let mut symbols: Vec<_> = graph
+            .symbols_of(lib_index)
+            .iter()
+            .filter_map(|index| graph.graph[*index].symbol.clone())
+            .map(|symbol| (symbol.name, symbol.kind))
+            .collect();
+        symbols.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            symbols,
+            vec![
+                ("C".to_string(), SymbolKind::Class),
+                ("E".to_string(), SymbolKind::Enum),
+                ("I".to_string(), SymbolKind::Interface),
+                ("T".to_string(), SymbolKind::TypeAlias),
+                ("f".to_string(), SymbolKind::Function),
+                ("v".to_string(), SymbolKind::Variable),
+            ]
+        );
+
+        // Only the imported symbols the file uses are linked
+        let main_index = graph.find_file(&main.to_string_lossy()).unwrap();
+        let edge_kinds = |to: petgraph::graph::NodeIndex| -> Vec<EdgeKind> {
+            graph
+                .graph
+                .edges_connecting(main_index, to)
+                .map(|edge| edge.weight().kind)
+                .collect()
+        };
+        assert_eq!(edge_kinds(lib_index), vec![EdgeKind::StaticImport]);
+        let used = graph.find_symbol(&lib_name, "f").unwrap();
+        assert_eq!(edge_kinds(used), vec![EdgeKind::StaticImport]);
+        let unused = graph.find_symbol(&lib_name, "v").unwrap();
+        assert!(edge_kinds(unused).is_empty());
+    }
+
+    #[test]
+    fn test_unresolved_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        write(&root, "lib.ts", "");
+        let main = write(
+            &root,
+            "main.ts",
+            r#"
+            import "./lib";
+            import "./button.js";
+            import "../shared/utils";
+            "#,
+        );
+        let packages = WorkspacePackages::discover(&root);
+        let source_code = std::fs::read_to_string(&main).unwrap();
+        let parsed = parse_typescript_file(&main, &source_code, false).unwrap();
+        let imports = ImportResolver::new(&main, None, &packages).resolve(parsed);
+
+        let mut unresolved: Vec<_> = imports.unresolved.into_iter().collect();
+        unresolved.sort();
+        assert_eq!(unresolved, vec!["button", "button.js", "utils"]);
+    }
 }
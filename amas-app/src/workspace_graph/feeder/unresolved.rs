@@ -0,0 +1,120 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// The names of the modules each file imports without them resolving to a
+/// file of the workspace, kept from the last pass so that creating a file
+/// only resolves again the files that may import it.
+#[derive(Default)]
+pub(super) struct UnresolvedImports {
+    names: Mutex<HashMap<PathBuf, HashSet<String>>>,
+}
+
+impl UnresolvedImports {
+    fn names(&self) -> MutexGuard<'_, HashMap<PathBuf, HashSet<String>>> {
+        // The names are replaced file by file, so they stay usable
+        self.names.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(super) fn clear(&self) {
+        self.names().clear();
+    }
+
+    /// Replaces the names of the modules `file_path` imports without
+    /// resolving them.
+    pub(super) fn set(&self, file_path: PathBuf, names: HashSet<String>) {
+        if names.is_empty() {
+            self.names().remove(&file_path);
+        } else {
+            self.names().insert(file_path, names);
+        }
+    }
+
+    pub(super) fn remove(&self, file_path: &Path) {
+        self.names().remove(file_path);
+    }
+
+    /// The files with an unresolved import of a module named like one of
+    /// `created_files`.
+    pub(super) fn importers_of<'p>(
+        &self,
+        created_files: impl IntoIterator<Item = &'p Path>,
+    ) -> Vec<PathBuf> {
+        let created_names: HashSet<&str> =
+            created_files.into_iter().flat_map(module_names).collect();
+        if created_names.is_empty() {
+            return Vec::new();
+        }
+        self.names()
+            .iter()
+            .filter(|(_, names)| {
+                names
+                    .iter()
+                    .any(|name| created_names.contains(name.as_str()))
+            })
+            .map(|(file_path, _)| file_path.clone())
+            .collect()
+    }
+}
+
+/// The names a module file may be imported by: its stem, and the name of its
+/// directory for the `index`, `__init__` and `mod` files standing for it.
+fn module_names(file_path: &Path) -> Vec<&str> {
+    let Some(stem) = file_path.file_stem().and_then(|s| s.to_str()) else {
+        return Vec::new();
+    };
+    let mut names = vec![stem];
+    if matches!(stem, "index" | "__init__" | "mod") {
+        names.extend(
+            file_path
+                .parent()
+                .and_then(Path::file_name)
+                .and_then(|s| s.to_str()),
+        );
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_importers_of() {
+        let unresolved = UnresolvedImports::default();
+        unresolved.set(PathBuf::from("/ws/main.ts"), names(&["button", "react"]));
+        unresolved.set(PathBuf::from("/ws/app.py"), names(&["models"]));
+        unresolved.set(PathBuf::from("/ws/lib.rs"), names(&["parser"]));
+
+        assert_eq!(
+            unresolved.importers_of([Path::new("/ws/button.tsx")]),
+            vec![PathBuf::from("/ws/main.ts")]
+        );
+        // Packages are imported by the name of their directory
+        assert_eq!(
+            unresolved.importers_of([Path::new("/ws/models/__init__.py")]),
+            vec![PathBuf::from("/ws/app.py")]
+        );
+        assert_eq!(
+            unresolved.importers_of([Path::new("/ws/parser/mod.rs")]),
+            vec![PathBuf::from("/ws/lib.rs")]
+        );
+        assert!(
+            unresolved
+                .importers_of([Path::new("/ws/other.ts")])
+                .is_empty()
+        );
+
+        // Files whose imports all resolved are forgotten
+        unresolved.set(PathBuf::from("/ws/main.ts"), HashSet::new());
+        assert!(
+            unresolved
+                .importers_of([Path::new("/ws/button.tsx")])
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+
+use super::WorkspaceGraph;
+use super::feeder::FeederRegistry;
+
+/// How many files were indexed out of the files found so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexingProgress {
+    pub indexed_files: usize,
+    pub total_files: usize,
+}
+
+/// Counts the files indexed by the feeders, which may run on several threads,
+/// and reports the count to the UI.
+#[derive(Debug, Default)]
+pub struct Progress {
+    indexed_files: AtomicUsize,
+    total_files: AtomicUsize,
+    tx: Option<Sender<IndexingProgress>>,
+}
+
+impl Progress {
+    pub fn new(tx: Sender<IndexingProgress>) -> Self {
+        Self {
+            tx: Some(tx),
+            ..Default::default()
+        }
+    }
+
+    /// Announces `count` more files to index.
+    pub fn add_files(&self, count: usize) {
+        self.total_files.fetch_add(count, Ordering::Relaxed);
+        self.report();
+    }
+
+    pub fn file_indexed(&self) {
+        let indexed_files = self.indexed_files.fetch_add(1, Ordering::Relaxed) + 1;
+        // Reporting every file would flood the UI thread
+        if indexed_files.is_multiple_of(64) {
+            self.report();
+        }
+    }
+
+    fn report(&self) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(IndexingProgress {
+                indexed_files: self.indexed_files.load(Ordering::Relaxed),
+                total_files: self.total_files.load(Ordering::Relaxed),
+            });
+        }
+    }
+}
+
+/// Feeds a new graph with the workspace at `workspace_path` on a background
+/// thread, reporting the progress to `progress_tx`, then hands the graph to
+/// `on_indexed`.
+pub fn index_workspace(
+    registry: Arc<FeederRegistry>,
+    workspace_path: PathBuf,
+    progress_tx: Sender<IndexingProgress>,
+    on_indexed: impl FnOnce(WorkspaceGraph) + Send + 'static,
+) {
+    let spawned = std::thread::Builder::new()
+        .name("AmasIndexer".to_owned())
+        .spawn(move || {
+            let progress = Progress::new(progress_tx);
+            let mut graph = WorkspaceGraph::new();
+            registry.feed(&mut graph, &workspace_path, &progress);
+            on_indexed(graph);
+        });
+    if let Err(e) = spawned {
+        eprintln!("Failed to start indexing: {}", e);
+    }
+}
+
+/// Updates `graph` on a background thread after `file_paths` were created,
+/// modified or removed, then hands it back to `on_updated`.
+pub fn update_workspace(
+    registry: Arc<FeederRegistry>,
+    workspace_path: PathBuf,
+    mut graph: WorkspaceGraph,
+    file_paths: Vec<PathBuf>,
+    on_updated: impl FnOnce(WorkspaceGraph) + Send + 'static,
+) {
+    let spawned = std::thread::Builder::new()
+        .name("AmasUpdater".to_owned())
+        .spawn(move || {
+            registry.update(&mut graph, &workspace_path, &file_paths);
+            on_updated(graph);
+        });
+    if let Err(e) = spawned {
+        eprintln!("Failed to start updating the graph: {}", e);
+    }
+}
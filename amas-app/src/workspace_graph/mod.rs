@@ -1,4 +1,10 @@
+mod edge;
+mod indexer;
+mod watcher;
 mod workspace_graph;
 
 pub mod feeder;
+pub use edge::{Edge, EdgeKind};
+pub use indexer::{IndexingProgress, Progress, index_workspace, update_workspace};
+pub use watcher::watch_workspace;
 pub use workspace_graph::WorkspaceGraph;
@@ -0,0 +1,72 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::time::Duration;
+
+use notify::Watcher as _;
+
+use super::feeder::is_ignored;
+
+/// How long the workspace must stay quiet before changed files are sent.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
+
+/// Watches the files of the workspace at `workspace_path` from a background
+/// thread, and sends the paths of the created, modified and removed files.
+///
+/// Changes are sent in batches, so that a burst of events (e.g. a `git
+/// checkout`) leads to a single update of the graph.
+pub fn watch_workspace(workspace_path: &Path) -> Receiver<Vec<PathBuf>> {
+    let (tx, rx) = channel();
+    let workspace_path = workspace_path.to_path_buf();
+    let spawned = std::thread::Builder::new()
+        .name("AmasWorkspaceWatcher".to_owned())
+        .spawn(move || {
+            if let Err(e) = watch(&workspace_path, tx) {
+                eprintln!("Failed to watch {}: {}", workspace_path.display(), e);
+            }
+        });
+    if let Err(e) = spawned {
+        eprintln!("Failed to start the workspace watcher: {}", e);
+    }
+    rx
+}
+
+fn watch(workspace_path: &Path, tx: Sender<Vec<PathBuf>>) -> notify::Result<()> {
+    let (event_tx, event_rx) = channel();
+    let mut watcher = notify::recommended_watcher(event_tx)?;
+    watcher.watch(workspace_path, notify::RecursiveMode::Recursive)?;
+
+    let mut changed_paths = BTreeSet::new();
+    loop {
+        let event = if changed_paths.is_empty() {
+            event_rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            event_rx.recv_timeout(DEBOUNCE_DELAY)
+        };
+        match event {
+            Ok(Ok(event)) => match event.kind {
+                notify::EventKind::Create(_)
+                | notify::EventKind::Modify(_)
+                | notify::EventKind::Remove(_) => {
+                    changed_paths.extend(event.paths.into_iter().filter(|path| {
+                        path.strip_prefix(workspace_path)
+                            .is_ok_and(|relative_path| !is_ignored(relative_path))
+                    }));
+                }
+                _ => {}
+            },
+            Ok(Err(e)) => {
+                eprintln!("Failed to watch {}: {}", workspace_path.display(), e)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let file_paths =
+                    std::mem::take(&mut changed_paths).into_iter().collect();
+                if tx.send(file_paths).is_err() {
+                    // Nobody listens to the changes anymore
+                    return Ok(());
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
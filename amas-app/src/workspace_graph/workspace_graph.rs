@@ -1,22 +1,261 @@
-use crate::file::File;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::{Edge, EdgeKind};
+use crate::file::{File, Symbol};
 
 #[derive(Debug, Clone)]
 pub struct WorkspaceGraph {
-    pub graph: petgraph::Graph<File, f64, petgraph::Undirected>,
+    /// A stable graph, so that node indices survive the removal of files.
+    pub graph: petgraph::stable_graph::StableDiGraph<File, Edge>,
+    file_indices: HashMap<String, petgraph::graph::NodeIndex>,
+    /// The symbol nodes of each file node.
+    symbols: HashMap<petgraph::graph::NodeIndex, Vec<petgraph::graph::NodeIndex>>,
 }
 
 impl WorkspaceGraph {
     pub fn new() -> Self {
         WorkspaceGraph {
-            graph: petgraph::Graph::new_undirected(),
+            graph: petgraph::stable_graph::StableDiGraph::new(),
+            file_indices: HashMap::new(),
+            symbols: HashMap::new(),
         }
     }
 
+    /// Adds `file` to the graph, or returns the existing node if a file with
+    /// the same name was already added (e.g. by another feeder).
     pub fn add_file(&mut self, file: File) -> petgraph::graph::NodeIndex {
-        self.graph.add_node(file)
+        if let Some(&node_index) = self.file_indices.get(&file.name) {
+            return node_index;
+        }
+        let name = file.name.clone();
+        let node_index = self.graph.add_node(file);
+        self.file_indices.insert(name, node_index);
+        node_index
+    }
+
+    /// Adds `symbol` as a child node of its file, which must already be in
+    /// the graph.
+    pub fn add_symbol(
+        &mut self,
+        symbol: Symbol,
+    ) -> Option<petgraph::graph::NodeIndex> {
+        let file_index = self.find_file(&symbol.file)?;
+        let symbol = File::new_symbol(symbol);
+        if let Some(&node_index) = self.file_indices.get(&symbol.name) {
+            return Some(node_index);
+        }
+        let node_index = self.add_file(symbol);
+        self.symbols.entry(file_index).or_default().push(node_index);
+        Some(node_index)
     }
 
-    pub fn add_import(&mut self, a: petgraph::graph::NodeIndex, b: petgraph::graph::NodeIndex) {
-        self.graph.add_edge(a, b, 1.0);
+    pub fn find_symbol(
+        &self,
+        file_name: &str,
+        symbol_name: &str,
+    ) -> Option<petgraph::graph::NodeIndex> {
+        self.find_file(&File::symbol_node_name(file_name, symbol_name))
+    }
+
+    pub fn symbols_of(
+        &self,
+        file_index: petgraph::graph::NodeIndex,
+    ) -> &[petgraph::graph::NodeIndex] {
+        self.symbols
+            .get(&file_index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn find_file(&self, name: &str) -> Option<petgraph::graph::NodeIndex> {
+        self.file_indices.get(name).copied()
+    }
+
+    /// Removes the node named `name` along with its edges, and its symbols
+    /// when it is a file.
+    pub fn remove_file(&mut self, name: &str) {
+        let Some(node_index) = self.file_indices.remove(name) else {
+            return;
+        };
+        for symbol_index in self.symbols.remove(&node_index).unwrap_or_default() {
+            if let Some(symbol) = self.graph.remove_node(symbol_index) {
+                self.file_indices.remove(&symbol.name);
+            }
+        }
+        if let Some(parent_symbols) = self
+            .graph
+            .remove_node(node_index)
+            .and_then(|file| file.symbol)
+            .and_then(|symbol| self.file_indices.get(&symbol.file).copied())
+            .and_then(|parent_index| self.symbols.get_mut(&parent_index))
+        {
+            parent_symbols.retain(|index| *index != node_index);
+        }
+    }
+
+    /// Removes the edges going out of `node_index`, before its imports are
+    /// added again.
+    pub fn remove_imports_from(&mut self, node_index: petgraph::graph::NodeIndex) {
+        let edges: Vec<_> = self
+            .graph
+            .edges_directed(node_index, petgraph::Direction::Outgoing)
+            .map(|edge| petgraph::visit::EdgeRef::id(&edge))
+            .collect();
+        for edge in edges {
+            self.graph.remove_edge(edge);
+        }
+    }
+
+    /// The nodes importing `node_index` or one of its symbols.
+    pub fn importers_of(
+        &self,
+        node_index: petgraph::graph::NodeIndex,
+    ) -> Vec<petgraph::graph::NodeIndex> {
+        let mut importers: Vec<_> = std::iter::once(node_index)
+            .chain(self.symbols_of(node_index).iter().copied())
+            .flat_map(|index| {
+                self.graph
+                    .neighbors_directed(index, petgraph::Direction::Incoming)
+            })
+            .collect();
+        importers.sort();
+        importers.dedup();
+        importers
+    }
+
+    /// Adds an edge from the importing file `from` to the imported file `to`.
+    /// Importing the same file several times with the same kind of import
+    /// increases the weight of a single edge.
+    pub fn add_import(
+        &mut self,
+        from: petgraph::graph::NodeIndex,
+        to: petgraph::graph::NodeIndex,
+        kind: EdgeKind,
+    ) {
+        if let Some(edge) = self
+            .graph
+            .edges_connecting(from, to)
+            .find(|edge| edge.weight().kind == kind)
+            .map(|edge| petgraph::visit::EdgeRef::id(&edge))
+        {
+            self.graph[edge].weight += 1.0;
+            return;
+        }
+        self.graph.add_edge(from, to, Edge { kind, weight: 1.0 });
+    }
+
+    /// A copy of the graph where the files under each of `directories`, and
+    /// their symbols, are merged into a single node for the directory. The
+    /// imports between merged files are dropped, the others are merged into
+    /// one edge per kind weighted by the number of imports it stands for.
+    pub fn with_collapsed_directories(
+        &self,
+        directories: &HashSet<String>,
+    ) -> WorkspaceGraph {
+        if directories.is_empty() {
+            return self.clone();
+        }
+
+        let mut collapsed = WorkspaceGraph::new();
+        let mut merged_into = HashMap::new();
+        // Files first, so that symbols can be added to their file
+        let (files, symbols): (Vec<_>, Vec<_>) = self
+            .graph
+            .node_indices()
+            .partition(|node_idx| self.graph[*node_idx].symbol.is_none());
+        for node_idx in files {
+            let file = &self.graph[node_idx];
+            let directory = (!file.external)
+                .then(|| collapsed_directory(&file.name, directories))
+                .flatten();
+            let new_idx = match directory {
+                Some(directory) => {
+                    collapsed.add_file(File::new_directory(directory.clone()))
+                }
+                None => collapsed.add_file(file.clone()),
+            };
+            merged_into.insert(node_idx, new_idx);
+        }
+        for node_idx in symbols {
+            let Some(symbol) = &self.graph[node_idx].symbol else {
+                continue;
+            };
+            let Some(file_idx) = self
+                .find_file(&symbol.file)
+                .and_then(|file_idx| merged_into.get(&file_idx).copied())
+            else {
+                continue;
+            };
+            let new_idx = if collapsed.graph[file_idx].directory {
+                Some(file_idx)
+            } else {
+                collapsed.add_symbol(symbol.clone())
+            };
+            if let Some(new_idx) = new_idx {
+                merged_into.insert(node_idx, new_idx);
+            }
+        }
+
+        let mut weights: HashMap<_, f64> = HashMap::new();
+        for edge in self.graph.edge_indices() {
+            let Some((from, to)) = self.graph.edge_endpoints(edge) else {
+                continue;
+            };
+            let (Some(&from), Some(&to)) =
+                (merged_into.get(&from), merged_into.get(&to))
+            else {
+                continue;
+            };
+            if from != to {
+                let Edge { kind, weight } = self.graph[edge];
+                *weights.entry((from, to, kind)).or_default() += weight;
+            }
+        }
+        for ((from, to, kind), weight) in weights {
+            collapsed.graph.add_edge(from, to, Edge { kind, weight });
+        }
+        collapsed
+    }
+}
+
+/// The outermost of `directories` containing the file `file_name`.
+fn collapsed_directory<'d>(
+    file_name: &str,
+    directories: &'d HashSet<String>,
+) -> Option<&'d String> {
+    Path::new(file_name)
+        .ancestors()
+        .skip(1)
+        .filter_map(|ancestor| directories.get(ancestor.to_str()?))
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapsed_directories_merge_edges() {
+        let mut graph = WorkspaceGraph::new();
+        let main = graph.add_file(File::new("/ws/main.ts".to_string()));
+        let a = graph.add_file(File::new("/ws/lib/a.ts".to_string()));
+        let b = graph.add_file(File::new("/ws/lib/nested/b.ts".to_string()));
+        graph.add_import(main, a, EdgeKind::StaticImport);
+        graph.add_import(main, b, EdgeKind::StaticImport);
+        graph.add_import(main, b, EdgeKind::StaticImport);
+        graph.add_import(a, b, EdgeKind::StaticImport);
+
+        let collapsed = graph.with_collapsed_directories(&HashSet::from([
+            "/ws/lib".to_string(),
+            "/ws/lib/nested".to_string(),
+        ]));
+        assert_eq!(collapsed.graph.node_count(), 2);
+        let lib = collapsed.find_file("/ws/lib").unwrap();
+        assert!(collapsed.graph[lib].directory);
+        // The import between a and b is now inside the directory
+        let edges: Vec<_> = collapsed.graph.edge_weights().collect();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].weight, 3.0);
     }
 }
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::workspace_layout::WorkspaceLayout;
+use floem::action::{TimerToken, exec_after};
+use floem::ext_event::create_ext_action;
+use floem::kurbo::Point;
+use floem::prelude::{
+    RwSignal, SignalGet as _, SignalTrack as _, SignalUpdate as _, SignalWith as _,
+    create_effect,
+};
+use floem::reactive::Scope;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef as _;
+
+/// Edges bundled at most, the heaviest ones, as bundling compares every pair
+/// of edges.
+const MAX_BUNDLED_EDGES: usize = 300;
+
+/// Time without the nodes moving after which the edges are bundled.
+const BUNDLE_DELAY: Duration = Duration::from_millis(200);
+
+/// How alike two edges must be, in direction, length and position, for them
+/// to pull on each other.
+const COMPATIBILITY_THRESHOLD: f64 = 0.6;
+
+/// Stiffness of the springs keeping the points of an edge together.
+const SPRING_CONSTANT: f64 = 10.0;
+
+/// Number of cycles, each doubling the points of the edges.
+const CYCLES: usize = 5;
+
+/// Iterations of the first cycle, and how much fewer each next cycle runs.
+const ITERATIONS: f64 = 50.0;
+const ITERATIONS_RATE: f64 = 2.0 / 3.0;
+
+/// How far the points move in an iteration of the first cycle, relative to
+/// the length of their edge, halved at each next cycle.
+const STEP_SIZE: f64 = 0.005;
+
+#[derive(Clone, Debug)]
+pub struct BundlingState {
+    pub enabled: RwSignal<bool>,
+    /// The points each edge bends through between its ends, in canvas units,
+    /// by importing and imported node.
+    pub bundles: RwSignal<HashMap<(NodeIndex, NodeIndex), Vec<Point>>>,
+    /// Bumped whenever the edges change, so that the bundles computed in the
+    /// background for edges that changed since are dropped.
+    generation: RwSignal<u64>,
+}
+
+impl BundlingState {
+    pub fn new() -> Self {
+        let enabled = RwSignal::new(false);
+        let bundles = RwSignal::new(HashMap::new());
+        let generation = RwSignal::new(0);
+        Self {
+            enabled,
+            bundles,
+            generation,
+        }
+    }
+}
+
+/// How alike edges `p` and `q` are, from 0 to 1, by the angle between them,
+/// their lengths and the distance between their middles.
+fn compatibility(p: (Point, Point), q: (Point, Point)) -> f64 {
+    let p_vector = p.1 - p.0;
+    let q_vector = q.1 - q.0;
+    let p_length = p_vector.hypot();
+    let q_length = q_vector.hypot();
+    if p_length == 0.0 || q_length == 0.0 {
+        return 0.0;
+    }
+    let angle = (p_vector.dot(q_vector) / (p_length * q_length)).abs();
+    let average_length = (p_length + q_length) / 2.0;
+    let scale = 2.0
+        / (average_length / p_length.min(q_length)
+            + p_length.max(q_length) / average_length);
+    let middles_distance = (p.0.midpoint(p.1) - q.0.midpoint(q.1)).hypot();
+    let position = average_length / (average_length + middles_distance);
+    angle * scale * position
+}
+
+/// Spreads `count` points evenly along the polyline going from `from`
+/// through `points` to `to`.
+fn subdivide(from: Point, points: &[Point], to: Point, count: usize) -> Vec<Point> {
+    let polyline: Vec<Point> = std::iter::once(from)
+        .chain(points.iter().copied())
+        .chain(std::iter::once(to))
+        .collect();
+    let length: f64 = polyline.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let step = length / (count + 1) as f64;
+    let mut subdivided = Vec::with_capacity(count);
+    // Length of the polyline before the current segment
+    let mut travelled = 0.0;
+    let mut next = step;
+    for segment in polyline.windows(2) {
+        let segment_length = segment[0].distance(segment[1]);
+        while subdivided.len() < count && next <= travelled + segment_length {
+            let t = (next - travelled) / segment_length;
+            subdivided.push(segment[0].lerp(segment[1], t));
+            next += step;
+        }
+        travelled += segment_length;
+    }
+    // Rounding may leave the last points out
+    subdivided.resize(count, to);
+    subdivided
+}
+
+/// Bundles `edges` with force-directed edge bundling: edges are split into
+/// points, which are pulled towards the points of alike edges while springs
+/// keep each edge together, so that edges running side by side merge into
+/// bundles. Returns the points each edge bends through between its ends.
+pub(super) fn bundle_edges(edges: &[(Point, Point)]) -> Vec<Vec<Point>> {
+    let compatible: Vec<Vec<(usize, f64)>> = edges
+        .iter()
+        .enumerate()
+        .map(|(p, &p_edge)| {
+            edges
+                .iter()
+                .enumerate()
+                .filter(|&(q, _)| q != p)
+                .map(|(q, &q_edge)| (q, compatibility(p_edge, q_edge)))
+                .filter(|&(_, compatibility)| {
+                    compatibility >= COMPATIBILITY_THRESHOLD
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut points: Vec<Vec<Point>> = edges
+        .iter()
+        .map(|(from, to)| vec![from.midpoint(*to)])
+        .collect();
+    let mut step_size = STEP_SIZE;
+    let mut iterations = ITERATIONS;
+    for cycle in 0..CYCLES {
+        if cycle > 0 {
+            let count = points.first().map_or(0, Vec::len) * 2;
+            points = edges
+                .iter()
+                .zip(&points)
+                .map(|((from, to), points)| subdivide(*from, points, *to, count))
+                .collect();
+        }
+        for _ in 0..iterations as usize {
+            points = edges
+                .iter()
+                .enumerate()
+                .map(|(p, &(from, to))| {
+                    let length = from.distance(to).max(1.0);
+                    let count = points[p].len();
+                    // Springs stiffen as the edge is split, so that its shape
+                    // does not depend on the number of points
+                    let spring = SPRING_CONSTANT * (count + 1) as f64 / length;
+                    (0..count)
+                        .map(|i| {
+                            let point = points[p][i];
+                            let previous =
+                                if i == 0 { from } else { points[p][i - 1] };
+                            let next = points[p].get(i + 1).copied().unwrap_or(to);
+                            let mut force =
+                                ((previous - point) + (next - point)) * spring;
+                            for &(q, compatibility) in &compatible[p] {
+                                let pull = points[q][i] - point;
+                                let distance = pull.hypot();
+                                if distance > f64::EPSILON {
+                                    force += pull / distance * compatibility;
+                                }
+                            }
+                            point + force * step_size * length
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+        step_size /= 2.0;
+        iterations *= ITERATIONS_RATE;
+    }
+    points
+}
+
+impl WorkspaceLayout {
+    /// Bundles the edges again in the background once the nodes stop moving
+    /// for a while, while bundling is enabled. Edges are left unbundled while
+    /// the nodes move.
+    pub(super) fn bundle_edges_on_layout_change(&self) {
+        let timer = RwSignal::new(None::<TimerToken>);
+        let scope = Scope::current();
+        let layout = self.clone();
+        create_effect(move |_| {
+            layout.cluster_state.visible_graph.track();
+            layout.layout_state.positions.track();
+            layout.filter_state.hidden_edge_kinds.track();
+            let bundling_state = &layout.bundling_state;
+            bundling_state
+                .generation
+                .update(|generation| *generation += 1);
+            if !bundling_state.bundles.with_untracked(HashMap::is_empty) {
+                bundling_state.bundles.set(HashMap::new());
+            }
+            if !bundling_state.enabled.get() {
+                timer.set(None);
+                return;
+            }
+            let token = exec_after(BUNDLE_DELAY, {
+                let layout = layout.clone();
+                move |token| {
+                    if timer.get_untracked() == Some(token) {
+                        layout.spawn_edge_bundling(scope);
+                    }
+                }
+            });
+            timer.set(Some(token));
+        });
+    }
+
+    /// Bundles the heaviest of the edges shown on a worker thread, keeping
+    /// the result only if the edges did not change in the meantime.
+    fn spawn_edge_bundling(&self, scope: Scope) {
+        let bundles = self.bundling_state.bundles;
+        let generation = self.bundling_state.generation;
+        let started_generation = generation.get_untracked();
+        let (pairs, edges) = self.edges_to_bundle();
+        let send = create_ext_action(
+            scope,
+            move |bundled: HashMap<(NodeIndex, NodeIndex), Vec<Point>>| {
+                if generation.get_untracked() == started_generation {
+                    bundles.set(bundled);
+                }
+            },
+        );
+        rayon::spawn(move || {
+            send(pairs.into_iter().zip(bundle_edges(&edges)).collect());
+        });
+    }
+
+    /// The heaviest of the edges shown, by importing and imported node, along
+    /// with the positions of their ends.
+    fn edges_to_bundle(&self) -> (Vec<(NodeIndex, NodeIndex)>, Vec<(Point, Point)>) {
+        let hidden_edge_kinds = self.filter_state.hidden_edge_kinds.get_untracked();
+        self.cluster_state.visible_graph.with_untracked(|graph| {
+            self.layout_state.positions.with_untracked(|positions| {
+                // Parallel edges of different kinds are drawn as one
+                let mut weights: HashMap<(NodeIndex, NodeIndex), f64> =
+                    HashMap::new();
+                for edge in graph.graph.edge_references() {
+                    if hidden_edge_kinds.contains(&edge.weight().kind)
+                        || edge.source() == edge.target()
+                        || !positions.contains_key(&edge.source())
+                        || !positions.contains_key(&edge.target())
+                    {
+                        continue;
+                    }
+                    *weights.entry((edge.source(), edge.target())).or_default() +=
+                        edge.weight().weight;
+                }
+                let mut pairs: Vec<_> = weights.into_iter().collect();
+                pairs.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+                pairs.truncate(MAX_BUNDLED_EDGES);
+                pairs
+                    .into_iter()
+                    .map(|(pair @ (source, target), _)| {
+                        let source = &positions[&source];
+                        let target = &positions[&target];
+                        (
+                            pair,
+                            (
+                                Point::new(source.x, source.y),
+                                Point::new(target.x, target.y),
+                            ),
+                        )
+                    })
+                    .unzip()
+            })
+        })
+    }
+
+    pub fn toggle_edge_bundling(&self) {
+        self.bundling_state
+            .enabled
+            .update(|enabled| *enabled = !*enabled);
+    }
+
+    pub fn is_edge_bundling(&self) -> bool {
+        self.bundling_state.enabled.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_parallel_edges() {
+        let edges = [
+            (Point::new(0.0, 0.0), Point::new(1000.0, 0.0)),
+            (Point::new(0.0, 100.0), Point::new(1000.0, 100.0)),
+            (Point::new(0.0, 0.0), Point::new(0.0, -1000.0)),
+        ];
+        let bundles = bundle_edges(&edges);
+        assert_eq!(bundles.len(), 3);
+        assert!(bundles.iter().all(|points| points.len() == 16));
+
+        // The parallel edges are pulled towards each other in the middle
+        let middle = |edge: usize| bundles[edge][7].midpoint(bundles[edge][8]);
+        assert!(middle(0).y > 10.0);
+        assert!(middle(1).y < 90.0);
+        // The edge running across them is left as it is
+        assert!(bundles[2].iter().all(|point| point.x.abs() < 1e-9));
+    }
+}
@@ -1,214 +1,242 @@
+use super::clustered_layout::{Region, clustered_layout};
+use super::force_layout::{ForceLayout, LayoutSettings, Position, layout_size};
+use super::layered_layout::layered_layout;
+use super::layout_state::LayoutMode;
 use crate::file::File;
+use crate::workspace_graph::{Edge, WorkspaceGraph};
 use petgraph::{graph::NodeIndex, visit::EdgeRef as _};
 use std::collections::HashMap;
-
-#[derive(Debug, Clone)]
-pub(crate) struct Position {
-    pub x: f64,
-    pub y: f64,
-}
-
-impl Position {
-    fn new(x: f64, y: f64) -> Self {
-        Position { x, y }
-    }
-
-    fn distance(&self, other: &Position) -> f64 {
-        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
-    }
-}
-
-impl super::workspace_layout::WorkspaceLayout {
-    pub fn calculate_positions(&self) -> Vec<(&File, Position, Vec<Position>)> {
-        if self.workspace_graph.graph.node_count() == 0 {
-            return vec![];
-        }
-
-        let mut layout =
-            ForceDirectedLayout::new(&self.workspace_graph.graph, 800.0, 600.0);
-        layout.run(&self.workspace_graph.graph, 100);
-
-        let mut result = Vec::new();
-        for node_idx in self.workspace_graph.graph.node_indices() {
-            let file = &self.workspace_graph.graph[node_idx];
-            let position = layout.positions[&node_idx].clone();
-
-            let mut connected_positions = Vec::new();
-            for edge in self.workspace_graph.graph.edges(node_idx) {
-                let target_idx = edge.target();
-                if let Some(target_pos) = layout.positions.get(&target_idx) {
-                    connected_positions.push(target_pos.clone());
-                }
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+/// A file with its position, along with the positions of the files it
+/// imports and the edge of each import.
+pub(super) type PositionedFile<'g> =
+    (&'g File, Position, Vec<(&'g File, Position, &'g Edge)>);
+
+/// Pairs every file with its position, along with the positions of the files
+/// it imports and the edge of each import. Files the simulation has not
+/// placed yet are left out.
+pub(super) fn positioned_files<'g>(
+    workspace_graph: &'g WorkspaceGraph,
+    positions: &HashMap<NodeIndex, Position>,
+) -> Vec<PositionedFile<'g>> {
+    let mut result = Vec::new();
+    for node_idx in workspace_graph.graph.node_indices() {
+        let Some(position) = positions.get(&node_idx) else {
+            continue;
+        };
+        let file = &workspace_graph.graph[node_idx];
+
+        let mut connected_positions = Vec::new();
+        // Outgoing edges only, so that each edge is listed once
+        for edge in workspace_graph.graph.edges(node_idx) {
+            let target_idx = edge.target();
+            if let Some(target_pos) = positions.get(&target_idx) {
+                connected_positions.push((
+                    &workspace_graph.graph[target_idx],
+                    target_pos.clone(),
+                    edge.weight(),
+                ));
             }
-
-            result.push((file, position, connected_positions));
         }
 
-        result
+        result.push((file, position.clone(), connected_positions));
     }
-}
 
-struct ForceDirectedLayout {
-    positions: HashMap<NodeIndex, Position>,
-    width: f64,
-    height: f64,
-    k: f64,
-    temperature: f64,
-    cooling_factor: f64,
+    result
 }
 
-impl ForceDirectedLayout {
-    fn new(
-        graph: &petgraph::Graph<File, f64, petgraph::Undirected>,
-        width: f64,
-        height: f64,
-    ) -> Self {
-        let mut positions = HashMap::new();
-
-        let center_x = width / 2.0;
-        let center_y = height / 2.0;
-        let radius = width.min(height) / 2.5;
-
-        let total = graph.node_count();
-        for (i, node_idx) in graph.node_indices().enumerate() {
-            let angle = (i as f64 / total as f64) * std::f64::consts::TAU;
-            let x = center_x + radius * angle.cos();
-            let y = center_y + radius * angle.sin();
-            positions.insert(node_idx, Position::new(x, y));
-        }
-
-        let area = width * height;
-        let k = (area / total as f64).sqrt();
-
-        ForceDirectedLayout {
-            positions,
-            width,
-            height,
-            k,
-            temperature: width / 10.0,
-            cooling_factor: 0.95,
-        }
-    }
-
-    fn calculate_repulsive_force(&self, distance: f64) -> f64 {
-        if distance == 0.0 {
-            return 1000.0;
-        }
-        (self.k * self.k) / distance
-    }
-
-    fn calculate_attractive_force(&self, distance: f64) -> f64 {
-        (distance * distance) / self.k
-    }
-
-    fn iterate(&mut self, graph: &petgraph::Graph<File, f64, petgraph::Undirected>) {
-        let mut displacements: HashMap<NodeIndex, (f64, f64)> =
-            graph.node_indices().map(|n| (n, (0.0, 0.0))).collect();
-
-        let nodes: Vec<NodeIndex> = graph.node_indices().collect();
-        for i in 0..nodes.len() {
-            for j in (i + 1)..nodes.len() {
-                let node_v = nodes[i];
-                let node_u = nodes[j];
+/// Minimum duration of an iteration of the simulation, so that the layout
+/// visibly settles instead of jumping to its final state.
+const FRAME_DURATION: Duration = Duration::from_millis(16);
 
-                let pos_v = self.positions[&node_v].clone();
-                let pos_u = self.positions[&node_u].clone();
-
-                let distance = pos_v.distance(&pos_u);
-                if distance > 0.0 {
-                    let repulsive_force = self.calculate_repulsive_force(distance);
-
-                    let dx = (pos_v.x - pos_u.x) / distance;
-                    let dy = (pos_v.y - pos_u.y) / distance;
+/// The positions of the nodes computed by a run of the layout, and the
+/// regions drawn around them.
+#[derive(Debug, Clone)]
+pub(super) struct LayoutFrame {
+    /// The generation the layout was started for.
+    pub generation: usize,
+    pub positions: HashMap<NodeIndex, Position>,
+    pub regions: Vec<Region>,
+}
 
-                    let disp_v = displacements.get_mut(&node_v).unwrap();
-                    disp_v.0 += dx * repulsive_force;
-                    disp_v.1 += dy * repulsive_force;
+/// The positions known before the graph is laid out.
+#[derive(Debug, Clone, Default)]
+pub(super) struct StartingPositions {
+    /// Positions of a previous run, which the force layout starts from so
+    /// that it does not start over when a few files change.
+    pub previous: HashMap<NodeIndex, Position>,
+    /// Positions of the nodes moved by hand, which every layout keeps.
+    pub pinned: HashMap<NodeIndex, Position>,
+    /// Whether `previous` was saved once the layout settled, in which case
+    /// the force layout keeps it as is unless nodes are missing from it.
+    pub restored: bool,
+}
 
-                    let disp_u = displacements.get_mut(&node_u).unwrap();
-                    disp_u.0 -= dx * repulsive_force;
-                    disp_u.1 -= dy * repulsive_force;
+/// Lays out `workspace_graph` on a background thread, sending the positions
+/// of its nodes to `frames_tx`. The force layout sends them after each
+/// iteration of the simulation, the layered and clustered layouts once.
+/// The simulation stops early once `generation` moves past `run_generation`.
+pub(super) fn spawn_layout(
+    workspace_graph: WorkspaceGraph,
+    mode: LayoutMode,
+    starting_positions: StartingPositions,
+    settings: LayoutSettings,
+    generation: Arc<AtomicUsize>,
+    run_generation: usize,
+    frames_tx: Sender<LayoutFrame>,
+) {
+    let spawned = std::thread::Builder::new()
+        .name("AmasLayout".to_owned())
+        .spawn(move || {
+            let file_graph = FileGraph::new(&workspace_graph);
+            let initial_positions: Vec<Option<Position>> = file_graph
+                .nodes
+                .iter()
+                .map(|node_idx| starting_positions.previous.get(node_idx).cloned())
+                .collect();
+            let (positions, regions) = match mode {
+                LayoutMode::Force if starting_positions.restored => (
+                    initial_positions
+                        .iter()
+                        .cloned()
+                        .collect::<Option<Vec<_>>>(),
+                    Vec::new(),
+                ),
+                LayoutMode::Force => (None, Vec::new()),
+                LayoutMode::Layered => (
+                    Some(layered_layout(file_graph.nodes.len(), &file_graph.edges)),
+                    Vec::new(),
+                ),
+                LayoutMode::Clustered => {
+                    let paths: Vec<Option<&Path>> = file_graph
+                        .nodes
+                        .iter()
+                        .map(|node_idx| {
+                            let file = &workspace_graph.graph[*node_idx];
+                            (!file.external).then(|| Path::new(&file.name))
+                        })
+                        .collect();
+                    let (positions, regions) = clustered_layout(&paths);
+                    (Some(positions), regions)
+                }
+            };
+            if let Some(mut positions) = positions {
+                for (i, node_idx) in file_graph.nodes.iter().enumerate() {
+                    if let Some(pinned) = starting_positions.pinned.get(node_idx) {
+                        positions[i] = pinned.clone();
+                    }
                 }
+                let _ = frames_tx.send(LayoutFrame {
+                    generation: run_generation,
+                    positions: file_graph
+                        .positions_with_symbols(&workspace_graph, &positions),
+                    regions,
+                });
+                return;
             }
-        }
-
-        for edge in graph.edge_indices() {
-            let (node_u, node_v) = graph.edge_endpoints(edge).unwrap();
 
-            let pos_u = self.positions[&node_u].clone();
-            let pos_v = self.positions[&node_v].clone();
-
-            let distance = pos_u.distance(&pos_v);
-            if distance > 0.0 {
-                let attractive_force = self.calculate_attractive_force(distance);
-
-                let dx = (pos_v.x - pos_u.x) / distance;
-                let dy = (pos_v.y - pos_u.y) / distance;
-
-                let disp_u = displacements.get_mut(&node_u).unwrap();
-                disp_u.0 += dx * attractive_force;
-                disp_u.1 += dy * attractive_force;
-
-                let disp_v = displacements.get_mut(&node_v).unwrap();
-                disp_v.0 -= dx * attractive_force;
-                disp_v.1 -= dy * attractive_force;
+            let (width, height) = layout_size(file_graph.nodes.len());
+            let mut layout = ForceLayout::new(
+                initial_positions,
+                file_graph.edges.clone(),
+                width,
+                height,
+                settings,
+            );
+            for (i, node_idx) in file_graph.nodes.iter().enumerate() {
+                if let Some(pinned) = starting_positions.pinned.get(node_idx) {
+                    layout.pin(i, pinned.clone());
+                }
             }
-        }
-
-        // Gravité vers le centre
-        let center_x = self.width / 2.0;
-        let center_y = self.height / 2.0;
-        let gravity_strength = self.k * 0.02;
-        let circular_spring_strength = 0.01;
-        let ideal_radius = self.width.min(self.height) / 2.5;
-
-        for node_idx in graph.node_indices() {
-            let pos = &self.positions[&node_idx];
-            let dx = center_x - pos.x;
-            let dy = center_y - pos.y;
-
-            let disp = displacements.get_mut(&node_idx).unwrap();
-            disp.0 += dx * gravity_strength;
-            disp.1 += dy * gravity_strength;
-
-            // Force pour rester sur le cercle
-            let to_center_dx = pos.x - center_x;
-            let to_center_dy = pos.y - center_y;
-            let dist =
-                (to_center_dx * to_center_dx + to_center_dy * to_center_dy).sqrt();
-            if dist > 0.0 {
-                let diff = dist - ideal_radius;
-                disp.0 -= (to_center_dx / dist) * diff * circular_spring_strength;
-                disp.1 -= (to_center_dy / dist) * diff * circular_spring_strength;
+            loop {
+                if generation.load(Ordering::Relaxed) != run_generation {
+                    return;
+                }
+                let started = Instant::now();
+                layout.iterate();
+
+                let positions = file_graph
+                    .positions_with_symbols(&workspace_graph, layout.positions());
+                let frame = LayoutFrame {
+                    generation: run_generation,
+                    positions,
+                    regions: Vec::new(),
+                };
+                if frames_tx.send(frame).is_err() || layout.is_settled() {
+                    return;
+                }
+                if let Some(remaining) =
+                    FRAME_DURATION.checked_sub(started.elapsed())
+                {
+                    std::thread::sleep(remaining);
+                }
             }
-        }
-
-        for (node_idx, (dx, dy)) in displacements {
-            let displacement_length = (dx * dx + dy * dy).sqrt();
-            if displacement_length > 0.0 {
-                let limited_displacement = displacement_length.min(self.temperature);
-                let normalized_dx = dx / displacement_length;
-                let normalized_dy = dy / displacement_length;
+        });
+    if let Err(e) = spawned {
+        eprintln!("Failed to start the layout: {}", e);
+    }
+}
 
-                let pos = self.positions.get_mut(&node_idx).unwrap();
-                pos.x += normalized_dx * limited_displacement;
-                pos.y += normalized_dy * limited_displacement;
+/// Distance between a file and its symbols, in canvas units.
+const SYMBOL_RING_RADIUS: f64 = 30.0;
 
-                pos.x = pos.x.max(0.0).min(self.width);
-                pos.y = pos.y.max(0.0).min(self.height);
-            }
-        }
+/// The files of a graph and the imports between them, indexed the way the
+/// layouts expect. Symbols are not laid out, but placed on a ring around
+/// their file.
+struct FileGraph {
+    /// The file node at each index.
+    nodes: Vec<NodeIndex>,
+    edges: Vec<(usize, usize)>,
+}
 
-        self.temperature *= self.cooling_factor;
+impl FileGraph {
+    fn new(workspace_graph: &WorkspaceGraph) -> Self {
+        let graph = &workspace_graph.graph;
+        let nodes: Vec<NodeIndex> = graph
+            .node_indices()
+            .filter(|node_idx| graph[*node_idx].symbol.is_none())
+            .collect();
+        let indices: HashMap<NodeIndex, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (*node, i))
+            .collect();
+        let edges = graph
+            .edge_indices()
+            .filter_map(|edge| {
+                let (node_u, node_v) = graph.edge_endpoints(edge)?;
+                Some((*indices.get(&node_u)?, *indices.get(&node_v)?))
+            })
+            .collect();
+        FileGraph { nodes, edges }
     }
 
-    fn run(
-        &mut self,
-        graph: &petgraph::Graph<File, f64, petgraph::Undirected>,
-        iterations: usize,
-    ) {
-        for _ in 0..iterations {
-            self.iterate(graph);
+    fn positions_with_symbols(
+        &self,
+        workspace_graph: &WorkspaceGraph,
+        file_positions: &[Position],
+    ) -> HashMap<NodeIndex, Position> {
+        let mut positions = HashMap::new();
+        for (node_idx, file_position) in self.nodes.iter().zip(file_positions) {
+            let symbols = workspace_graph.symbols_of(*node_idx);
+            for (i, symbol_idx) in symbols.iter().enumerate() {
+                let angle =
+                    (i as f64 / symbols.len() as f64) * std::f64::consts::TAU;
+                positions.insert(
+                    *symbol_idx,
+                    Position::new(
+                        file_position.x + SYMBOL_RING_RADIUS * angle.cos(),
+                        file_position.y + SYMBOL_RING_RADIUS * angle.sin(),
+                    ),
+                );
+            }
+            positions.insert(*node_idx, file_position.clone());
         }
+        positions
     }
 }
@@ -1,19 +1,216 @@
-use floem::prelude::{RwSignal, SignalUpdate as _};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
+use floem::prelude::{RwSignal, SignalUpdate as _, SignalWith as _, create_effect};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef as _;
+
+use super::spatial_index::{Bounds, SpatialIndex};
+use super::workspace_layout::WorkspaceLayout;
 use crate::file::File;
+use crate::workspace_graph::{EdgeKind, WorkspaceGraph};
+
+/// Size of the cells of the indices of what is drawn, in pixels.
+const SCREEN_CELL_SIZE: f64 = 64.0;
+
+/// Size of the cells of the index of the nodes, in canvas units.
+const CANVAS_CELL_SIZE: f64 = 80.0;
 
 #[derive(Clone, Debug)]
 pub struct CanvaState {
-    pub files: RwSignal<Vec<(File, (f64, f64, f64, f64))>>,
+    /// The nodes drawn, by their bounds on the screen.
+    pub files: RwSignal<SpatialIndex<NodeIndex>>,
+    /// The name band of each directory region, by its bounds on the screen.
+    pub regions: RwSignal<SpatialIndex<String>>,
+    /// The nodes laid out, by their bounds in canvas units, so that only the
+    /// visible ones are drawn.
+    pub nodes: RwSignal<SpatialIndex<NodeIndex>>,
+    /// The edges of the visible graph, merged when the graph changes rather
+    /// than on every frame.
+    pub edges: RwSignal<EdgeIndex>,
 }
 
 impl CanvaState {
     pub fn new() -> Self {
-        let files = RwSignal::new(Vec::new());
-        Self { files }
+        let files = RwSignal::new(SpatialIndex::new(SCREEN_CELL_SIZE));
+        let regions = RwSignal::new(SpatialIndex::new(SCREEN_CELL_SIZE));
+        let nodes = RwSignal::new(SpatialIndex::new(CANVAS_CELL_SIZE));
+        let edges = RwSignal::new(EdgeIndex::default());
+        Self {
+            files,
+            regions,
+            nodes,
+            edges,
+        }
+    }
+
+    /// Files drawn last are found first when several overlap.
+    pub fn set_files(&self, files: Vec<(NodeIndex, Bounds)>) {
+        self.files.set(build_index(files, SCREEN_CELL_SIZE));
+    }
+
+    pub fn set_regions(&self, regions: Vec<(String, Bounds)>) {
+        self.regions.set(build_index(regions, SCREEN_CELL_SIZE));
+    }
+}
+
+/// The imports of every kind from a node to another, drawn as one edge.
+#[derive(Debug, Clone, Copy)]
+pub struct MergedEdge {
+    pub source: NodeIndex,
+    pub target: NodeIndex,
+    /// The kind standing for the most imports, and their number.
+    pub kind: EdgeKind,
+    kind_weight: f64,
+    /// The number of imports of every kind.
+    pub weight: f64,
+}
+
+/// The edges of a graph, with the parallel edges of different kinds merged,
+/// by the nodes at their ends.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeIndex {
+    edges: Vec<MergedEdge>,
+    node_edges: HashMap<NodeIndex, Vec<usize>>,
+}
+
+impl EdgeIndex {
+    fn build(graph: &WorkspaceGraph, hidden_edge_kinds: &HashSet<EdgeKind>) -> Self {
+        let mut merged_edges: BTreeMap<(NodeIndex, NodeIndex), MergedEdge> =
+            BTreeMap::new();
+        for edge in graph.graph.edge_references() {
+            let kind = edge.weight().kind;
+            if hidden_edge_kinds.contains(&kind) {
+                continue;
+            }
+            let weight = edge.weight().weight;
+            let merged = merged_edges
+                .entry((edge.source(), edge.target()))
+                .or_insert(MergedEdge {
+                    source: edge.source(),
+                    target: edge.target(),
+                    kind,
+                    kind_weight: 0.0,
+                    weight: 0.0,
+                });
+            merged.weight += weight;
+            if weight > merged.kind_weight {
+                merged.kind = kind;
+                merged.kind_weight = weight;
+            }
+        }
+
+        let edges: Vec<MergedEdge> = merged_edges.into_values().collect();
+        let mut node_edges: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+        for (i, edge) in edges.iter().enumerate() {
+            node_edges.entry(edge.source).or_default().push(i);
+            node_edges.entry(edge.target).or_default().push(i);
+        }
+        Self { edges, node_edges }
+    }
+
+    /// The edges from or to one of `nodes`, each once, ordered by their ends
+    /// so that they overlap the same way from one frame to the next.
+    pub fn touching(&self, nodes: &[NodeIndex]) -> Vec<MergedEdge> {
+        let mut entries: Vec<usize> = nodes
+            .iter()
+            .filter_map(|node_idx| self.node_edges.get(node_idx))
+            .flatten()
+            .copied()
+            .collect();
+        entries.sort_unstable();
+        entries.dedup();
+        entries.into_iter().map(|entry| self.edges[entry]).collect()
+    }
+}
+
+fn build_index<T>(entries: Vec<(T, Bounds)>, cell_size: f64) -> SpatialIndex<T> {
+    let mut index = SpatialIndex::new(cell_size);
+    for (value, bounds) in entries {
+        index.insert(value, bounds);
+    }
+    index
+}
+
+impl WorkspaceLayout {
+    /// Indexes the bounds of the nodes whenever they move.
+    pub(super) fn index_nodes_on_layout_change(&self) {
+        let visible_graph = self.cluster_state.visible_graph;
+        let positions = self.layout_state.positions;
+        let nodes = self.canva_state.nodes;
+        create_effect(move |_| {
+            let index = visible_graph.with(|graph| {
+                positions.with(|positions| {
+                    let nodes =
+                        positions.iter().filter_map(|(node_idx, position)| {
+                            let file = graph.graph.node_weight(*node_idx)?;
+                            let half_size = node_size(file) / 2.0;
+                            let bounds = (
+                                position.x - half_size,
+                                position.y - half_size,
+                                position.x + half_size,
+                                position.y + half_size,
+                            );
+                            Some((*node_idx, bounds))
+                        });
+                    build_index(nodes.collect(), CANVAS_CELL_SIZE)
+                })
+            });
+            nodes.set(index);
+        });
+    }
+}
+
+impl WorkspaceLayout {
+    /// Merges the edges whenever the graph or the shown kinds of edges
+    /// change, rather than on every frame.
+    pub(super) fn index_edges_on_graph_change(&self) {
+        let visible_graph = self.cluster_state.visible_graph;
+        let hidden_edge_kinds = self.filter_state.hidden_edge_kinds;
+        let edges = self.canva_state.edges;
+        create_effect(move |_| {
+            let index = visible_graph.with(|graph| {
+                hidden_edge_kinds.with(|hidden_edge_kinds| {
+                    EdgeIndex::build(graph, hidden_edge_kinds)
+                })
+            });
+            edges.set(index);
+        });
     }
+}
+
+/// Size of the square drawn for a node, in canvas units.
+pub(super) fn node_size(file: &File) -> f64 {
+    if file.symbol.is_some() { 12.0 } else { 40.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_index() {
+        let mut graph = WorkspaceGraph::new();
+        let a = graph.add_file(File::new("a".to_string()));
+        let b = graph.add_file(File::new("b".to_string()));
+        let c = graph.add_file(File::new("c".to_string()));
+        graph.add_import(a, b, EdgeKind::StaticImport);
+        graph.add_import(a, b, EdgeKind::TypeImport);
+        graph.add_import(a, b, EdgeKind::TypeImport);
+        graph.add_import(b, c, EdgeKind::Require);
+
+        let index = EdgeIndex::build(&graph, &HashSet::new());
+        let edges = index.touching(&[a, b]);
+        assert_eq!(edges.len(), 2);
+        assert_eq!((edges[0].source, edges[0].target), (a, b));
+        assert_eq!(edges[0].kind, EdgeKind::TypeImport);
+        assert_eq!(edges[0].weight, 3.0);
+        assert_eq!((edges[1].source, edges[1].target), (b, c));
+        assert_eq!(index.touching(&[c]).len(), 1);
 
-    pub fn set_files(&self, files: Vec<(File, (f64, f64, f64, f64))>) {
-        self.files.set(files);
+        let index = EdgeIndex::build(&graph, &HashSet::from([EdgeKind::TypeImport]));
+        let edges = index.touching(&[a]);
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].kind, EdgeKind::StaticImport);
+        assert_eq!(edges[0].weight, 1.0);
     }
 }
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use super::workspace_layout::WorkspaceLayout;
+use crate::workspace_graph::WorkspaceGraph;
+use floem::prelude::{
+    RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _, create_effect,
+};
+
+#[derive(Clone, Debug)]
+pub struct ClusterState {
+    /// Directories shown as a single node in place of the files under them.
+    pub collapsed_directories: RwSignal<HashSet<String>>,
+    /// The workspace graph with the collapsed directories merged, which is
+    /// the one laid out and drawn.
+    pub visible_graph: RwSignal<WorkspaceGraph>,
+    /// The directory whose name is under the pointer.
+    pub hovered_directory: RwSignal<Option<String>>,
+}
+
+impl ClusterState {
+    pub fn new() -> Self {
+        let collapsed_directories = RwSignal::new(HashSet::new());
+        let visible_graph = RwSignal::new(WorkspaceGraph::new());
+        let hovered_directory = RwSignal::new(None);
+        Self {
+            collapsed_directories,
+            visible_graph,
+            hovered_directory,
+        }
+    }
+}
+
+impl WorkspaceLayout {
+    /// Merges the collapsed directories into the visible graph whenever the
+    /// graph or the collapsed directories change.
+    pub(super) fn collapse_on_graph_change(&self) {
+        let workspace_graph = self.workspace_graph;
+        let cluster_state = self.cluster_state.clone();
+        create_effect(move |_| {
+            let visible_graph =
+                cluster_state
+                    .collapsed_directories
+                    .with(|collapsed_directories| {
+                        workspace_graph.with(|workspace_graph| {
+                            workspace_graph
+                                .with_collapsed_directories(collapsed_directories)
+                        })
+                    });
+            cluster_state.visible_graph.set(visible_graph);
+        });
+    }
+
+    pub fn track_hovered_directory(&self, x: f64, y: f64) {
+        let directory = self
+            .canva_state
+            .regions
+            .with(|regions| regions.at(x, y).cloned());
+        self.cluster_state.hovered_directory.set(directory);
+    }
+
+    /// Expands the hovered node when it is a collapsed directory, otherwise
+    /// collapses the directory whose name is hovered. Returns whether a
+    /// directory was toggled.
+    pub fn toggle_hovered_directory(&self) -> bool {
+        if let Some(file_name) = self.get_hovered_file() {
+            let is_directory =
+                self.cluster_state.visible_graph.with_untracked(|graph| {
+                    graph
+                        .find_file(&file_name)
+                        .is_some_and(|node_idx| graph.graph[node_idx].directory)
+                });
+            if is_directory {
+                self.cluster_state
+                    .collapsed_directories
+                    .update(|collapsed| {
+                        collapsed.remove(&file_name);
+                    });
+            }
+            return is_directory;
+        }
+
+        let Some(directory) = self.cluster_state.hovered_directory.get_untracked()
+        else {
+            return false;
+        };
+        self.cluster_state
+            .collapsed_directories
+            .update(|collapsed| {
+                collapsed.insert(directory);
+            });
+        // The header under the pointer is gone with the directory
+        self.cluster_state.hovered_directory.set(None);
+        true
+    }
+}
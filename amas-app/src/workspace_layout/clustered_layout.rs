@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::force_layout::Position;
+
+/// Side of the square taken up by a file, in canvas units.
+const CELL_SIZE: f64 = 64.0;
+
+/// Space between the files and directories of a directory, and between its
+/// border and its content.
+const PADDING: f64 = 16.0;
+
+/// Height of the band at the top of a directory holding its name.
+pub(super) const HEADER_HEIGHT: f64 = 28.0;
+
+/// How much wider than tall the content of a directory tends to be.
+const ASPECT_RATIO: f64 = 1.5;
+
+/// A directory drawn as a labelled box around its files.
+#[derive(Debug, Clone)]
+pub struct Region {
+    /// Path of the directory, which collapses it when clicked.
+    pub directory: String,
+    /// Path of the directory relative to the enclosing region.
+    pub label: String,
+    pub min: Position,
+    pub max: Position,
+}
+
+/// Groups the nodes by directory into nested regions, each directory packing
+/// its files and subdirectories in rows. A directory with a single
+/// subdirectory and no file shares the region of its subdirectory.
+///
+/// Nodes are identified by their index and given by their path, `None`
+/// standing for external packages, which are packed apart below the
+/// workspace. The positions are returned in the same order.
+pub(super) fn clustered_layout(
+    paths: &[Option<&Path>],
+) -> (Vec<Position>, Vec<Region>) {
+    let mut root = DirectoryTree::new(common_directory(paths));
+    let mut externals = Vec::new();
+    for (node, path) in paths.iter().enumerate() {
+        let Some(directory) = path.and_then(Path::parent) else {
+            externals.push(Block::file(node));
+            continue;
+        };
+        let relative = directory.strip_prefix(&root.path).unwrap_or(Path::new(""));
+        let mut tree = &mut root;
+        for component in relative.components() {
+            let name = component.as_os_str().to_string_lossy().into_owned();
+            let path = tree.path.join(&name);
+            tree = tree
+                .children
+                .entry(name)
+                .or_insert_with(|| DirectoryTree::new(path));
+        }
+        tree.nodes.push(node);
+    }
+
+    let mut layout = Block::default();
+    if !root.is_empty() {
+        let label = root
+            .path
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+        layout = root.layout(label);
+    }
+    if !externals.is_empty() {
+        let mut externals = Block::pack(externals);
+        externals.translate(0.0, layout.height + 2.0 * PADDING);
+        layout.append(externals);
+    }
+
+    let mut positions = vec![Position::new(0.0, 0.0); paths.len()];
+    for (node, position) in layout.positions {
+        positions[node] = position;
+    }
+    (positions, layout.regions)
+}
+
+/// The deepest directory containing every path.
+fn common_directory(paths: &[Option<&Path>]) -> PathBuf {
+    let mut directories = paths.iter().flatten().filter_map(|path| path.parent());
+    let Some(first) = directories.next() else {
+        return PathBuf::new();
+    };
+    let mut common = first.to_path_buf();
+    for directory in directories {
+        while !directory.starts_with(&common) {
+            if !common.pop() {
+                return common;
+            }
+        }
+    }
+    common
+}
+
+struct DirectoryTree {
+    path: PathBuf,
+    /// The nodes directly in the directory.
+    nodes: Vec<usize>,
+    /// The subdirectories, by name.
+    children: BTreeMap<String, DirectoryTree>,
+}
+
+impl DirectoryTree {
+    fn new(path: PathBuf) -> Self {
+        DirectoryTree {
+            path,
+            nodes: Vec::new(),
+            children: BTreeMap::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.children.is_empty()
+    }
+
+    /// Packs the content of the directory in a region named `label`, with
+    /// the directory at its top left corner.
+    fn layout(&self, mut label: String) -> Block {
+        let mut directory = self;
+        while directory.nodes.is_empty() && directory.children.len() == 1 {
+            let Some((name, child)) = directory.children.iter().next() else {
+                break;
+            };
+            label = if label.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", label, name)
+            };
+            directory = child;
+        }
+
+        let items = directory
+            .children
+            .iter()
+            .map(|(name, child)| child.layout(name.clone()))
+            .chain(directory.nodes.iter().map(|node| Block::file(*node)))
+            .collect();
+        let mut content = Block::pack(items);
+        content.translate(PADDING, HEADER_HEIGHT);
+
+        let width = content.width + 2.0 * PADDING;
+        let height = content.height + HEADER_HEIGHT + PADDING;
+        let mut block = Block {
+            width,
+            height,
+            positions: Vec::new(),
+            regions: vec![Region {
+                directory: directory.path.to_string_lossy().into_owned(),
+                label,
+                min: Position::new(0.0, 0.0),
+                max: Position::new(width, height),
+            }],
+        };
+        // Regions come before the regions they contain, to be drawn below
+        block.append(content);
+        block
+    }
+}
+
+/// A laid out piece of the graph, with its top left corner at the origin.
+#[derive(Default)]
+struct Block {
+    width: f64,
+    height: f64,
+    positions: Vec<(usize, Position)>,
+    regions: Vec<Region>,
+}
+
+impl Block {
+    fn file(node: usize) -> Self {
+        Block {
+            width: CELL_SIZE,
+            height: CELL_SIZE,
+            positions: vec![(node, Position::new(CELL_SIZE / 2.0, CELL_SIZE / 2.0))],
+            regions: Vec::new(),
+        }
+    }
+
+    fn translate(&mut self, dx: f64, dy: f64) {
+        for (_, position) in &mut self.positions {
+            position.x += dx;
+            position.y += dy;
+        }
+        for region in &mut self.regions {
+            region.min.x += dx;
+            region.min.y += dy;
+            region.max.x += dx;
+            region.max.y += dy;
+        }
+    }
+
+    /// Adds the content of `other`, which must already be in place.
+    fn append(&mut self, other: Block) {
+        self.width = self.width.max(other.width);
+        self.height = self.height.max(other.height);
+        self.positions.extend(other.positions);
+        self.regions.extend(other.regions);
+    }
+
+    /// Places `items` in rows, the tallest first so that rows waste little
+    /// space, with rows about as wide as the aspect ratio asks for.
+    fn pack(mut items: Vec<Block>) -> Block {
+        items.sort_by(|a, b| b.height.total_cmp(&a.height));
+        let area: f64 = items
+            .iter()
+            .map(|item| (item.width + PADDING) * (item.height + PADDING))
+            .sum();
+        let widest = items.iter().map(|item| item.width).fold(0.0, f64::max);
+        let row_width = (area * ASPECT_RATIO).sqrt().max(widest);
+
+        let mut packed = Block::default();
+        let (mut x, mut y, mut row_height) = (0.0, 0.0, 0.0_f64);
+        for mut item in items {
+            if x > 0.0 && x + item.width > row_width {
+                x = 0.0;
+                y += row_height + PADDING;
+                row_height = 0.0;
+            }
+            item.translate(x, y);
+            x += item.width + PADDING;
+            row_height = row_height.max(item.height);
+            packed.width = packed.width.max(x - PADDING);
+            packed.height = packed.height.max(y + item.height);
+            packed.positions.extend(item.positions);
+            packed.regions.extend(item.regions);
+        }
+        packed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(region: &Region, position: &Position) -> bool {
+        position.x > region.min.x
+            && position.x < region.max.x
+            && position.y > region.min.y
+            && position.y < region.max.y
+    }
+
+    #[test]
+    fn test_files_are_inside_their_directory() {
+        let paths = [
+            Some(Path::new("/ws/src/main.rs")),
+            Some(Path::new("/ws/src/app/mod.rs")),
+            Some(Path::new("/ws/src/app/view.rs")),
+            Some(Path::new("/ws/tests/app.rs")),
+            None,
+        ];
+        let (positions, regions) = clustered_layout(&paths);
+        let labels: Vec<&str> =
+            regions.iter().map(|region| region.label.as_str()).collect();
+        assert_eq!(labels, vec!["ws", "src", "app", "tests"]);
+
+        let app = &regions[2];
+        assert_eq!(app.directory, "/ws/src/app");
+        assert!(contains(&regions[1], &app.min) && contains(&regions[1], &app.max));
+        assert!(!contains(app, &positions[0]));
+        assert!(contains(app, &positions[1]) && contains(app, &positions[2]));
+        // External packages are outside of the workspace
+        assert!(!contains(&regions[0], &positions[4]));
+    }
+
+    #[test]
+    fn test_directories_with_a_single_subdirectory_are_merged() {
+        let paths = [
+            Some(Path::new("/ws/a/b/c/one.ts")),
+            Some(Path::new("/ws/a/b/c/two.ts")),
+            Some(Path::new("/ws/d/three.ts")),
+        ];
+        let (_, regions) = clustered_layout(&paths);
+        let labels: Vec<&str> =
+            regions.iter().map(|region| region.label.as_str()).collect();
+        assert_eq!(labels, vec!["ws", "a/b/c", "d"]);
+        assert_eq!(regions[1].directory, "/ws/a/b/c");
+    }
+}
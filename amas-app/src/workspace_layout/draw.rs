@@ -1,63 +1,742 @@
+use std::collections::HashMap;
+use std::path::Path;
+
 use floem::{
-    kurbo::{Line, Rect, Stroke},
+    kurbo::{BezPath, Circle, Point, Rect, Stroke, Vec2},
+    peniko::Color,
     prelude::{palette::css, *},
     text::{Attrs, AttrsList, FamilyOwned, TextLayout},
 };
 
-use crate::file::File;
+use super::calculate_positions::{PositionedFile, positioned_files};
+use super::canva_state::node_size;
+use super::clustered_layout::HEADER_HEIGHT;
+use super::preview_state::Preview;
+use super::selection_state::AreaShape;
+use super::spatial_index::overlaps;
+use crate::file::{File, SymbolKind};
+use crate::workspace_graph::EdgeKind;
+use petgraph::graph::NodeIndex;
+
+/// Zoom level below which files are gathered into their directory.
+const FILES_MIN_ZOOM: f64 = 0.4;
+
+/// Zoom level from which files are expanded into their exported symbols.
+const SYMBOLS_MIN_ZOOM: f64 = 2.0;
+
+/// Zoom level from which the first lines of the files are shown.
+const PREVIEWS_MIN_ZOOM: f64 = 2.5;
+
+/// Size of the text of the previews, in canvas units.
+const PREVIEW_FONT_SIZE: f64 = 4.0;
+
+/// Color of the selected nodes, and of the area dragged to select them.
+const SELECTION_COLOR: Color = css::LIGHT_SKY_BLUE;
+
+/// Color of the edges from the hovered or selected nodes to the nodes they
+/// import.
+const DEPENDENCY_COLOR: Color = css::SPRING_GREEN;
+
+/// Color of the edges to the hovered or selected nodes from the nodes
+/// importing them.
+const DEPENDENT_COLOR: Color = css::HOT_PINK;
+
+/// Opacity of the nodes and edges away from the hovered or selected nodes.
+const DIMMED_ALPHA: f32 = 0.15;
+
+/// How far the middle of an edge bends away from the straight line between
+/// its ends, relative to its length. Edges bend to their left, so that the
+/// imports going both ways between two nodes do not overlap.
+const EDGE_CURVATURE: f64 = 0.15;
+
+/// Width of an edge standing for a single import, and of a dimmed one.
+const EDGE_WIDTH: f64 = 2.0;
+const DIMMED_EDGE_WIDTH: f64 = 1.0;
+
+/// How much wider than an edge standing for a single import an edge can
+/// get, however many imports it stands for.
+const MAX_EDGE_WIDTH_FACTOR: f64 = 3.0;
+
+/// Room left around the screen when looking for the nodes to draw, in
+/// pixels, so that the labels of the nodes just off screen are drawn.
+const VIEWPORT_MARGIN: f64 = 200.0;
+
+/// What is drawn of the graph, from the farthest zoom level to the closest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LevelOfDetail {
+    /// Directories and external packages, each standing for its files.
+    Directories,
+    /// Files with their name.
+    Files,
+    /// Files surrounded by their exported symbols.
+    Symbols,
+    /// Symbols, along with a highlighted preview of the first lines of the
+    /// files.
+    Previews,
+}
+
+impl LevelOfDetail {
+    fn from_zoom(zoom: f64) -> Self {
+        if zoom < FILES_MIN_ZOOM {
+            LevelOfDetail::Directories
+        } else if zoom < SYMBOLS_MIN_ZOOM {
+            LevelOfDetail::Files
+        } else if zoom < PREVIEWS_MIN_ZOOM {
+            LevelOfDetail::Symbols
+        } else {
+            LevelOfDetail::Previews
+        }
+    }
+}
+
+fn symbol_color(kind: SymbolKind) -> Color {
+    match kind {
+        SymbolKind::Function => css::MEDIUM_SEA_GREEN,
+        SymbolKind::Class => css::GOLDENROD,
+        SymbolKind::Interface | SymbolKind::TypeAlias => css::STEEL_BLUE,
+        SymbolKind::Enum => css::MEDIUM_PURPLE,
+        SymbolKind::Variable => css::SLATE_GRAY,
+    }
+}
+
+/// External packages and collapsed directories are drawn apart from the
+/// workspace files.
+fn node_color(file: &File) -> Color {
+    match &file.symbol {
+        Some(symbol) => symbol_color(symbol.kind),
+        None if file.external => css::GRAY,
+        None if file.directory => css::DARK_ORANGE,
+        None => css::BLUE,
+    }
+}
+
+/// Color of a highlighting scope, as in the default dark theme.
+fn syntax_color(scope: &str) -> Color {
+    let (r, g, b) = match scope {
+        "comment" => (0x5C, 0x63, 0x70),
+        "constant"
+        | "type"
+        | "attribute"
+        | "constructor"
+        | "punctuation.delimiter"
+        | "text.reference" => (0xE5, 0xC0, 0x7B),
+        "function" | "tag" | "markup.link.url" => (0x61, 0xAF, 0xEF),
+        "keyword" | "markup.link.label" | "markup.link.text" => (0xC6, 0x78, 0xDD),
+        "property" | "variable" | "variable.other.member" | "markup.heading" => {
+            (0xE0, 0x6C, 0x75)
+        }
+        "string" => (0x98, 0xC3, 0x79),
+        "type.builtin" | "escape" | "string.escape" | "embedded" | "text.uri" => {
+            (0x56, 0xB6, 0xC2)
+        }
+        "text.title" | "markup.bold" | "markup.italic" | "markup.list" => {
+            (0xD1, 0x9A, 0x66)
+        }
+        _ => (0xAB, 0xB2, 0xBF),
+    };
+    Color::from_rgb8(r, g, b)
+}
+
+pub(super) fn edge_color(kind: EdgeKind) -> Color {
+    match kind {
+        EdgeKind::StaticImport => css::WHITE,
+        EdgeKind::TypeImport => css::LIGHT_SKY_BLUE,
+        EdgeKind::DynamicImport => css::ORANGE,
+        EdgeKind::Require => css::KHAKI,
+        EdgeKind::ReExport => css::VIOLET,
+        EdgeKind::ModuleDeclaration => css::LIGHT_GREEN,
+    }
+}
+
+/// The width of an edge standing for `weight` imports, growing with the
+/// logarithm of the weight so that heavy edges do not hide the others.
+fn edge_width(weight: f64, width: f64) -> f64 {
+    width * (1.0 + weight.max(1.0).log2() / 2.0).min(MAX_EDGE_WIDTH_FACTOR)
+}
+
+/// The point an unbundled edge from `from` to `to` bends through.
+fn edge_bend(from: Point, to: Point) -> Point {
+    let direction = to - from;
+    from.midpoint(to) + Vec2::new(direction.y, -direction.x) * EDGE_CURVATURE
+}
+
+/// Draws a curve from the first of `points` to the last one, bending through
+/// the others and ending with an arrowhead. The curve stops `target_radius`
+/// before its end, so that the head stays visible next to the target node.
+fn draw_edge(
+    cx: &mut floem::context::PaintCx<'_>,
+    points: &[Point],
+    target_radius: f64,
+    color: Color,
+    width: f64,
+) {
+    let [from, bends @ .., to] = points else {
+        return;
+    };
+    if from.distance(*to) <= target_radius {
+        return;
+    }
+    let head_length = (width * 3.0).clamp(6.0, 18.0);
+    // Bends hidden under the target node or its arrowhead are left out
+    let bends = match bends
+        .iter()
+        .rposition(|bend| bend.distance(*to) > target_radius + head_length)
+    {
+        Some(last) => &bends[..=last],
+        None => &[],
+    };
+    let last = bends.last().unwrap_or(from);
+    let unit = (*to - *last) / last.distance(*to);
+    let tip = *to - unit * target_radius;
+    let base = tip - unit * head_length;
+
+    // Goes through the middles between the bends, which keeps the curve
+    // smooth
+    let mut path = BezPath::new();
+    path.move_to(*from);
+    for pair in bends.windows(2) {
+        path.quad_to(pair[0], pair[0].midpoint(pair[1]));
+    }
+    match bends.last() {
+        Some(bend) => path.quad_to(*bend, base),
+        None => path.line_to(base),
+    }
+    cx.stroke(&path, color, &Stroke::new(width));
+
+    let normal = Vec2::new(-unit.y, unit.x) * head_length / 2.0;
+    let mut head = BezPath::new();
+    head.move_to(tip);
+    head.line_to(base + normal);
+    head.line_to(base - normal);
+    head.close_path();
+    cx.fill(&head, color, 0.0);
+}
+
+/// Draws the first lines of a file in a box with its top left corner at
+/// `origin`.
+fn draw_preview(
+    cx: &mut floem::context::PaintCx<'_>,
+    preview: &Preview,
+    origin: Point,
+    zoom: f64,
+) {
+    if preview.text.is_empty() {
+        return;
+    }
+    let family = [FamilyOwned::Monospace];
+    let attrs = Attrs::new()
+        .family(&family)
+        .font_size((PREVIEW_FONT_SIZE * zoom) as f32)
+        .color(syntax_color(""));
+    let mut attrs_list = AttrsList::new(attrs.clone());
+    for (range, scope) in &preview.styles {
+        attrs_list.add_span(range.clone(), attrs.clone().color(syntax_color(scope)));
+    }
+    let mut text_layout = TextLayout::new();
+    text_layout.set_text(&preview.text, attrs_list);
+
+    let padding = 2.0 * zoom;
+    let rect =
+        Rect::from_origin_size(origin, text_layout.size()).inflate(padding, padding);
+    cx.fill(
+        &rect.to_rounded_rect(padding),
+        Color::from_rgb8(0x28, 0x2C, 0x34),
+        0.0,
+    );
+    cx.draw_text(&text_layout, origin);
+}
+
+/// The directory a node is gathered into when zoomed out, or the node itself
+/// for external packages and collapsed directories.
+fn directory_of(file: &File) -> String {
+    if file.external || file.directory {
+        return file.name.clone();
+    }
+    Path::new(file.file_name()).parent().map_or_else(
+        || file.name.clone(),
+        |directory| directory.to_string_lossy().into_owned(),
+    )
+}
+
+/// The files gathered into a directory when zoomed out.
+#[derive(Default)]
+struct DirectoryNode {
+    x: f64,
+    y: f64,
+    file_count: usize,
+    external: bool,
+}
 
 impl super::workspace_layout::WorkspaceLayout {
     pub fn draw(
         &self,
         cx: &mut floem::context::PaintCx<'_>,
-        _size: floem::kurbo::Size,
+        size: floem::kurbo::Size,
     ) -> () {
         let zoom = self.view_state.zoom.get();
         let translation_x = self.view_state.translation_x.get();
         let translation_y = self.view_state.translation_y.get();
+        let level = LevelOfDetail::from_zoom(zoom);
 
-        let positions = self.calculate_positions();
-
-        // Draw edges
-        for pos in positions.iter() {
-            let pos_u = &pos.1;
-            for pos_v in &pos.2 {
-                let x1 = pos_u.x * zoom + translation_x;
-                let y1 = pos_u.y * zoom + translation_y;
-                let x2 = pos_v.x * zoom + translation_x;
-                let y2 = pos_v.y * zoom + translation_y;
-
-                cx.stroke(
-                    &Line::new((x1, y1), (x2, y2)),
-                    css::WHITE,
-                    &Stroke::new(4.0),
+        // Draw the directories below the files they contain
+        let mut regions: Vec<(String, (f64, f64, f64, f64))> = vec![];
+        self.layout_state.regions.with(|layout_regions| {
+            for region in layout_regions {
+                let rect = Rect::new(
+                    region.min.x * zoom + translation_x,
+                    region.min.y * zoom + translation_y,
+                    region.max.x * zoom + translation_x,
+                    region.max.y * zoom + translation_y,
                 );
+                let header = rect.with_size((
+                    rect.width(),
+                    (HEADER_HEIGHT * zoom).min(rect.height()),
+                ));
+                cx.fill(&rect, css::WHITE.with_alpha(0.04), 0.0);
+                cx.fill(&header, css::WHITE.with_alpha(0.12), 0.0);
+                cx.stroke(&rect, css::DIM_GRAY, &Stroke::new(1.0));
+
+                let mut text_layout = TextLayout::new();
+                text_layout.set_text(
+                    &region.label,
+                    AttrsList::new(
+                        Attrs::new()
+                            .family(&[FamilyOwned::SansSerif])
+                            .color(css::LIGHT_GRAY),
+                    ),
+                );
+                cx.draw_text(&text_layout, (header.x0 + 6.0, header.y0 + 4.0));
+
+                regions.push((
+                    region.directory.clone(),
+                    (header.x0, header.y0, header.x1, header.y1),
+                ));
             }
+        });
+        self.canva_state.set_regions(regions);
+
+        self.cluster_state.visible_graph.with(|workspace_graph| {
+            if level == LevelOfDetail::Directories {
+                let positions = self
+                    .layout_state
+                    .positions
+                    .with(|positions| positioned_files(workspace_graph, positions));
+                self.draw_directories(
+                    cx,
+                    &positions,
+                    zoom,
+                    Vec2::new(translation_x, translation_y),
+                );
+                // Files are too small to be pointed at
+                self.canva_state.set_files(Vec::new());
+                return;
+            }
+            let show_symbols = level >= LevelOfDetail::Symbols;
+
+            // Only what is on the screen is drawn, with some room for the
+            // labels of the nodes next to it
+            let margin = VIEWPORT_MARGIN / zoom;
+            let (x0, y0, x1, y1) = self.viewport();
+            let viewport = (x0 - margin, y0 - margin, x1 + margin, y1 + margin);
+            let mut visible_nodes: Vec<NodeIndex> =
+                self.canva_state.nodes.with(|nodes| {
+                    nodes.in_area(viewport).into_iter().copied().collect()
+                });
+            // Nodes are indexed in no particular order, while they should
+            // overlap the same way from one frame to the next
+            visible_nodes.sort_unstable();
+
+            let search_matches = self.search_matches();
+            let hovered_file = self.get_hovered_file();
+            let neighbourhood = self.neighbourhood(workspace_graph);
+            let ego_network = self.ego_network(workspace_graph);
+            let bundles = self.bundling_state.bundles.get();
+            let is_shown = |node_idx: NodeIndex| {
+                ego_network
+                    .as_ref()
+                    .is_none_or(|ego_network| ego_network.contains(&node_idx))
+            };
+
+            let mut files: Vec<(NodeIndex, (f64, f64, f64, f64))> = vec![];
+            let mut previews: Vec<(String, Point)> = vec![];
+            self.layout_state.positions.with(|positions| {
+                // Draw edges, from the importing file to the imported one, the
+                // ones of the hovered or selected nodes above the others. Only
+                // the edges of the nodes next to the screen are drawn
+                let edges = self
+                    .canva_state
+                    .edges
+                    .with(|edges| edges.touching(&visible_nodes));
+                let mut emphasised_edges = vec![];
+                for edge in edges {
+                    let (source, target) = (edge.source, edge.target);
+                    if !is_shown(source) || !is_shown(target) {
+                        continue;
+                    }
+                    if workspace_graph.graph[target].symbol.is_some()
+                        && !show_symbols
+                    {
+                        continue;
+                    }
+                    let (Some(pos_u), Some(pos_v)) =
+                        (positions.get(&source), positions.get(&target))
+                    else {
+                        continue;
+                    };
+                    let from = Point::new(pos_u.x, pos_u.y);
+                    let to = Point::new(pos_v.x, pos_v.y);
+                    let bends = bundles
+                        .get(&(source, target))
+                        .cloned()
+                        .unwrap_or_else(|| vec![edge_bend(from, to)]);
+                    let points: Vec<Point> = std::iter::once(from)
+                        .chain(bends)
+                        .chain(std::iter::once(to))
+                        .collect();
+                    let bounds = points.iter().fold(
+                        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+                        |(x0, y0, x1, y1), point| {
+                            (
+                                x0.min(point.x),
+                                y0.min(point.y),
+                                x1.max(point.x),
+                                y1.max(point.y),
+                            )
+                        },
+                    );
+                    if !overlaps(bounds, viewport) {
+                        continue;
+                    }
+                    let points: Vec<Point> = points
+                        .into_iter()
+                        .map(|point| {
+                            Point::new(
+                                point.x * zoom + translation_x,
+                                point.y * zoom + translation_y,
+                            )
+                        })
+                        .collect();
+                    let target_radius =
+                        node_size(&workspace_graph.graph[target]) * zoom / 2.0;
+                    match &neighbourhood {
+                        None => draw_edge(
+                            cx,
+                            &points,
+                            target_radius,
+                            edge_color(edge.kind),
+                            edge_width(edge.weight, EDGE_WIDTH),
+                        ),
+                        Some(neighbourhood)
+                            if neighbourhood.focus.contains(&source) =>
+                        {
+                            emphasised_edges.push((
+                                points,
+                                target_radius,
+                                DEPENDENCY_COLOR,
+                                edge.weight,
+                            ));
+                        }
+                        Some(neighbourhood)
+                            if neighbourhood.focus.contains(&target) =>
+                        {
+                            emphasised_edges.push((
+                                points,
+                                target_radius,
+                                DEPENDENT_COLOR,
+                                edge.weight,
+                            ));
+                        }
+                        Some(_) => draw_edge(
+                            cx,
+                            &points,
+                            target_radius,
+                            edge_color(edge.kind).with_alpha(DIMMED_ALPHA),
+                            edge_width(edge.weight, DIMMED_EDGE_WIDTH),
+                        ),
+                    }
+                }
+                for (points, target_radius, color, weight) in emphasised_edges {
+                    draw_edge(
+                        cx,
+                        &points,
+                        target_radius,
+                        color,
+                        edge_width(weight, EDGE_WIDTH),
+                    );
+                }
+
+                // Draw nodes
+                for node_idx in visible_nodes {
+                    let (Some(file), Some(position)) = (
+                        workspace_graph.graph.node_weight(node_idx),
+                        positions.get(&node_idx),
+                    ) else {
+                        continue;
+                    };
+                    if file.symbol.is_some() && !show_symbols {
+                        continue;
+                    }
+                    if !is_shown(node_idx) {
+                        continue;
+                    }
+                    let x = position.x * zoom + translation_x;
+                    let y = position.y * zoom + translation_y;
+                    let side = node_size(file) * zoom;
+                    let dimmed =
+                        neighbourhood.as_ref().is_some_and(|neighbourhood| {
+                            !neighbourhood.contains(node_idx)
+                        });
+
+                    let rect = Rect::from_center_size((x, y), (side, side));
+                    let color = node_color(file);
+                    if dimmed {
+                        cx.fill(&rect, color.with_alpha(DIMMED_ALPHA), 0.0);
+                    } else {
+                        cx.fill(&rect, color, 0.0);
+                    }
+                    if self.is_focused(&file.name) {
+                        cx.stroke(
+                            &rect.inflate(4.0, 4.0),
+                            css::WHITE,
+                            &Stroke::new(2.0),
+                        );
+                    } else if self.is_selected(&file.name) {
+                        cx.stroke(
+                            &rect.inflate(4.0, 4.0),
+                            SELECTION_COLOR,
+                            &Stroke::new(2.0),
+                        );
+                    } else if hovered_file.as_ref() == Some(&file.name) {
+                        cx.stroke(
+                            &rect.inflate(4.0, 4.0),
+                            css::WHITE.with_alpha(0.6),
+                            &Stroke::new(1.0),
+                        );
+                    }
+                    if search_matches.contains(&file.name) {
+                        cx.stroke(
+                            &rect.inflate(2.0, 2.0),
+                            css::YELLOW,
+                            &Stroke::new(2.0),
+                        );
+                    }
+                    if self.is_pinned(&file.name) {
+                        cx.fill(
+                            &Circle::new((rect.x1, rect.y0), 3.0 * zoom.max(1.0)),
+                            css::WHITE,
+                            0.0,
+                        );
+                    }
+
+                    let file_name =
+                        file.name.split('/').last().unwrap_or(&file.name);
+                    let label = if let Some(symbol) = &file.symbol {
+                        symbol.name.clone()
+                    } else if file.external {
+                        file.name.clone()
+                    } else if file.directory {
+                        format!("{}/", file_name)
+                    } else {
+                        file_name.to_string()
+                    };
+                    let family = [FamilyOwned::SansSerif];
+                    let mut attrs = Attrs::new().family(&family);
+                    if dimmed {
+                        attrs = attrs.color(css::DIM_GRAY);
+                    }
+                    let mut text_layout = TextLayout::new();
+                    text_layout.set_text(&label, AttrsList::new(attrs));
+                    cx.draw_text(&text_layout, (x, y));
+
+                    let on_screen = rect.x1 >= 0.0
+                        && rect.y1 >= 0.0
+                        && rect.x0 <= size.width
+                        && rect.y0 <= size.height;
+                    if level == LevelOfDetail::Previews
+                        && on_screen
+                        && file.symbol.is_none()
+                        && !file.external
+                        && !file.directory
+                    {
+                        previews.push((
+                            file.name.clone(),
+                            Point::new(rect.x1 + 4.0 * zoom, rect.y0),
+                        ));
+                    }
+
+                    files.push((node_idx, (rect.x0, rect.y0, rect.x1, rect.y1)));
+                }
+            });
+            self.canva_state.set_files(files);
+
+            // Draw the previews above the nodes they could overlap
+            for (file_name, origin) in previews {
+                if let Some(preview) = self.preview(&file_name) {
+                    draw_preview(cx, &preview, origin, zoom);
+                }
+            }
+        });
+
+        self.draw_selection_area(cx);
+    }
+
+    /// Draws the area being dragged over the files to select.
+    fn draw_selection_area(&self, cx: &mut floem::context::PaintCx<'_>) {
+        self.selection_state.area.with(|area| {
+            let Some(area) = area else {
+                return;
+            };
+            let points = match area.shape {
+                AreaShape::Rectangle => {
+                    let (x0, y0, x1, y1) = area.bounds();
+                    vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+                }
+                AreaShape::Lasso => area.points.clone(),
+            };
+            let mut path = BezPath::new();
+            let mut points = points.into_iter();
+            if let Some(first) = points.next() {
+                path.move_to(first);
+            }
+            for point in points {
+                path.line_to(point);
+            }
+            path.close_path();
+            cx.fill(&path, SELECTION_COLOR.with_alpha(0.1), 0.0);
+            cx.stroke(&path, SELECTION_COLOR, &Stroke::new(1.0));
+        });
+    }
+
+    /// Draws a node for each directory at the center of its files, sized by
+    /// their number, and the imports between directories.
+    fn draw_directories(
+        &self,
+        cx: &mut floem::context::PaintCx<'_>,
+        positions: &[PositionedFile<'_>],
+        zoom: f64,
+        translation: Vec2,
+    ) {
+        let hidden_edge_kinds = self.filter_state.hidden_edge_kinds.get();
+        let mut directories: HashMap<String, DirectoryNode> = HashMap::new();
+        // The number of imports from a directory to another
+        let mut imports: HashMap<(String, String), f64> = HashMap::new();
+        for (file, position, targets) in positions {
+            if file.symbol.is_some() {
+                continue;
+            }
+            let directory = directory_of(file);
+            for (target, _, edge) in targets {
+                if hidden_edge_kinds.contains(&edge.kind) {
+                    continue;
+                }
+                let target_directory = directory_of(target);
+                if target_directory != directory {
+                    *imports
+                        .entry((directory.clone(), target_directory))
+                        .or_default() += edge.weight;
+                }
+            }
+            let node = directories.entry(directory).or_default();
+            node.x += position.x;
+            node.y += position.y;
+            node.file_count += 1;
+            node.external = file.external;
         }
 
-        let mut files: Vec<(File, (f64, f64, f64, f64))> = vec![];
-        // Draw nodes
-        for pos in positions.iter() {
-            let file = pos.0;
-            let x = pos.1.x * zoom + translation_x;
-            let y = pos.1.y * zoom + translation_y;
-            let size = 40.0 * zoom;
+        // The center and size of each directory on the canvas
+        let nodes: HashMap<&String, (Point, f64)> = directories
+            .iter()
+            .map(|(directory, node)| {
+                let center = Point::new(
+                    node.x / node.file_count as f64 * zoom + translation.x,
+                    node.y / node.file_count as f64 * zoom + translation.y,
+                );
+                let size = 40.0 * (node.file_count as f64).sqrt() * zoom;
+                (directory, (center, size))
+            })
+            .collect();
+
+        for ((from, to), weight) in &imports {
+            let (Some((from, _)), Some((to, to_size))) =
+                (nodes.get(from), nodes.get(to))
+            else {
+                continue;
+            };
+            draw_edge(
+                cx,
+                &[*from, edge_bend(*from, *to), *to],
+                to_size / 2.0,
+                css::WHITE.with_alpha(0.6),
+                edge_width(*weight, EDGE_WIDTH),
+            );
+        }
 
-            let rect = Rect::from_origin_size(
-                (x - size / 2.0, y - size / 2.0),
-                (size as f64, size as f64),
+        for (directory, (center, size)) in &nodes {
+            let rect = Rect::from_center_size(*center, (*size, *size));
+            let external = directories[*directory].external;
+            cx.fill(
+                &rect,
+                if external {
+                    css::GRAY
+                } else {
+                    css::DARK_ORANGE
+                },
+                0.0,
             );
-            cx.fill(&rect, css::BLUE, 0.0);
 
+            let label = if external {
+                directory.to_string()
+            } else {
+                let name = directory.split('/').last().unwrap_or(directory);
+                format!("{}/", name)
+            };
             let mut text_layout = TextLayout::new();
             text_layout.set_text(
-                &file.name.split('/').last().unwrap_or(&file.name),
+                &label,
                 AttrsList::new(Attrs::new().family(&[FamilyOwned::SansSerif])),
             );
-            cx.draw_text(&text_layout, (x, y));
+            cx.draw_text(&text_layout, *center);
+        }
+    }
+
+    /// Draws every file as a dot, and the part of the canvas shown on the
+    /// screen as a rectangle.
+    pub fn draw_minimap(
+        &self,
+        cx: &mut floem::context::PaintCx<'_>,
+        size: floem::kurbo::Size,
+    ) {
+        let Some(transform) = self.minimap_transform() else {
+            return;
+        };
+        self.cluster_state.visible_graph.with(|workspace_graph| {
+            self.layout_state.positions.with(|positions| {
+                for (node_idx, position) in positions {
+                    let Some(file) = workspace_graph.graph.node_weight(*node_idx)
+                    else {
+                        continue;
+                    };
+                    if file.symbol.is_some() {
+                        continue;
+                    }
+                    let (x, y) = transform.to_minimap(position.x, position.y);
+                    cx.fill(
+                        &Rect::from_center_size((x, y), (2.0, 2.0)),
+                        node_color(file),
+                        0.0,
+                    );
+                }
+            });
+        });
 
-            files.push((file.clone(), (x - size / 2.0, y - size / 2.0, x + size / 2.0, y + size / 2.0)));
+        let (x0, y0, x1, y1) = self.viewport();
+        let (x0, y0) = transform.to_minimap(x0, y0);
+        let (x1, y1) = transform.to_minimap(x1, y1);
+        // The view may be larger than the graph, or away from it
+        let viewport = Rect::new(x0, y0, x1, y1).intersect(size.to_rect());
+        if viewport.area() > 0.0 {
+            cx.fill(&viewport, css::WHITE.with_alpha(0.1), 0.0);
+            cx.stroke(&viewport, css::WHITE, &Stroke::new(1.0));
         }
-        self.canva_state.set_files(files);
     }
 }
@@ -0,0 +1,180 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::PathBuf;
+
+use super::workspace_layout::WorkspaceLayout;
+use floem::action::open_file;
+use floem::file::FileDialogOptions;
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _};
+use floem::views::editor::text::SystemClipboard;
+use lapce_core::register::Clipboard as _;
+use petgraph::Direction;
+
+/// The files waiting for the user to confirm they go to the trash. The
+/// files are moved through the proxy of the editor.
+#[derive(Debug, Clone)]
+pub struct FileActions {
+    pub pending_trash: RwSignal<Vec<PathBuf>>,
+}
+
+impl FileActions {
+    pub fn new() -> Self {
+        Self {
+            pending_trash: RwSignal::new(Vec::new()),
+        }
+    }
+}
+
+impl WorkspaceLayout {
+    /// The paths of the selected files and collapsed directories, leaving out
+    /// the external packages. Symbols stand for their file.
+    fn selected_paths(&self) -> Vec<PathBuf> {
+        let selected_files = self.selection_state.selected_files.get_untracked();
+        let paths: BTreeSet<PathBuf> =
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                selected_files
+                    .iter()
+                    .filter_map(|file_name| graph.find_file(file_name))
+                    .map(|node_idx| &graph.graph[node_idx])
+                    .filter(|file| !file.external)
+                    .map(|file| PathBuf::from(file.file_name()))
+                    .collect()
+            });
+        paths.into_iter().collect()
+    }
+
+    /// Opens the selected files side by side in the editor.
+    pub fn open_selected_files(&self) {
+        let selected_files = self.selection_state.selected_files.get_untracked();
+        let file_names: BTreeSet<String> = selected_files
+            .iter()
+            .filter_map(|file_name| self.file_to_open(file_name))
+            .collect();
+        if !file_names.is_empty() {
+            self.editor.open_files(file_names.into_iter().collect());
+        }
+    }
+
+    /// Copies the paths of the selection, one per line.
+    pub fn copy_selected_paths(&self) {
+        let paths: Vec<String> = self
+            .selected_paths()
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        let mut clipboard = SystemClipboard::new();
+        clipboard.put_string(paths.join("\n"));
+    }
+
+    /// Opens the folders holding the selection in the file explorer of the
+    /// system.
+    pub fn reveal_selected_files(&self) {
+        let folders: BTreeSet<PathBuf> = self
+            .selected_paths()
+            .into_iter()
+            .filter_map(|path| path.parent().map(PathBuf::from))
+            .collect();
+        for folder in folders {
+            if let Err(e) = open::that(&folder) {
+                eprintln!("Failed to reveal {}: {}", folder.display(), e);
+            }
+        }
+    }
+
+    /// Asks for a folder to move the selection to.
+    pub fn move_selected_files(&self) {
+        let paths = self.selected_paths();
+        if paths.is_empty() {
+            return;
+        }
+        let proxy = self.editor.proxy().clone();
+        let options = FileDialogOptions::new()
+            .title("Move to folder")
+            .select_directories()
+            .force_starting_directory(self.workspace_path.clone());
+        open_file(options, move |file| {
+            let Some(folder) = file.and_then(|mut file| file.path.pop()) else {
+                return;
+            };
+            for from in paths {
+                let Some(name) = from.file_name() else {
+                    continue;
+                };
+                let to = folder.join(name);
+                if to == from {
+                    continue;
+                }
+                proxy.rename_path(from.clone(), to, move |result| {
+                    if let Err(e) = result {
+                        eprintln!(
+                            "Failed to move {}: {}",
+                            from.display(),
+                            e.message
+                        );
+                    }
+                });
+            }
+        });
+    }
+
+    /// Asks for confirmation before moving the selection to the trash, as
+    /// it may have grown past the files picked by hand.
+    pub fn ask_to_trash_selected_files(&self) {
+        let paths = self.selected_paths();
+        if !paths.is_empty() {
+            self.file_actions.pending_trash.set(paths);
+        }
+    }
+
+    /// Moves the files waiting for confirmation to the trash. The graph
+    /// follows as the watcher sees the files go.
+    pub fn confirm_trash(&self) {
+        let paths = self.file_actions.pending_trash.get_untracked();
+        self.file_actions.pending_trash.set(Vec::new());
+        for path in paths {
+            self.editor.proxy().trash_path(path.clone(), move |result| {
+                if let Err(e) = result {
+                    eprintln!("Failed to trash {}: {}", path.display(), e.message);
+                }
+            });
+        }
+        self.selection_state.selected_files.set(HashSet::new());
+    }
+
+    pub fn cancel_trash(&self) {
+        self.file_actions.pending_trash.set(Vec::new());
+    }
+
+    /// Adds to the selection every node the selected ones lead to through the
+    /// edges in `direction`: their dependencies when it is outgoing, their
+    /// dependents when it is incoming.
+    pub fn select_linked_files(&self, direction: Direction) {
+        let selected_files = self.selection_state.selected_files.get_untracked();
+        let linked_files: Vec<String> =
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                let mut reached: HashSet<_> = selected_files
+                    .iter()
+                    .filter_map(|file_name| graph.find_file(file_name))
+                    .collect();
+                let mut to_visit: Vec<_> = reached.iter().copied().collect();
+                while let Some(node_idx) = to_visit.pop() {
+                    for linked_idx in
+                        graph.graph.neighbors_directed(node_idx, direction)
+                    {
+                        if reached.insert(linked_idx) {
+                            to_visit.push(linked_idx);
+                        }
+                    }
+                }
+                reached
+                    .into_iter()
+                    .map(|node_idx| graph.graph[node_idx].name.clone())
+                    .collect()
+            });
+        self.selection_state
+            .selected_files
+            .update(|selected_files| selected_files.extend(linked_files));
+    }
+}
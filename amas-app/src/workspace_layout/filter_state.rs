@@ -0,0 +1,33 @@
+use std::collections::HashSet;
+
+use super::workspace_layout::WorkspaceLayout;
+use crate::workspace_graph::EdgeKind;
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _};
+
+#[derive(Clone, Debug)]
+pub struct FilterState {
+    pub hidden_edge_kinds: RwSignal<HashSet<EdgeKind>>,
+}
+
+impl FilterState {
+    pub fn new() -> Self {
+        let hidden_edge_kinds = RwSignal::new(HashSet::new());
+        Self { hidden_edge_kinds }
+    }
+}
+
+impl WorkspaceLayout {
+    pub fn toggle_edge_kind(&self, kind: EdgeKind) {
+        self.filter_state
+            .hidden_edge_kinds
+            .update(|hidden_edge_kinds| {
+                if !hidden_edge_kinds.remove(&kind) {
+                    hidden_edge_kinds.insert(kind);
+                }
+            });
+    }
+
+    pub fn is_edge_kind_visible(&self, kind: EdgeKind) -> bool {
+        !self.filter_state.hidden_edge_kinds.get().contains(&kind)
+    }
+}
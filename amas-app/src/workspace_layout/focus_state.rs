@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+
+use super::workspace_layout::WorkspaceLayout;
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _};
+use petgraph::Direction as EdgeDirection;
+use petgraph::graph::NodeIndex;
+
+/// How much closer a node imported by or importing the focused one counts,
+/// so that the focus rather moves along the edges.
+const LINKED_DISTANCE_FACTOR: f64 = 0.5;
+
+/// How much the distance across the direction of a move counts, compared
+/// to the distance along it.
+const ACROSS_DISTANCE_FACTOR: f64 = 2.0;
+
+/// Where the arrow keys move the focus on the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    fn vector(self) -> (f64, f64) {
+        match self {
+            Direction::Left => (-1.0, 0.0),
+            Direction::Right => (1.0, 0.0),
+            Direction::Up => (0.0, -1.0),
+            Direction::Down => (0.0, 1.0),
+        }
+    }
+}
+
+/// A walk through the imports, then the importers, of a node.
+#[derive(Debug, Clone)]
+struct NeighbourWalk {
+    file_name: String,
+    /// The index of the neighbour reached, and its name.
+    index: usize,
+    reached: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct FocusState {
+    /// The node moved to with the keyboard, which is selected too.
+    pub focused_file: RwSignal<Option<String>>,
+    walk: RwSignal<Option<NeighbourWalk>>,
+}
+
+impl FocusState {
+    pub fn new() -> Self {
+        let focused_file = RwSignal::new(None);
+        let walk = RwSignal::new(None);
+        Self { focused_file, walk }
+    }
+}
+
+impl WorkspaceLayout {
+    /// The focused node, which is the selected one when the focus was not
+    /// moved with the keyboard since.
+    fn focused_file(&self) -> Option<String> {
+        let selected_files = self.get_selected_files();
+        self.focus_state
+            .focused_file
+            .get_untracked()
+            .filter(|file_name| selected_files.contains(file_name))
+            .or_else(|| {
+                let mut selected_files = selected_files.into_iter();
+                let file_name = selected_files.next()?;
+                selected_files.next().is_none().then_some(file_name)
+            })
+    }
+
+    /// Focuses and selects `file_name`, moving the view to it.
+    fn focus_file(&self, file_name: String) {
+        self.selection_state
+            .selected_files
+            .set(HashSet::from([file_name.clone()]));
+        self.focus_state.focused_file.set(Some(file_name.clone()));
+        self.fly_to_file(&file_name);
+    }
+
+    /// Moves the focus to the nearest node in `direction`, rather following
+    /// the edges of the focused node. Without a focused node, focuses the
+    /// node nearest to the middle of the screen.
+    pub fn move_focus(&self, direction: Direction) {
+        self.focus_state.walk.set(None);
+        let (x0, y0, x1, y1) = self.viewport();
+        let focused_file = self.focused_file();
+        let file_name = self.cluster_state.visible_graph.with_untracked(|graph| {
+            self.layout_state.positions.with_untracked(|positions| {
+                let focused = focused_file
+                    .as_deref()
+                    .and_then(|file_name| graph.find_file(file_name))
+                    .and_then(|node_idx| {
+                        Some((node_idx, positions.get(&node_idx)?))
+                    });
+                let nodes = positions.iter().filter(|(node_idx, _)| {
+                    graph
+                        .graph
+                        .node_weight(**node_idx)
+                        .is_some_and(|file| file.symbol.is_none())
+                });
+                let nearest = match focused {
+                    Some((focused_idx, focused_position)) => {
+                        let (dx, dy) = direction.vector();
+                        let linked: HashSet<NodeIndex> =
+                            graph.graph.neighbors_undirected(focused_idx).collect();
+                        nodes
+                            .filter(|(node_idx, _)| **node_idx != focused_idx)
+                            .filter_map(|(node_idx, position)| {
+                                let x = position.x - focused_position.x;
+                                let y = position.y - focused_position.y;
+                                let along = x * dx + y * dy;
+                                if along <= 0.0 {
+                                    return None;
+                                }
+                                let across = (x * dy - y * dx).abs();
+                                let mut distance =
+                                    along + across * ACROSS_DISTANCE_FACTOR;
+                                if linked.contains(node_idx) {
+                                    distance *= LINKED_DISTANCE_FACTOR;
+                                }
+                                Some((*node_idx, distance))
+                            })
+                            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    }
+                    None => {
+                        let x = (x0 + x1) / 2.0;
+                        let y = (y0 + y1) / 2.0;
+                        nodes
+                            .map(|(node_idx, position)| {
+                                let distance =
+                                    (position.x - x).hypot(position.y - y);
+                                (*node_idx, distance)
+                            })
+                            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    }
+                };
+                nearest.map(|(node_idx, _)| graph.graph[node_idx].name.clone())
+            })
+        });
+        if let Some(file_name) = file_name {
+            self.focus_file(file_name);
+        }
+    }
+
+    /// Moves the focus to the next, or previous, of the imports and then the
+    /// importers of the node the walk started from.
+    pub fn walk_neighbours(&self, forward: bool) {
+        let Some(focused_file) = self.focused_file() else {
+            // Focuses the node in the middle of the screen
+            self.move_focus(Direction::Right);
+            return;
+        };
+        // Keep walking around the same node while the focus stays where the
+        // walk left it
+        let (file_name, index) = match self
+            .focus_state
+            .walk
+            .get_untracked()
+            .filter(|walk| walk.reached == focused_file)
+        {
+            Some(walk) => (walk.file_name, Some(walk.index)),
+            None => (focused_file, None),
+        };
+        let neighbours: Vec<String> =
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                let Some(node_idx) = graph.find_file(&file_name) else {
+                    return Vec::new();
+                };
+                let imports = graph
+                    .graph
+                    .neighbors_directed(node_idx, EdgeDirection::Outgoing);
+                let importers = graph
+                    .graph
+                    .neighbors_directed(node_idx, EdgeDirection::Incoming);
+                let mut seen = HashSet::new();
+                imports
+                    .chain(importers)
+                    .filter(|neighbour_idx| seen.insert(*neighbour_idx))
+                    .map(|neighbour_idx| &graph.graph[neighbour_idx])
+                    .filter(|file| file.symbol.is_none())
+                    .map(|file| file.name.clone())
+                    .collect()
+            });
+        if neighbours.is_empty() {
+            return;
+        }
+        let count = neighbours.len();
+        let index = match (index, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(index), true) => (index + 1) % count,
+            (Some(index), false) => (index + count - 1) % count,
+        };
+        let reached = neighbours[index].clone();
+        self.focus_file(reached.clone());
+        self.focus_state.walk.set(Some(NeighbourWalk {
+            file_name,
+            index,
+            reached,
+        }));
+    }
+
+    pub fn open_focused_file(&self) {
+        if let Some(file_name) = self.focused_file() {
+            self.open_file(&file_name);
+        }
+    }
+
+    /// Zooms around the focused node, or the middle of the screen without
+    /// one, opening the focused file when zooming in past the open zoom
+    /// level.
+    pub fn zoom_around_focus(&self, factor: f64) {
+        let zoom = self.view_state.zoom.get_untracked();
+        let focused_position = self.focused_file().and_then(|file_name| {
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                let node_idx = graph.find_file(&file_name)?;
+                self.layout_state
+                    .positions
+                    .with_untracked(|positions| positions.get(&node_idx).cloned())
+            })
+        });
+        let (x, y) = match focused_position {
+            Some(position) => (
+                position.x * zoom + self.view_state.translation_x.get_untracked(),
+                position.y * zoom + self.view_state.translation_y.get_untracked(),
+            ),
+            None => (
+                self.view_state.width.get_untracked() / 2.0,
+                self.view_state.height.get_untracked() / 2.0,
+            ),
+        };
+        self.zoom_around(factor, x, y);
+        self.open_when_zoomed_in(factor, self.focused_file());
+    }
+
+    /// Whether `file_name` is the node focused with the keyboard.
+    pub fn is_focused(&self, file_name: &str) -> bool {
+        self.focus_state
+            .focused_file
+            .with(|focused_file| focused_file.as_deref() == Some(file_name))
+            && self
+                .selection_state
+                .selected_files
+                .with(|selected_files| selected_files.contains(file_name))
+    }
+}
@@ -0,0 +1,520 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::read_settings;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Position {
+    pub fn new(x: f64, y: f64) -> Self {
+        Position { x, y }
+    }
+
+    fn distance(&self, other: &Position) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+/// How the force layout runs, read from the `[amas.layout]` table of the
+/// settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LayoutSettings {
+    /// Barnes-Hut accuracy: a group of nodes is approximated by its center of
+    /// mass when its width divided by its distance is below `theta`. `0.0`
+    /// computes every pair of nodes exactly, higher values are faster but
+    /// coarser.
+    pub theta: f64,
+    /// Maximum number of iterations of the simulation.
+    pub iterations: usize,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            theta: 0.8,
+            iterations: 100,
+        }
+    }
+}
+
+impl LayoutSettings {
+    /// Loads the settings of the user, falling back to the defaults when
+    /// they are missing or do not make sense.
+    pub fn load() -> Self {
+        let Some(settings) = read_settings::<LayoutSettings>("layout") else {
+            return Self::default();
+        };
+        if !settings.is_valid() {
+            eprintln!("Ignoring the invalid layout settings: {:?}", settings);
+            return Self::default();
+        }
+        settings
+    }
+
+    fn is_valid(&self) -> bool {
+        self.theta.is_finite() && self.theta >= 0.0
+    }
+}
+
+/// Size of the area the smallest graphs are laid out in.
+const MIN_WIDTH: f64 = 800.0;
+const MIN_HEIGHT: f64 = 600.0;
+
+/// Number of nodes laid out in the smallest area, past which the area grows
+/// so that each node keeps the same room.
+const MIN_AREA_NODES: f64 = 100.0;
+
+/// Size of the area `node_count` nodes are laid out in. Its sides grow with
+/// the square root of the number of nodes, so that large graphs are not
+/// crammed into the room of small ones.
+pub fn layout_size(node_count: usize) -> (f64, f64) {
+    let scale = (node_count as f64 / MIN_AREA_NODES).sqrt().max(1.0);
+    (MIN_WIDTH * scale, MIN_HEIGHT * scale)
+}
+
+/// Temperature below which the simulation is considered settled.
+const MIN_TEMPERATURE: f64 = 0.5;
+
+/// A Fruchterman-Reingold simulation, where the repulsion between nodes is
+/// approximated with a Barnes-Hut quadtree.
+///
+/// Nodes are identified by their index in the positions given to
+/// [`ForceLayout::new`].
+pub struct ForceLayout {
+    positions: Vec<Position>,
+    /// Whether each node stays where it is, still pushing and pulling the
+    /// others.
+    pinned: Vec<bool>,
+    edges: Vec<(usize, usize)>,
+    settings: LayoutSettings,
+    width: f64,
+    height: f64,
+    k: f64,
+    temperature: f64,
+    cooling_factor: f64,
+    iteration: usize,
+}
+
+impl ForceLayout {
+    /// Nodes without an initial position are placed on a circle.
+    pub fn new(
+        initial_positions: Vec<Option<Position>>,
+        edges: Vec<(usize, usize)>,
+        width: f64,
+        height: f64,
+        settings: LayoutSettings,
+    ) -> Self {
+        let center_x = width / 2.0;
+        let center_y = height / 2.0;
+        let radius = width.min(height) / 2.5;
+
+        let total = initial_positions.len();
+        let reused_positions =
+            initial_positions.iter().filter(|p| p.is_some()).count();
+        let positions = initial_positions
+            .into_iter()
+            .enumerate()
+            .map(|(i, position)| {
+                position.unwrap_or_else(|| {
+                    let angle = (i as f64 / total as f64) * std::f64::consts::TAU;
+                    Position::new(
+                        center_x + radius * angle.cos(),
+                        center_y + radius * angle.sin(),
+                    )
+                })
+            })
+            .collect();
+
+        let area = width * height;
+        let k = (area / total as f64).sqrt();
+
+        // Start cooler when most of the nodes are already settled, so that
+        // the layout does not shake whenever a file changes
+        let temperature = if reused_positions * 2 > total {
+            width / 40.0
+        } else {
+            width / 10.0
+        };
+
+        ForceLayout {
+            positions,
+            pinned: vec![false; total],
+            edges,
+            settings,
+            width,
+            height,
+            k,
+            temperature,
+            cooling_factor: 0.95,
+            iteration: 0,
+        }
+    }
+
+    /// Moves `node` to `position` and keeps it there.
+    pub fn pin(&mut self, node: usize, position: Position) {
+        self.positions[node] = position;
+        self.pinned[node] = true;
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.positions.is_empty()
+            || self.iteration >= self.settings.iterations
+            || self.temperature < MIN_TEMPERATURE
+    }
+
+    fn calculate_attractive_force(&self, distance: f64) -> f64 {
+        (distance * distance) / self.k
+    }
+
+    pub fn iterate(&mut self) {
+        let quad_tree = QuadTree::build(&self.positions);
+        let k_squared = self.k * self.k;
+        let mut displacements: Vec<(f64, f64)> = self
+            .positions
+            .par_iter()
+            .enumerate()
+            .map(|(node, position)| {
+                quad_tree.repulsion(node, position, self.settings.theta, k_squared)
+            })
+            .collect();
+
+        for &(node_u, node_v) in &self.edges {
+            let pos_u = &self.positions[node_u];
+            let pos_v = &self.positions[node_v];
+
+            let distance = pos_u.distance(pos_v);
+            if distance > 0.0 {
+                let attractive_force = self.calculate_attractive_force(distance);
+
+                let dx = (pos_v.x - pos_u.x) / distance;
+                let dy = (pos_v.y - pos_u.y) / distance;
+
+                displacements[node_u].0 += dx * attractive_force;
+                displacements[node_u].1 += dy * attractive_force;
+
+                displacements[node_v].0 -= dx * attractive_force;
+                displacements[node_v].1 -= dy * attractive_force;
+            }
+        }
+
+        // Gravité vers le centre
+        let center_x = self.width / 2.0;
+        let center_y = self.height / 2.0;
+        let gravity_strength = self.k * 0.02;
+        let circular_spring_strength = 0.01;
+        let ideal_radius = self.width.min(self.height) / 2.5;
+
+        for (pos, disp) in self.positions.iter().zip(&mut displacements) {
+            let dx = center_x - pos.x;
+            let dy = center_y - pos.y;
+
+            disp.0 += dx * gravity_strength;
+            disp.1 += dy * gravity_strength;
+
+            // Force pour rester sur le cercle
+            let to_center_dx = pos.x - center_x;
+            let to_center_dy = pos.y - center_y;
+            let dist =
+                (to_center_dx * to_center_dx + to_center_dy * to_center_dy).sqrt();
+            if dist > 0.0 {
+                let diff = dist - ideal_radius;
+                disp.0 -= (to_center_dx / dist) * diff * circular_spring_strength;
+                disp.1 -= (to_center_dy / dist) * diff * circular_spring_strength;
+            }
+        }
+
+        for ((pos, (dx, dy)), pinned) in self
+            .positions
+            .iter_mut()
+            .zip(displacements)
+            .zip(&self.pinned)
+        {
+            if *pinned {
+                continue;
+            }
+            let displacement_length = (dx * dx + dy * dy).sqrt();
+            if displacement_length > 0.0 {
+                let limited_displacement = displacement_length.min(self.temperature);
+                let normalized_dx = dx / displacement_length;
+                let normalized_dy = dy / displacement_length;
+
+                pos.x += normalized_dx * limited_displacement;
+                pos.y += normalized_dy * limited_displacement;
+
+                pos.x = pos.x.max(0.0).min(self.width);
+                pos.y = pos.y.max(0.0).min(self.height);
+            }
+        }
+
+        self.temperature *= self.cooling_factor;
+        self.iteration += 1;
+    }
+}
+
+/// Marks a leaf of the quadtree.
+const NO_CHILDREN: usize = 0;
+
+/// Depth past which nodes are not split anymore, as nodes at the same
+/// position would be split forever.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone)]
+struct Quad {
+    center_x: f64,
+    center_y: f64,
+    half_size: f64,
+    /// Number of nodes in the quad.
+    mass: f64,
+    /// Sum of the positions of the nodes in the quad, which divided by
+    /// `mass` gives their center of mass.
+    mass_x: f64,
+    mass_y: f64,
+    /// Index of the first of the 4 children of the quad, which are stored
+    /// next to each other, or `NO_CHILDREN` for a leaf. The root is at index
+    /// 0, so it is never a child.
+    children: usize,
+    /// The first node added to a leaf.
+    node: Option<usize>,
+}
+
+impl Quad {
+    fn new(center_x: f64, center_y: f64, half_size: f64) -> Self {
+        Quad {
+            center_x,
+            center_y,
+            half_size,
+            mass: 0.0,
+            mass_x: 0.0,
+            mass_y: 0.0,
+            children: NO_CHILDREN,
+            node: None,
+        }
+    }
+
+    fn add_mass(&mut self, position: &Position) {
+        self.mass += 1.0;
+        self.mass_x += position.x;
+        self.mass_y += position.y;
+    }
+}
+
+/// A Barnes-Hut quadtree, with its quads stored in a flat `Vec` rather than
+/// as boxed children.
+struct QuadTree {
+    quads: Vec<Quad>,
+}
+
+impl QuadTree {
+    fn build(positions: &[Position]) -> Self {
+        let (min_x, min_y, max_x, max_y) = positions.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), position| {
+                (
+                    min_x.min(position.x),
+                    min_y.min(position.y),
+                    max_x.max(position.x),
+                    max_y.max(position.y),
+                )
+            },
+        );
+        // The root is a square containing all the nodes
+        let half_size = ((max_x - min_x).max(max_y - min_y) / 2.0).max(1.0);
+        let root =
+            Quad::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0, half_size);
+
+        let mut quad_tree = QuadTree {
+            quads: Vec::with_capacity(positions.len() * 2),
+        };
+        quad_tree.quads.push(root);
+        for node in 0..positions.len() {
+            quad_tree.insert(node, positions);
+        }
+        quad_tree
+    }
+
+    fn insert(&mut self, node: usize, positions: &[Position]) {
+        let position = &positions[node];
+        let mut quad_idx = 0;
+        let mut depth = 0;
+        loop {
+            if self.quads[quad_idx].children == NO_CHILDREN {
+                match self.quads[quad_idx].node {
+                    None => {
+                        self.quads[quad_idx].node = Some(node);
+                        self.quads[quad_idx].add_mass(position);
+                        return;
+                    }
+                    Some(_) if depth >= MAX_DEPTH => {
+                        self.quads[quad_idx].add_mass(position);
+                        return;
+                    }
+                    Some(other) => {
+                        // Move the node of the leaf down into a new child
+                        self.subdivide(quad_idx);
+                        let child =
+                            self.child_containing(quad_idx, &positions[other]);
+                        self.quads[child].node = Some(other);
+                        self.quads[child].add_mass(&positions[other]);
+                        self.quads[quad_idx].node = None;
+                    }
+                }
+            }
+            self.quads[quad_idx].add_mass(position);
+            quad_idx = self.child_containing(quad_idx, position);
+            depth += 1;
+        }
+    }
+
+    fn subdivide(&mut self, quad_idx: usize) {
+        let quad = &self.quads[quad_idx];
+        let half_size = quad.half_size / 2.0;
+        let (center_x, center_y) = (quad.center_x, quad.center_y);
+        self.quads[quad_idx].children = self.quads.len();
+        for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            self.quads.push(Quad::new(
+                center_x + dx * half_size,
+                center_y + dy * half_size,
+                half_size,
+            ));
+        }
+    }
+
+    fn child_containing(&self, quad_idx: usize, position: &Position) -> usize {
+        let quad = &self.quads[quad_idx];
+        let right = usize::from(position.x >= quad.center_x);
+        let bottom = usize::from(position.y >= quad.center_y);
+        quad.children + right + bottom * 2
+    }
+
+    /// The repulsive force of every other node on `node`.
+    fn repulsion(
+        &self,
+        node: usize,
+        position: &Position,
+        theta: f64,
+        k_squared: f64,
+    ) -> (f64, f64) {
+        let mut displacement = (0.0, 0.0);
+        let mut stack = vec![0];
+        while let Some(quad_idx) = stack.pop() {
+            let quad = &self.quads[quad_idx];
+            if quad.mass == 0.0 || (quad.node == Some(node) && quad.mass == 1.0) {
+                continue;
+            }
+            let center_of_mass =
+                Position::new(quad.mass_x / quad.mass, quad.mass_y / quad.mass);
+            let distance = position.distance(&center_of_mass);
+
+            // Far enough quads act as a single node at their center of mass
+            let is_far = quad.half_size * 2.0 < theta * distance;
+            if quad.children != NO_CHILDREN && !is_far {
+                stack.extend(quad.children..quad.children + 4);
+                continue;
+            }
+            if distance > 0.0 {
+                let repulsive_force = k_squared * quad.mass / distance;
+                displacement.0 +=
+                    (position.x - center_of_mass.x) / distance * repulsive_force;
+                displacement.1 +=
+                    (position.y - center_of_mass.y) / distance * repulsive_force;
+            }
+        }
+        displacement
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions() -> Vec<Position> {
+        (0..200)
+            .map(|i| {
+                let i = i as f64;
+                Position::new((i * 37.0) % 800.0, (i * 53.0) % 600.0)
+            })
+            .collect()
+    }
+
+    fn exact_repulsion(
+        node: usize,
+        positions: &[Position],
+        k_squared: f64,
+    ) -> (f64, f64) {
+        let mut displacement = (0.0, 0.0);
+        for (other, other_position) in positions.iter().enumerate() {
+            let distance = positions[node].distance(other_position);
+            if other != node && distance > 0.0 {
+                let force = k_squared / distance;
+                displacement.0 +=
+                    (positions[node].x - other_position.x) / distance * force;
+                displacement.1 +=
+                    (positions[node].y - other_position.y) / distance * force;
+            }
+        }
+        displacement
+    }
+
+    #[test]
+    fn test_zero_theta_is_exact() {
+        let positions = positions();
+        let quad_tree = QuadTree::build(&positions);
+        for node in 0..positions.len() {
+            let (x, y) = quad_tree.repulsion(node, &positions[node], 0.0, 100.0);
+            let (expected_x, expected_y) = exact_repulsion(node, &positions, 100.0);
+            assert!((x - expected_x).abs() < 1e-6, "{x} != {expected_x}");
+            assert!((y - expected_y).abs() < 1e-6, "{y} != {expected_y}");
+        }
+    }
+
+    #[test]
+    fn test_nodes_at_the_same_position() {
+        let positions = vec![Position::new(10.0, 10.0); 100];
+        let quad_tree = QuadTree::build(&positions);
+        assert!(quad_tree.quads.len() <= 4 * MAX_DEPTH + 1);
+        assert_eq!(quad_tree.quads[0].mass, 100.0);
+    }
+
+    #[test]
+    fn test_pinned_nodes_stay_in_place() {
+        let mut layout = ForceLayout::new(
+            positions().into_iter().map(Some).collect(),
+            vec![(0, 1), (1, 2)],
+            800.0,
+            600.0,
+            LayoutSettings::default(),
+        );
+        layout.pin(1, Position::new(1000.0, -50.0));
+        for _ in 0..10 {
+            layout.iterate();
+        }
+        assert_eq!(layout.positions()[1].x, 1000.0);
+        assert_eq!(layout.positions()[1].y, -50.0);
+    }
+
+    #[test]
+    fn test_layout_size_grows_with_nodes() {
+        assert_eq!(layout_size(0), (800.0, 600.0));
+        assert_eq!(layout_size(100), (800.0, 600.0));
+        assert_eq!(layout_size(400), (1600.0, 1200.0));
+    }
+
+    #[test]
+    fn test_layout_settings_validity() {
+        assert!(LayoutSettings::default().is_valid());
+        let negative_theta = LayoutSettings {
+            theta: -1.0,
+            ..Default::default()
+        };
+        assert!(!negative_theta.is_valid());
+    }
+}
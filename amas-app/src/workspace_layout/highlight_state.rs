@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use super::workspace_layout::WorkspaceLayout;
+use crate::workspace_graph::WorkspaceGraph;
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _};
+use petgraph::graph::NodeIndex;
+
+/// The hops around the selection the ego network mode can be set to.
+pub(super) const EGO_NETWORK_HOPS: [usize; 3] = [1, 2, 3];
+
+/// The nodes emphasised while a node is hovered, or some are selected.
+#[derive(Debug, Clone)]
+pub(super) struct Neighbourhood {
+    /// The hovered node, or else the selected ones.
+    pub focus: HashSet<NodeIndex>,
+    /// The nodes imported by or importing the focus.
+    pub neighbours: HashSet<NodeIndex>,
+}
+
+impl Neighbourhood {
+    pub fn contains(&self, node_idx: NodeIndex) -> bool {
+        self.focus.contains(&node_idx) || self.neighbours.contains(&node_idx)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HighlightState {
+    /// How many hops around the selection are shown in the ego network
+    /// mode, or `None` to show the whole graph.
+    pub ego_hops: RwSignal<Option<usize>>,
+}
+
+impl HighlightState {
+    pub fn new() -> Self {
+        let ego_hops = RwSignal::new(None);
+        Self { ego_hops }
+    }
+}
+
+/// The nodes at most `hops` edges away from `start`, whichever way the edges
+/// go.
+fn nodes_within(
+    graph: &WorkspaceGraph,
+    start: HashSet<NodeIndex>,
+    hops: usize,
+) -> HashSet<NodeIndex> {
+    let mut reached = start.clone();
+    let mut frontier: Vec<NodeIndex> = start.into_iter().collect();
+    for _ in 0..hops {
+        frontier = frontier
+            .into_iter()
+            .flat_map(|node_idx| graph.graph.neighbors_undirected(node_idx))
+            .filter(|node_idx| reached.insert(*node_idx))
+            .collect();
+    }
+    reached
+}
+
+impl WorkspaceLayout {
+    fn selected_nodes(&self, graph: &WorkspaceGraph) -> HashSet<NodeIndex> {
+        self.selection_state.selected_files.with(|selected_files| {
+            selected_files
+                .iter()
+                .filter_map(|file_name| graph.find_file(file_name))
+                .collect()
+        })
+    }
+
+    /// The neighbourhood of the hovered node, or else of the selected ones,
+    /// or `None` when there is nothing to emphasise.
+    pub(super) fn neighbourhood(
+        &self,
+        graph: &WorkspaceGraph,
+    ) -> Option<Neighbourhood> {
+        let focus = match self.selection_state.hovered_file.get() {
+            Some(file_name) => graph.find_file(&file_name).into_iter().collect(),
+            None => self.selected_nodes(graph),
+        };
+        if focus.is_empty() {
+            return None;
+        }
+        let neighbours = focus
+            .iter()
+            .flat_map(|node_idx| graph.graph.neighbors_undirected(*node_idx))
+            .filter(|node_idx| !focus.contains(node_idx))
+            .collect();
+        Some(Neighbourhood { focus, neighbours })
+    }
+
+    /// The nodes shown in the ego network mode, or `None` when the whole
+    /// graph is shown, which it is too while nothing is selected.
+    pub(super) fn ego_network(
+        &self,
+        graph: &WorkspaceGraph,
+    ) -> Option<HashSet<NodeIndex>> {
+        let hops = self.highlight_state.ego_hops.get()?;
+        let selected_nodes = self.selected_nodes(graph);
+        if selected_nodes.is_empty() {
+            return None;
+        }
+        Some(nodes_within(graph, selected_nodes, hops))
+    }
+
+    pub fn set_ego_hops(&self, hops: Option<usize>) {
+        self.highlight_state.ego_hops.set(hops);
+    }
+
+    pub fn is_ego_hops(&self, hops: Option<usize>) -> bool {
+        self.highlight_state.ego_hops.get() == hops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::File;
+    use crate::workspace_graph::EdgeKind;
+
+    #[test]
+    fn test_nodes_within_hops() {
+        let mut graph = WorkspaceGraph::new();
+        let main = graph.add_file(File::new("/ws/main.ts".to_string()));
+        let a = graph.add_file(File::new("/ws/a.ts".to_string()));
+        let b = graph.add_file(File::new("/ws/b.ts".to_string()));
+        let c = graph.add_file(File::new("/ws/c.ts".to_string()));
+        let other = graph.add_file(File::new("/ws/other.ts".to_string()));
+        graph.add_import(main, a, EdgeKind::StaticImport);
+        graph.add_import(b, a, EdgeKind::StaticImport);
+        graph.add_import(c, b, EdgeKind::StaticImport);
+
+        let start = HashSet::from([a]);
+        assert_eq!(nodes_within(&graph, start.clone(), 0), start);
+        assert_eq!(
+            nodes_within(&graph, start.clone(), 1),
+            HashSet::from([main, a, b])
+        );
+        assert_eq!(
+            nodes_within(&graph, start, 2),
+            HashSet::from([main, a, b, c])
+        );
+        assert!(!nodes_within(&graph, HashSet::from([main]), 3).contains(&other));
+    }
+}
@@ -0,0 +1,34 @@
+use super::workspace_layout::WorkspaceLayout;
+use crate::workspace_graph::IndexingProgress;
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _};
+
+#[derive(Clone, Debug)]
+pub struct IndexingState {
+    /// The progress of the indexing of the workspace, `None` once the graph
+    /// is fully fed.
+    pub progress: RwSignal<Option<IndexingProgress>>,
+}
+
+impl IndexingState {
+    pub fn new() -> Self {
+        let progress = RwSignal::new(Some(IndexingProgress::default()));
+        Self { progress }
+    }
+}
+
+impl WorkspaceLayout {
+    pub fn set_indexing_progress(&self, progress: IndexingProgress) {
+        // Progress sent before the indexing ended may arrive after it
+        if self.is_indexing() {
+            self.indexing_state.progress.set(Some(progress));
+        }
+    }
+
+    pub fn end_indexing(&self) {
+        self.indexing_state.progress.set(None);
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.indexing_state.progress.get_untracked().is_some()
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use super::focus_state::Direction;
+use super::workspace_layout::WorkspaceLayout;
+use floem::keyboard::KeyEvent;
+use lapce_app::config::LapceConfig;
+use lapce_app::keypress::KeyPressData;
+use lapce_app::keypress::keymap::KeyMapPress;
+use serde::Deserialize;
+
+/// The bindings of the canvas, in the format of the keymaps file of Lapce.
+/// Bindings of the keymaps file take precedence, and prefixing a command
+/// with `-` there removes its binding.
+const DEFAULT_KEYMAPS: &str = r#"
+[[keymaps]]
+key = "left"
+command = "graph.focus_left"
+
+[[keymaps]]
+key = "right"
+command = "graph.focus_right"
+
+[[keymaps]]
+key = "up"
+command = "graph.focus_up"
+
+[[keymaps]]
+key = "down"
+command = "graph.focus_down"
+
+[[keymaps]]
+key = "tab"
+command = "graph.focus_next_neighbour"
+
+[[keymaps]]
+key = "shift+tab"
+command = "graph.focus_previous_neighbour"
+
+[[keymaps]]
+key = "enter"
+command = "graph.open"
+
+[[keymaps]]
+key = "="
+command = "graph.zoom_in"
+
+[[keymaps]]
+key = "+"
+command = "graph.zoom_in"
+
+[[keymaps]]
+key = "shift++"
+command = "graph.zoom_in"
+
+[[keymaps]]
+key = "-"
+command = "graph.zoom_out"
+"#;
+
+const DEFAULT_KEYMAPS_MACOS: &str = r#"
+[[keymaps]]
+key = "meta+f"
+command = "graph.search"
+
+[[keymaps]]
+key = "meta+="
+command = "graph.zoom_in"
+
+[[keymaps]]
+key = "meta+-"
+command = "graph.zoom_out"
+"#;
+
+const DEFAULT_KEYMAPS_NONMACOS: &str = r#"
+[[keymaps]]
+key = "ctrl+f"
+command = "graph.search"
+
+[[keymaps]]
+key = "ctrl+="
+command = "graph.zoom_in"
+
+[[keymaps]]
+key = "ctrl+-"
+command = "graph.zoom_out"
+"#;
+
+/// Zoom factor of a key press.
+const KEY_ZOOM_FACTOR: f64 = 0.25;
+
+/// What the keys pressed on the canvas do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanvasCommand {
+    Focus(Direction),
+    /// Walks through the imports, then the importers, of a node.
+    FocusNextNeighbour,
+    FocusPreviousNeighbour,
+    Open,
+    ZoomIn,
+    ZoomOut,
+    Search,
+}
+
+impl CanvasCommand {
+    fn from_name(name: &str) -> Option<Self> {
+        let command = match name {
+            "graph.focus_left" => CanvasCommand::Focus(Direction::Left),
+            "graph.focus_right" => CanvasCommand::Focus(Direction::Right),
+            "graph.focus_up" => CanvasCommand::Focus(Direction::Up),
+            "graph.focus_down" => CanvasCommand::Focus(Direction::Down),
+            "graph.focus_next_neighbour" => CanvasCommand::FocusNextNeighbour,
+            "graph.focus_previous_neighbour" => {
+                CanvasCommand::FocusPreviousNeighbour
+            }
+            "graph.open" => CanvasCommand::Open,
+            "graph.zoom_in" => CanvasCommand::ZoomIn,
+            "graph.zoom_out" => CanvasCommand::ZoomOut,
+            "graph.search" => CanvasCommand::Search,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KeymapsFile {
+    #[serde(default)]
+    keymaps: Vec<KeymapEntry>,
+}
+
+/// A binding of the keymaps file. The `when` and `mode` of the bindings of
+/// the editor do not apply to the canvas.
+#[derive(Debug, Deserialize)]
+struct KeymapEntry {
+    key: String,
+    command: String,
+}
+
+/// The commands of the canvas by the key that runs them.
+#[derive(Debug, Clone, Default)]
+pub struct CanvasKeymap {
+    bindings: HashMap<KeyMapPress, CanvasCommand>,
+}
+
+impl CanvasKeymap {
+    /// Loads the default bindings, then the ones of the keymaps file.
+    pub fn load() -> Self {
+        let mut keymap = CanvasKeymap::default();
+        keymap.load_from_str(DEFAULT_KEYMAPS);
+        keymap.load_from_str(if cfg!(target_os = "macos") {
+            DEFAULT_KEYMAPS_MACOS
+        } else {
+            DEFAULT_KEYMAPS_NONMACOS
+        });
+        if let Some(path) = LapceConfig::keymaps_file() {
+            match std::fs::read_to_string(&path) {
+                Ok(keymaps) => keymap.load_from_str(&keymaps),
+                Err(e) => {
+                    eprintln!("Failed to read {}: {}", path.display(), e)
+                }
+            }
+        }
+        keymap
+    }
+
+    /// Adds the bindings of `keymaps` to the canvas, leaving out the ones of
+    /// the editor and the sequences of several keys.
+    fn load_from_str(&mut self, keymaps: &str) {
+        let keymaps: KeymapsFile = match toml::from_str(keymaps) {
+            Ok(keymaps) => keymaps,
+            Err(e) => {
+                eprintln!("Failed to read the keymaps: {}", e);
+                return;
+            }
+        };
+        for entry in keymaps.keymaps {
+            let (name, bind) = match entry.command.strip_prefix('-') {
+                Some(name) => (name, false),
+                None => (entry.command.as_str(), true),
+            };
+            let Some(command) = CanvasCommand::from_name(name) else {
+                continue;
+            };
+            let presses = KeyMapPress::parse(&entry.key);
+            let [press] = presses.as_slice() else {
+                continue;
+            };
+            if bind {
+                self.bindings.insert(press.clone(), command);
+            } else if self.bindings.get(press) == Some(&command) {
+                self.bindings.remove(press);
+            }
+        }
+    }
+
+    pub fn command(&self, key_event: &KeyEvent) -> Option<CanvasCommand> {
+        let press = KeyPressData::keypress(key_event)?.keymap_press()?;
+        self.bindings.get(&press).copied()
+    }
+}
+
+impl WorkspaceLayout {
+    pub fn run_canvas_command(&self, command: CanvasCommand) {
+        match command {
+            CanvasCommand::Focus(direction) => self.move_focus(direction),
+            CanvasCommand::FocusNextNeighbour => self.walk_neighbours(true),
+            CanvasCommand::FocusPreviousNeighbour => self.walk_neighbours(false),
+            CanvasCommand::Open => self.open_focused_file(),
+            CanvasCommand::ZoomIn => self.zoom_around_focus(KEY_ZOOM_FACTOR),
+            CanvasCommand::ZoomOut => {
+                // Undoes a zoom in
+                self.zoom_around_focus(1.0 / (1.0 + KEY_ZOOM_FACTOR) - 1.0)
+            }
+            CanvasCommand::Search => self.open_search(),
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use super::force_layout::Position;
+
+/// Horizontal distance between the nodes of a layer, in canvas units.
+const NODE_SPACING: f64 = 80.0;
+
+/// Vertical distance between two layers, in canvas units.
+const LAYER_SPACING: f64 = 160.0;
+
+/// Maximum number of sweeps of the barycenter heuristic, alternately down
+/// and up.
+const ORDERING_SWEEPS: usize = 24;
+
+/// Number of sweeps without fewer crossings after which the ordering is
+/// considered as good as it gets.
+const ORDERING_PATIENCE: usize = 4;
+
+/// A Sugiyama-style layout: nodes are stacked in layers so that edges point
+/// downwards, entry points at the top and leaves at the bottom, then ordered
+/// within their layer to reduce the crossings between edges.
+///
+/// Nodes are identified by their index, from `0` to `node_count`, and the
+/// positions are returned in the same order.
+pub(super) fn layered_layout(
+    node_count: usize,
+    edges: &[(usize, usize)],
+) -> Vec<Position> {
+    let edges = acyclic_edges(node_count, edges);
+    let node_layers = assign_layers(node_count, &edges);
+    let mut layering = Layering::new(&node_layers, &edges);
+    layering.minimize_crossings();
+    layering.positions(node_count)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+/// Breaks the cycles of the graph by reversing the edges that lead back to a
+/// node being visited by a depth-first search started from the entry points.
+/// Self-imports and duplicate edges are dropped.
+fn acyclic_edges(
+    node_count: usize,
+    edges: &[(usize, usize)],
+) -> Vec<(usize, usize)> {
+    let mut edges: Vec<(usize, usize)> = edges
+        .iter()
+        .copied()
+        .filter(|(node_u, node_v)| node_u != node_v)
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+
+    let mut outgoing = vec![Vec::new(); node_count];
+    let mut has_incoming = vec![false; node_count];
+    for (edge, &(node_u, node_v)) in edges.iter().enumerate() {
+        outgoing[node_u].push(edge);
+        has_incoming[node_v] = true;
+    }
+
+    let mut visits = vec![Visit::New; node_count];
+    let mut reversed = vec![false; edges.len()];
+    // Start from the entry points, then from the nodes only found in cycles
+    let roots = (0..node_count)
+        .filter(|node| !has_incoming[*node])
+        .chain(0..node_count);
+    for root in roots {
+        if visits[root] != Visit::New {
+            continue;
+        }
+        visits[root] = Visit::Active;
+        let mut stack = vec![(root, 0)];
+        while let Some(&(node, cursor)) = stack.last() {
+            let Some(&edge) = outgoing[node].get(cursor) else {
+                visits[node] = Visit::Done;
+                stack.pop();
+                continue;
+            };
+            let last = stack.len() - 1;
+            stack[last].1 += 1;
+
+            let target = edges[edge].1;
+            match visits[target] {
+                Visit::New => {
+                    visits[target] = Visit::Active;
+                    stack.push((target, 0));
+                }
+                Visit::Active => reversed[edge] = true,
+                Visit::Done => {}
+            }
+        }
+    }
+
+    let mut edges: Vec<(usize, usize)> = edges
+        .into_iter()
+        .zip(reversed)
+        .map(|((node_u, node_v), reversed)| {
+            if reversed {
+                (node_v, node_u)
+            } else {
+                (node_u, node_v)
+            }
+        })
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+/// Puts each node one layer below the lowest node pointing to it, then moves
+/// the leaves down to the bottom layer. `edges` must not contain cycles.
+fn assign_layers(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut outgoing = vec![Vec::new(); node_count];
+    let mut incoming_count = vec![0; node_count];
+    for &(node_u, node_v) in edges {
+        outgoing[node_u].push(node_v);
+        incoming_count[node_v] += 1;
+    }
+
+    let mut layers = vec![0; node_count];
+    let mut ready: Vec<usize> = (0..node_count)
+        .filter(|node| incoming_count[*node] == 0)
+        .collect();
+    while let Some(node) = ready.pop() {
+        for &target in &outgoing[node] {
+            layers[target] = layers[target].max(layers[node] + 1);
+            incoming_count[target] -= 1;
+            if incoming_count[target] == 0 {
+                ready.push(target);
+            }
+        }
+    }
+
+    let bottom_layer = layers.iter().copied().max().unwrap_or(0);
+    let mut has_incoming = vec![false; node_count];
+    for &(_, node_v) in edges {
+        has_incoming[node_v] = true;
+    }
+    for node in 0..node_count {
+        // Files that are not linked to any other stay at the top
+        if outgoing[node].is_empty() && has_incoming[node] {
+            layers[node] = bottom_layer;
+        }
+    }
+    layers
+}
+
+/// The nodes of each layer, where edges spanning several layers go through
+/// a dummy node in each layer they cross, so that they take part in the
+/// ordering.
+struct Layering {
+    /// The nodes of each layer, from the top, in their order. Nodes from the
+    /// node count of the graph onwards are dummy nodes.
+    layers: Vec<Vec<usize>>,
+    /// The neighbours of each node in the layer above.
+    upper: Vec<Vec<usize>>,
+    /// The neighbours of each node in the layer below.
+    lower: Vec<Vec<usize>>,
+}
+
+impl Layering {
+    fn new(node_layers: &[usize], edges: &[(usize, usize)]) -> Self {
+        let layer_count = node_layers.iter().copied().max().map_or(0, |l| l + 1);
+        let mut layers = vec![Vec::new(); layer_count];
+        for (node, &layer) in node_layers.iter().enumerate() {
+            layers[layer].push(node);
+        }
+        let mut layering = Layering {
+            layers,
+            upper: vec![Vec::new(); node_layers.len()],
+            lower: vec![Vec::new(); node_layers.len()],
+        };
+
+        for &(node_u, node_v) in edges {
+            let mut previous = node_u;
+            for layer in node_layers[node_u] + 1..node_layers[node_v] {
+                let dummy = layering.upper.len();
+                layering.upper.push(Vec::new());
+                layering.lower.push(Vec::new());
+                layering.layers[layer].push(dummy);
+                layering.link(previous, dummy);
+                previous = dummy;
+            }
+            layering.link(previous, node_v);
+        }
+        layering
+    }
+
+    fn link(&mut self, upper: usize, lower: usize) {
+        self.lower[upper].push(lower);
+        self.upper[lower].push(upper);
+    }
+
+    /// The index of each node within its layer.
+    fn orders(&self) -> Vec<usize> {
+        let mut orders = vec![0; self.upper.len()];
+        for layer in &self.layers {
+            for (order, &node) in layer.iter().enumerate() {
+                orders[node] = order;
+            }
+        }
+        orders
+    }
+
+    /// Orders each layer by the average order of the neighbours of its nodes
+    /// in the previous layer, sweeping down then up, and keeps the ordering
+    /// with the fewest crossings.
+    fn minimize_crossings(&mut self) {
+        let mut best_layers = self.layers.clone();
+        let mut best_crossings = self.crossings();
+        let mut best_sweep = 0;
+        for sweep in 0..ORDERING_SWEEPS {
+            let downwards = sweep % 2 == 0;
+            let layer_indices: Vec<usize> = if downwards {
+                (1..self.layers.len()).collect()
+            } else {
+                (0..self.layers.len().saturating_sub(1)).rev().collect()
+            };
+            let mut orders = self.orders();
+            for layer in layer_indices {
+                let neighbours = if downwards { &self.upper } else { &self.lower };
+                let mut barycenters: Vec<(f64, usize)> = self.layers[layer]
+                    .iter()
+                    .map(|&node| {
+                        let barycenter = if neighbours[node].is_empty() {
+                            // Nodes without neighbours keep their place
+                            orders[node] as f64
+                        } else {
+                            neighbours[node]
+                                .iter()
+                                .map(|neighbour| orders[*neighbour] as f64)
+                                .sum::<f64>()
+                                / neighbours[node].len() as f64
+                        };
+                        (barycenter, node)
+                    })
+                    .collect();
+                barycenters.sort_by(|a, b| a.0.total_cmp(&b.0));
+                self.layers[layer] =
+                    barycenters.into_iter().map(|(_, node)| node).collect();
+                for (order, &node) in self.layers[layer].iter().enumerate() {
+                    orders[node] = order;
+                }
+            }
+
+            let crossings = self.crossings();
+            if crossings < best_crossings {
+                best_crossings = crossings;
+                best_layers = self.layers.clone();
+                best_sweep = sweep;
+            }
+            if best_crossings == 0 || sweep - best_sweep >= ORDERING_PATIENCE {
+                break;
+            }
+        }
+        self.layers = best_layers;
+    }
+
+    /// Counts the crossings between the edges of each pair of adjacent
+    /// layers, as the inversions in the orders of the lower ends of the edges
+    /// sorted by their upper ends.
+    fn crossings(&self) -> usize {
+        let orders = &self.orders();
+        let mut crossings = 0;
+        for layer in 0..self.layers.len().saturating_sub(1) {
+            let mut edges: Vec<(usize, usize)> = self.layers[layer]
+                .iter()
+                .flat_map(|&node| {
+                    self.lower[node]
+                        .iter()
+                        .map(move |lower| (orders[node], orders[*lower]))
+                })
+                .collect();
+            edges.sort_unstable();
+
+            // A Fenwick tree of the lower ends seen so far
+            let lower_count = self.layers[layer + 1].len();
+            let mut tree = vec![0; lower_count + 1];
+            for (seen, (_, lower)) in edges.into_iter().enumerate() {
+                let mut smaller_or_equal = 0;
+                let mut i = lower + 1;
+                while i > 0 {
+                    smaller_or_equal += tree[i];
+                    i -= i & i.wrapping_neg();
+                }
+                crossings += seen - smaller_or_equal;
+
+                let mut i = lower + 1;
+                while i <= lower_count {
+                    tree[i] += 1;
+                    i += i & i.wrapping_neg();
+                }
+            }
+        }
+        crossings
+    }
+
+    /// The positions of the first `node_count` nodes, the dummy nodes only
+    /// taking up space. Layers are centered below each other.
+    fn positions(&self, node_count: usize) -> Vec<Position> {
+        let widest_layer = self.layers.iter().map(Vec::len).max().unwrap_or(0);
+        let mut positions = vec![Position::new(0.0, 0.0); node_count];
+        for (layer_index, layer) in self.layers.iter().enumerate() {
+            let offset = (widest_layer - layer.len()) as f64 / 2.0;
+            for (order, &node) in layer.iter().enumerate() {
+                if node < node_count {
+                    positions[node] = Position::new(
+                        (offset + order as f64) * NODE_SPACING,
+                        layer_index as f64 * LAYER_SPACING,
+                    );
+                }
+            }
+        }
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers_of_a_cyclic_graph() {
+        // 0 -> 1 -> 2 -> 1, 0 -> 3, and 4 alone
+        let edges = acyclic_edges(5, &[(0, 1), (1, 2), (2, 1), (0, 3), (3, 3)]);
+        assert_eq!(edges, vec![(0, 1), (0, 3), (1, 2)]);
+        // Leaves are at the bottom, unlinked files at the top
+        assert_eq!(assign_layers(5, &edges), vec![0, 1, 2, 2, 0]);
+    }
+
+    #[test]
+    fn test_minimize_crossings() {
+        // 0 -> 3 and 1 -> 2 cross with the initial order
+        let mut layering = Layering::new(&[0, 0, 1, 1], &[(0, 3), (1, 2)]);
+        assert_eq!(layering.crossings(), 1);
+        layering.minimize_crossings();
+        assert_eq!(layering.crossings(), 0);
+    }
+
+    #[test]
+    fn test_long_edges_go_through_dummy_nodes() {
+        let positions = layered_layout(3, &[(0, 1), (1, 2), (0, 2)]);
+        assert_eq!(positions[0].y, 0.0);
+        assert_eq!(positions[1].y, LAYER_SPACING);
+        assert_eq!(positions[2].y, 2.0 * LAYER_SPACING);
+        // The dummy node of 0 -> 2 sits next to 1
+        assert_ne!(positions[1].x, positions[2].x);
+    }
+}
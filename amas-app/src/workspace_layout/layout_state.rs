@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+
+use super::calculate_positions::{StartingPositions, spawn_layout};
+use super::clustered_layout::Region;
+use super::force_layout::{LayoutSettings, Position};
+use super::workspace_layout::WorkspaceLayout;
+use floem::ext_event::create_signal_from_channel;
+use floem::prelude::{
+    RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _, create_effect,
+};
+use petgraph::graph::NodeIndex;
+use serde::{Deserialize, Serialize};
+
+/// How the files are laid out on the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LayoutMode {
+    /// Linked files are pulled together, unlinked ones pushed apart.
+    Force,
+    /// Files are stacked in layers, importers above the files they import.
+    Layered,
+    /// Files are grouped by directory into nested regions.
+    Clustered,
+}
+
+impl LayoutMode {
+    pub const ALL: [LayoutMode; 3] = [
+        LayoutMode::Force,
+        LayoutMode::Layered,
+        LayoutMode::Clustered,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LayoutMode::Force => "Force",
+            LayoutMode::Layered => "Layered",
+            LayoutMode::Clustered => "Directories",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutState {
+    /// Positions of the nodes in canvas units, refined by the simulation
+    /// running in the background.
+    pub positions: RwSignal<HashMap<NodeIndex, Position>>,
+    /// The directories drawn around their files, by the clustered layout.
+    pub regions: RwSignal<Vec<Region>>,
+    pub mode: RwSignal<LayoutMode>,
+    /// Changing the settings lays the graph out again. They start from the
+    /// `[amas.layout]` table of the settings file.
+    pub settings: RwSignal<LayoutSettings>,
+    /// Bumped whenever the graph changes, which stops the running simulation.
+    generation: Arc<AtomicUsize>,
+    /// Positions saved in the workspace, by file name, until the graph they
+    /// were saved for is indexed.
+    restored_positions: RwSignal<HashMap<String, Position>>,
+}
+
+impl LayoutState {
+    pub fn new() -> Self {
+        let positions = RwSignal::new(HashMap::new());
+        let regions = RwSignal::new(Vec::new());
+        let mode = RwSignal::new(LayoutMode::Force);
+        let settings = RwSignal::new(LayoutSettings::load());
+        let generation = Arc::new(AtomicUsize::new(0));
+        let restored_positions = RwSignal::new(HashMap::new());
+        Self {
+            positions,
+            regions,
+            mode,
+            settings,
+            generation,
+            restored_positions,
+        }
+    }
+
+    pub(super) fn restore_positions(&self, positions: HashMap<String, Position>) {
+        self.restored_positions.set(positions);
+    }
+}
+
+impl WorkspaceLayout {
+    /// Lays the graph out again whenever it, the layout mode or the layout
+    /// settings change.
+    pub(super) fn layout_on_graph_change(&self) {
+        let (frames_tx, frames_rx) = channel();
+        let frame = create_signal_from_channel(frames_rx);
+        create_effect({
+            let layout_state = self.layout_state.clone();
+            move |_| {
+                // Positions sent by a stopped simulation may still arrive
+                if let Some(frame) = frame.get().filter(|frame| {
+                    frame.generation
+                        == layout_state.generation.load(Ordering::Relaxed)
+                }) {
+                    layout_state.positions.set(frame.positions);
+                    layout_state.regions.set(frame.regions);
+                }
+            }
+        });
+
+        let workspace_graph = self.cluster_state.visible_graph;
+        let pinned = self.pin_state.pinned;
+        let layout_state = self.layout_state.clone();
+        create_effect(move |previous_mode: Option<LayoutMode>| {
+            let workspace_graph = workspace_graph.get();
+            let mode = layout_state.mode.get();
+            let settings = layout_state.settings.get();
+            let generation =
+                layout_state.generation.fetch_add(1, Ordering::Relaxed) + 1;
+            // The force layout starts over when switching from another mode
+            let mut previous = if previous_mode == Some(mode) {
+                layout_state.positions.get_untracked()
+            } else {
+                HashMap::new()
+            };
+            let restored = workspace_graph.graph.node_count() > 0
+                && layout_state
+                    .restored_positions
+                    .with_untracked(|positions| !positions.is_empty());
+            if restored {
+                for (file_name, position) in
+                    layout_state.restored_positions.get_untracked()
+                {
+                    if let Some(node_idx) = workspace_graph.find_file(&file_name) {
+                        previous.insert(node_idx, position);
+                    }
+                }
+                layout_state.restored_positions.set(HashMap::new());
+            }
+            let pinned = pinned.with(|pinned| {
+                pinned.get(&mode).map_or_else(HashMap::new, |pins| {
+                    pins.iter()
+                        .filter_map(|(file_name, position)| {
+                            let node_idx = workspace_graph.find_file(file_name)?;
+                            Some((node_idx, position.clone()))
+                        })
+                        .collect()
+                })
+            });
+            spawn_layout(
+                workspace_graph,
+                mode,
+                StartingPositions {
+                    previous,
+                    pinned,
+                    restored,
+                },
+                settings,
+                layout_state.generation.clone(),
+                generation,
+                frames_tx.clone(),
+            );
+            mode
+        });
+    }
+
+    /// Stops the running simulation, whose positions are ignored from now
+    /// on.
+    pub(super) fn stop_layout(&self) {
+        self.layout_state.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_layout_mode(&self, mode: LayoutMode) {
+        self.layout_state.mode.set(mode);
+    }
+
+    pub fn is_layout_mode(&self, mode: LayoutMode) -> bool {
+        self.layout_state.mode.get() == mode
+    }
+}
@@ -0,0 +1,116 @@
+use super::spatial_index::Bounds;
+use super::workspace_layout::WorkspaceLayout;
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _};
+
+/// Size of the minimap, in pixels.
+pub(super) const MINIMAP_WIDTH: f64 = 200.0;
+pub(super) const MINIMAP_HEIGHT: f64 = 150.0;
+
+/// Room left around the graph in the minimap, in pixels.
+const MINIMAP_PADDING: f64 = 8.0;
+
+/// Scales the canvas down so that the whole graph fits in the minimap.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct MinimapTransform {
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl MinimapTransform {
+    /// Centers `bounds`, in canvas units, in the minimap.
+    fn fit(bounds: Bounds) -> Self {
+        let (x0, y0, x1, y1) = bounds;
+        let width = (x1 - x0).max(1.0);
+        let height = (y1 - y0).max(1.0);
+        let scale = ((MINIMAP_WIDTH - 2.0 * MINIMAP_PADDING) / width)
+            .min((MINIMAP_HEIGHT - 2.0 * MINIMAP_PADDING) / height);
+        Self {
+            scale,
+            offset_x: (MINIMAP_WIDTH - width * scale) / 2.0 - x0 * scale,
+            offset_y: (MINIMAP_HEIGHT - height * scale) / 2.0 - y0 * scale,
+        }
+    }
+
+    pub fn to_minimap(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            x * self.scale + self.offset_x,
+            y * self.scale + self.offset_y,
+        )
+    }
+
+    pub fn to_canvas(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.offset_x) / self.scale,
+            (y - self.offset_y) / self.scale,
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MinimapState {
+    /// Whether the pointer is held down on the minimap, in which case the
+    /// view follows it.
+    dragging: RwSignal<bool>,
+}
+
+impl MinimapState {
+    pub fn new() -> Self {
+        let dragging = RwSignal::new(false);
+        Self { dragging }
+    }
+}
+
+impl WorkspaceLayout {
+    /// Fits the nodes laid out so far in the minimap, or `None` while there
+    /// are none.
+    pub(super) fn minimap_transform(&self) -> Option<MinimapTransform> {
+        self.layout_state.positions.with(|positions| {
+            let mut positions = positions.values();
+            let first = positions.next()?;
+            let bounds = positions.fold(
+                (first.x, first.y, first.x, first.y),
+                |(x0, y0, x1, y1), position| {
+                    (
+                        x0.min(position.x),
+                        y0.min(position.y),
+                        x1.max(position.x),
+                        y1.max(position.y),
+                    )
+                },
+            );
+            Some(MinimapTransform::fit(bounds))
+        })
+    }
+
+    pub fn start_minimap_drag(&self, x: f64, y: f64) {
+        self.minimap_state.dragging.set(true);
+        self.center_view_on_minimap(x, y);
+    }
+
+    pub fn minimap_drag(&self, x: f64, y: f64) {
+        if self.minimap_state.dragging.get_untracked() {
+            self.center_view_on_minimap(x, y);
+        }
+    }
+
+    pub fn end_minimap_drag(&self) {
+        self.minimap_state.dragging.set(false);
+    }
+
+    /// Moves the view so that the point of the minimap at `x` and `y` is in
+    /// the middle of the screen.
+    fn center_view_on_minimap(&self, x: f64, y: f64) {
+        let Some(transform) = self.minimap_transform() else {
+            return;
+        };
+        let (x, y) = transform.to_canvas(x, y);
+        let zoom = self.view_state.zoom.get_untracked();
+        self.view_state
+            .translation_x
+            .set(self.view_state.width.get_untracked() / 2.0 - x * zoom);
+        self.view_state
+            .translation_y
+            .set(self.view_state.height.get_untracked() / 2.0 - y * zoom);
+    }
+}
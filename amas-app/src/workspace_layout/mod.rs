@@ -1,7 +1,25 @@
-pub mod workspace_layout;
-mod draw;
+mod bundling_state;
 mod calculate_positions;
-mod view_state;
-mod selection_state;
 mod canva_state;
+mod cluster_state;
+mod clustered_layout;
+mod draw;
+mod file_actions;
+mod filter_state;
+mod focus_state;
+pub mod force_layout;
+mod highlight_state;
+mod indexing_state;
+mod keymap;
+mod layered_layout;
+mod layout_state;
+mod minimap_state;
+mod pin_state;
+mod preview_state;
+mod saved_layout;
+mod search_state;
+mod selection_state;
+mod spatial_index;
 mod view;
+mod view_state;
+pub mod workspace_layout;
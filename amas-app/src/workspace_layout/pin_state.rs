@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use super::force_layout::Position;
+use super::layout_state::LayoutMode;
+use super::workspace_layout::WorkspaceLayout;
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _};
+
+/// The positions of the nodes moved by hand in each layout mode, by file
+/// name.
+pub(super) type Pins = HashMap<LayoutMode, HashMap<String, Position>>;
+
+/// Nodes being dragged by hand.
+#[derive(Debug, Clone)]
+struct NodeDrag {
+    files: Vec<String>,
+    /// Whether the pointer moved since the drag started, which tells a drag
+    /// from a click.
+    moved: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct PinState {
+    /// Nodes moved by hand, which the layouts keep in place.
+    pub pinned: RwSignal<Pins>,
+    drag: RwSignal<Option<NodeDrag>>,
+}
+
+impl PinState {
+    pub fn new() -> Self {
+        let pinned = RwSignal::new(HashMap::new());
+        let drag = RwSignal::new(None);
+        Self { pinned, drag }
+    }
+}
+
+impl WorkspaceLayout {
+    /// Starts dragging the hovered node, along with the rest of the selection
+    /// when it is selected.
+    pub(super) fn start_node_drag(&self) {
+        let Some(hovered_file) = self.get_hovered_file() else {
+            return;
+        };
+        let selected_files = self.get_selected_files();
+        let files: Vec<String> = if selected_files.contains(&hovered_file) {
+            selected_files.into_iter().collect()
+        } else {
+            vec![hovered_file]
+        };
+        // Symbols follow their file
+        let files: Vec<String> =
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                files
+                    .into_iter()
+                    .filter(|file_name| {
+                        graph.find_file(file_name).is_some_and(|node_idx| {
+                            graph.graph[node_idx].symbol.is_none()
+                        })
+                    })
+                    .collect()
+            });
+        if !files.is_empty() {
+            self.pin_state.drag.set(Some(NodeDrag {
+                files,
+                moved: false,
+            }));
+        }
+    }
+
+    /// Moves the dragged nodes and their symbols by `dx` and `dy` pixels.
+    /// Returns whether nodes are being dragged.
+    pub(super) fn drag_nodes(&self, dx: f64, dy: f64) -> bool {
+        let Some(drag) = self.pin_state.drag.get_untracked() else {
+            return false;
+        };
+        if !drag.moved {
+            // The simulation would move the nodes back
+            self.stop_layout();
+            self.pin_state.drag.set(Some(NodeDrag {
+                moved: true,
+                ..drag.clone()
+            }));
+        }
+
+        let zoom = self.view_state.zoom.get_untracked();
+        let node_indices: Vec<_> =
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                drag.files
+                    .iter()
+                    .filter_map(|file_name| graph.find_file(file_name))
+                    .flat_map(|node_idx| {
+                        std::iter::once(node_idx)
+                            .chain(graph.symbols_of(node_idx).iter().copied())
+                    })
+                    .collect()
+            });
+        self.layout_state.positions.update(|positions| {
+            for node_idx in node_indices {
+                if let Some(position) = positions.get_mut(&node_idx) {
+                    position.x += dx / zoom;
+                    position.y += dy / zoom;
+                }
+            }
+        });
+        true
+    }
+
+    /// Pins the dragged nodes where they were dropped, which lays the graph
+    /// out again around them.
+    pub(super) fn end_node_drag(&self) {
+        let Some(drag) = self.pin_state.drag.get_untracked() else {
+            return;
+        };
+        self.pin_state.drag.set(None);
+        if !drag.moved {
+            return;
+        }
+
+        let mode = self.layout_state.mode.get_untracked();
+        let dropped: Vec<(String, Position)> =
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                self.layout_state.positions.with_untracked(|positions| {
+                    drag.files
+                        .into_iter()
+                        .filter_map(|file_name| {
+                            let node_idx = graph.find_file(&file_name)?;
+                            let position = positions.get(&node_idx)?.clone();
+                            Some((file_name, position))
+                        })
+                        .collect()
+                })
+            });
+        self.pin_state.pinned.update(|pinned| {
+            pinned.entry(mode).or_default().extend(dropped);
+        });
+    }
+
+    /// Lets the layout place the nodes pinned in the current layout mode
+    /// again.
+    pub fn unpin_all(&self) {
+        let mode = self.layout_state.mode.get_untracked();
+        self.pin_state.pinned.update(|pinned| {
+            pinned.remove(&mode);
+        });
+    }
+
+    pub fn has_pins(&self) -> bool {
+        let mode = self.layout_state.mode.get();
+        self.pin_state
+            .pinned
+            .with(|pinned| pinned.get(&mode).is_some_and(|pins| !pins.is_empty()))
+    }
+
+    pub fn is_pinned(&self, file_name: &str) -> bool {
+        let mode = self.layout_state.mode.get();
+        self.pin_state.pinned.with(|pinned| {
+            pinned
+                .get(&mode)
+                .is_some_and(|pins| pins.contains_key(file_name))
+        })
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::workspace_layout::WorkspaceLayout;
+use floem::ext_event::create_ext_action;
+use floem::prelude::{
+    RwSignal, SignalTrack as _, SignalUpdate as _, SignalWith as _, create_effect,
+};
+use floem::reactive::Scope;
+use lapce_core::syntax::Syntax;
+use lapce_xi_rope::Rope;
+
+/// Number of lines shown in the preview of a file.
+const PREVIEW_LINES: usize = 12;
+
+/// Number of characters after which the lines of a preview are cut.
+const PREVIEW_COLUMNS: usize = 80;
+
+/// The first lines of a file, highlighted.
+#[derive(Debug, Clone, Default)]
+pub struct Preview {
+    pub text: String,
+    /// The byte range of each highlighted span of `text`, with its scope
+    /// (e.g. `keyword`).
+    pub styles: Vec<(Range<usize>, String)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PreviewState {
+    /// The previews highlighted so far, by file name. `None` while the
+    /// preview is being highlighted.
+    pub previews: RwSignal<HashMap<String, Option<Arc<Preview>>>>,
+}
+
+impl PreviewState {
+    pub fn new() -> Self {
+        let previews = RwSignal::new(HashMap::new());
+        Self { previews }
+    }
+}
+
+impl WorkspaceLayout {
+    /// Drops the previews whenever the graph changes, as their file may have
+    /// changed too.
+    pub(super) fn clear_previews_on_graph_change(&self) {
+        let workspace_graph = self.workspace_graph;
+        let previews = self.preview_state.previews;
+        create_effect(move |_| {
+            workspace_graph.track();
+            previews.set(HashMap::new());
+        });
+    }
+
+    /// The preview of `file_name`, which is highlighted in the background
+    /// the first time it is asked for.
+    pub(super) fn preview(&self, file_name: &str) -> Option<Arc<Preview>> {
+        let previews = self.preview_state.previews;
+        if let Some(preview) =
+            previews.with(|previews| previews.get(file_name).cloned())
+        {
+            return preview;
+        }
+
+        previews.update(|previews| {
+            previews.insert(file_name.to_string(), None);
+        });
+        let file_name = file_name.to_string();
+        let send = create_ext_action(Scope::current(), {
+            let file_name = file_name.clone();
+            move |preview: Preview| {
+                previews.update(|previews| {
+                    previews.insert(file_name, Some(Arc::new(preview)));
+                });
+            }
+        });
+        rayon::spawn(move || {
+            send(highlight_preview(Path::new(&file_name)));
+        });
+        None
+    }
+}
+
+/// Reads the first lines of the file at `path` and highlights them. Only
+/// these lines are parsed, which is enough for a glance at the file.
+fn highlight_preview(path: &Path) -> Preview {
+    let source_code = match std::fs::read_to_string(path) {
+        Ok(source_code) => source_code,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            return Preview::default();
+        }
+    };
+    let text = source_code
+        .lines()
+        .take(PREVIEW_LINES)
+        .map(|line| line.chars().take(PREVIEW_COLUMNS).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut syntax = Syntax::init(path);
+    syntax.parse(0, Rope::from(text.as_str()), None);
+    let styles = syntax.styles.map_or_else(Vec::new, |spans| {
+        spans
+            .iter()
+            .filter_map(|(interval, style)| {
+                let scope = style.fg_color.clone()?;
+                Some((interval.start()..interval.end(), scope))
+            })
+            .collect()
+    });
+    Preview { text, styles }
+}
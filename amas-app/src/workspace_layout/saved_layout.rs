@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use super::force_layout::Position;
+use super::layout_state::LayoutMode;
+use super::pin_state::Pins;
+use super::workspace_layout::WorkspaceLayout;
+use floem::action::{TimerToken, exec_after};
+use floem::prelude::{
+    RwSignal, SignalGet as _, SignalTrack as _, SignalUpdate as _, SignalWith as _,
+    create_effect,
+};
+use serde::{Deserialize, Serialize};
+
+/// Where the layout is saved, relative to the workspace, so that it can be
+/// committed and shared with the rest of the team.
+const LAYOUT_FILE: &str = ".amas/layout.json";
+
+/// Time without changes after which the layout is saved.
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// The canvas as it was left. Files are saved relative to the workspace,
+/// while external packages are saved by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedLayout {
+    mode: LayoutMode,
+    zoom: f64,
+    translation_x: f64,
+    translation_y: f64,
+    /// Positions of the files in the layout mode, by file name.
+    positions: HashMap<String, Position>,
+    pinned: Pins,
+}
+
+impl SavedLayout {
+    fn load(workspace_path: &Path) -> Option<Self> {
+        let layout_path = workspace_path.join(LAYOUT_FILE);
+        let layout = match std::fs::read_to_string(layout_path) {
+            Ok(layout) => layout,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                eprintln!("Failed to read the saved layout: {}", e);
+                return None;
+            }
+        };
+        let layout: SavedLayout = match serde_json::from_str(&layout) {
+            Ok(layout) => layout,
+            Err(e) => {
+                eprintln!("Failed to read the saved layout: {}", e);
+                return None;
+            }
+        };
+        Some(SavedLayout {
+            positions: absolute_names(layout.positions, workspace_path),
+            pinned: layout
+                .pinned
+                .into_iter()
+                .map(|(mode, pins)| (mode, absolute_names(pins, workspace_path)))
+                .collect(),
+            ..layout
+        })
+    }
+
+    fn save(self, workspace_path: &Path) {
+        let layout = SavedLayout {
+            positions: relative_names(self.positions, workspace_path),
+            pinned: self
+                .pinned
+                .into_iter()
+                .map(|(mode, pins)| (mode, relative_names(pins, workspace_path)))
+                .collect(),
+            ..self
+        };
+        let layout_path = workspace_path.join(LAYOUT_FILE);
+        let saved = serde_json::to_string_pretty(&layout)
+            .map_err(std::io::Error::from)
+            .and_then(|layout| {
+                if let Some(directory) = layout_path.parent() {
+                    std::fs::create_dir_all(directory)?;
+                }
+                std::fs::write(&layout_path, layout)
+            });
+        if let Err(e) = saved {
+            eprintln!("Failed to save the layout: {}", e);
+        }
+    }
+}
+
+fn relative_names<T>(
+    by_name: HashMap<String, T>,
+    workspace_path: &Path,
+) -> HashMap<String, T> {
+    by_name
+        .into_iter()
+        .map(|(name, value)| {
+            let name = Path::new(&name)
+                .strip_prefix(workspace_path)
+                .map_or(name.clone(), |relative| {
+                    relative.to_string_lossy().into_owned()
+                });
+            (name, value)
+        })
+        .collect()
+}
+
+/// Resolves the names of files against the workspace, leaving the names of
+/// external packages, and of files that do not exist anymore, as they are.
+fn absolute_names<T>(
+    by_name: HashMap<String, T>,
+    workspace_path: &Path,
+) -> HashMap<String, T> {
+    by_name
+        .into_iter()
+        .map(|(name, value)| {
+            let path = workspace_path.join(&name);
+            if path.exists() {
+                (path.to_string_lossy().into_owned(), value)
+            } else {
+                (name, value)
+            }
+        })
+        .collect()
+}
+
+impl WorkspaceLayout {
+    /// Puts the canvas back the way it was left in the workspace, if it was
+    /// saved.
+    pub(super) fn restore_layout(&self) {
+        let Some(layout) = SavedLayout::load(&self.workspace_path) else {
+            return;
+        };
+        self.view_state.zoom.set(layout.zoom);
+        self.view_state.translation_x.set(layout.translation_x);
+        self.view_state.translation_y.set(layout.translation_y);
+        self.layout_state.mode.set(layout.mode);
+        self.layout_state.restore_positions(layout.positions);
+        self.pin_state.pinned.set(layout.pinned);
+    }
+
+    /// Saves the layout once the canvas stops changing for a while.
+    pub(super) fn save_layout_on_change(&self) {
+        let timer = RwSignal::new(None::<TimerToken>);
+        let layout = self.clone();
+        create_effect(move |_| {
+            layout.view_state.zoom.track();
+            layout.view_state.translation_x.track();
+            layout.view_state.translation_y.track();
+            layout.layout_state.positions.track();
+            layout.pin_state.pinned.track();
+            // Nothing is laid out yet
+            if layout.is_indexing() {
+                return;
+            }
+            let token = exec_after(SAVE_DELAY, {
+                let layout = layout.clone();
+                move |token| {
+                    if timer.get_untracked() == Some(token) {
+                        layout.save_layout();
+                    }
+                }
+            });
+            timer.set(Some(token));
+        });
+    }
+
+    fn save_layout(&self) {
+        let positions = self.cluster_state.visible_graph.with_untracked(|graph| {
+            self.layout_state.positions.with_untracked(|positions| {
+                positions
+                    .iter()
+                    .filter_map(|(node_idx, position)| {
+                        let file = graph.graph.node_weight(*node_idx)?;
+                        // Symbols are placed around their file
+                        file.symbol
+                            .is_none()
+                            .then(|| (file.name.clone(), position.clone()))
+                    })
+                    .collect()
+            })
+        });
+        let layout = SavedLayout {
+            mode: self.layout_state.mode.get_untracked(),
+            zoom: self.view_state.zoom.get_untracked(),
+            translation_x: self.view_state.translation_x.get_untracked(),
+            translation_y: self.view_state.translation_y.get_untracked(),
+            positions,
+            pinned: self.pin_state.pinned.get_untracked(),
+        };
+        layout.save(&self.workspace_path);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::workspace_layout::WorkspaceLayout;
+use floem::prelude::{
+    RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _, create_effect,
+};
+use nucleo::Utf32Str;
+use nucleo::pattern::{CaseMatching, Normalization, Pattern};
+
+/// Number of matches listed under the search field.
+const MAX_MATCHES: usize = 20;
+
+/// A file whose path matches the search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+    pub file_name: String,
+    /// The path shown, relative to the workspace.
+    pub path: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchState {
+    pub open: RwSignal<bool>,
+    pub query: RwSignal<String>,
+    /// The files matching the query, best matches first.
+    pub matches: RwSignal<Vec<SearchMatch>>,
+    /// The index of the chosen match, which the view flies to.
+    pub selected: RwSignal<usize>,
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        let open = RwSignal::new(false);
+        let query = RwSignal::new(String::new());
+        let matches = RwSignal::new(Vec::new());
+        let selected = RwSignal::new(0);
+        Self {
+            open,
+            query,
+            matches,
+            selected,
+        }
+    }
+}
+
+/// Fuzzy matches `query` against the paths of `files`, as the palette does,
+/// keeping the best matches first.
+fn fuzzy_match(query: &str, files: Vec<SearchMatch>) -> Vec<SearchMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
+    let mut matcher = nucleo::Matcher::new(nucleo::Config::DEFAULT.match_paths());
+    let mut path_buf = Vec::new();
+    let mut matches: Vec<(u32, SearchMatch)> = files
+        .into_iter()
+        .filter_map(|file| {
+            path_buf.clear();
+            let path = Utf32Str::new(&file.path, &mut path_buf);
+            let score = pattern.score(path, &mut matcher)?;
+            Some((score, file))
+        })
+        .collect();
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.cmp(a_score).then_with(|| a.path.cmp(&b.path))
+    });
+    matches
+        .into_iter()
+        .take(MAX_MATCHES)
+        .map(|(_, file)| file)
+        .collect()
+}
+
+impl WorkspaceLayout {
+    /// Matches the files again whenever the query changes, and flies to the
+    /// best match.
+    pub(super) fn search_on_query_change(&self) {
+        let layout = self.clone();
+        create_effect(move |_| {
+            let query = layout.search_state.query.get();
+            let files: Vec<SearchMatch> =
+                layout.cluster_state.visible_graph.with_untracked(|graph| {
+                    graph
+                        .graph
+                        .node_weights()
+                        .filter(|file| file.symbol.is_none())
+                        .map(|file| SearchMatch {
+                            file_name: file.name.clone(),
+                            path: Path::new(&file.name)
+                                .strip_prefix(&layout.workspace_path)
+                                .map_or(file.name.clone(), |path| {
+                                    path.to_string_lossy().into_owned()
+                                }),
+                        })
+                        .collect()
+                });
+            layout.search_state.matches.set(fuzzy_match(&query, files));
+            layout.select_match(0);
+        });
+    }
+
+    pub fn open_search(&self) {
+        self.search_state.query.set(String::new());
+        self.search_state.open.set(true);
+    }
+
+    pub fn close_search(&self) {
+        self.search_state.open.set(false);
+        self.search_state.query.set(String::new());
+    }
+
+    /// Chooses the match at `index`, flying to its node.
+    pub fn select_match(&self, index: usize) {
+        let file_name = self.search_state.matches.with_untracked(|matches| {
+            matches.get(index).map(|file| file.file_name.clone())
+        });
+        if let Some(file_name) = file_name {
+            self.search_state.selected.set(index);
+            self.fly_to_file(&file_name);
+        }
+    }
+
+    /// Chooses the match before or after the chosen one.
+    pub fn select_next_match(&self, forward: bool) {
+        let count = self
+            .search_state
+            .matches
+            .with_untracked(|matches| matches.len());
+        if count == 0 {
+            return;
+        }
+        let selected = self.search_state.selected.get_untracked();
+        let index = if forward {
+            (selected + 1) % count
+        } else {
+            (selected + count - 1) % count
+        };
+        self.select_match(index);
+    }
+
+    /// Selects the node of the chosen match and closes the search, opening
+    /// the file too when `open` is set.
+    pub fn confirm_match(&self, open: bool) {
+        let selected = self.search_state.selected.get_untracked();
+        let file_name = self.search_state.matches.with_untracked(|matches| {
+            matches.get(selected).map(|file| file.file_name.clone())
+        });
+        self.close_search();
+        let Some(file_name) = file_name else {
+            return;
+        };
+        self.selection_state
+            .selected_files
+            .set(HashSet::from([file_name.clone()]));
+        if open {
+            self.open_file(&file_name);
+        }
+    }
+
+    /// The files matching the search while it is open.
+    pub fn search_matches(&self) -> HashSet<String> {
+        if !self.search_state.open.get() {
+            return HashSet::new();
+        }
+        self.search_state.matches.with(|matches| {
+            matches.iter().map(|file| file.file_name.clone()).collect()
+        })
+    }
+}
@@ -1,38 +1,102 @@
 use std::collections::HashSet;
 
+use super::spatial_index::{Bounds, overlaps};
 use super::workspace_layout::WorkspaceLayout;
-use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate};
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate, SignalWith as _};
+use petgraph::graph::NodeIndex;
+
+/// How the files are picked by dragging over the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaShape {
+    /// Picks the files touching the rectangle between where the drag started
+    /// and where it is.
+    Rectangle,
+    /// Picks the files whose center is inside the path of the drag.
+    Lasso,
+}
+
+/// An area being dragged over the canvas to select the files in it.
+#[derive(Debug, Clone)]
+pub struct SelectionArea {
+    pub shape: AreaShape,
+    /// The points the pointer went through, on the screen.
+    pub points: Vec<(f64, f64)>,
+    /// Whether the files in the area are added to the selection rather than
+    /// replacing it.
+    add: bool,
+}
+
+impl SelectionArea {
+    pub fn bounds(&self) -> Bounds {
+        let (x, y) = self.points[0];
+        self.points
+            .iter()
+            .fold((x, y, x, y), |(x0, y0, x1, y1), (x, y)| {
+                (x0.min(*x), y0.min(*y), x1.max(*x), y1.max(*y))
+            })
+    }
+
+    /// Whether the file drawn within `bounds` is in the area.
+    fn selects(&self, bounds: Bounds) -> bool {
+        match self.shape {
+            AreaShape::Rectangle => overlaps(self.bounds(), bounds),
+            AreaShape::Lasso => contains(
+                &self.points,
+                ((bounds.0 + bounds.2) / 2.0, (bounds.1 + bounds.3) / 2.0),
+            ),
+        }
+    }
+}
+
+/// Whether `point` is inside the polygon closed by `points`, by the
+/// even-odd rule.
+fn contains(points: &[(f64, f64)], point: (f64, f64)) -> bool {
+    let (x, y) = point;
+    let mut inside = false;
+    let mut previous = points.len().wrapping_sub(1);
+    for (current, (x1, y1)) in points.iter().enumerate() {
+        let (x0, y0) = points[previous];
+        if (*y1 > y) != (y0 > y) && x < (x0 - x1) * (y - y1) / (y0 - y1) + x1 {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
 
 #[derive(Clone, Debug)]
 pub struct SelectionState {
     pub selected_files: RwSignal<HashSet<String>>,
     pub hovered_file: RwSignal<Option<String>>,
+    /// The area being dragged, while one is.
+    pub area: RwSignal<Option<SelectionArea>>,
 }
 
 impl SelectionState {
     pub fn new() -> Self {
         let selected_files = RwSignal::new(HashSet::new());
         let hovered_file = RwSignal::new(None);
+        let area = RwSignal::new(None);
         Self {
             selected_files,
             hovered_file,
+            area,
         }
     }
 }
 
 impl WorkspaceLayout {
     fn get_file_at_position(&self, x: f64, y: f64) -> Option<String> {
-        self.canva_state
+        let node_idx = self
+            .canva_state
             .files
-            .get()
-            .iter()
-            .find_map(|(file, (fx, fy, fw, fh))| {
-                if x >= *fx && x <= *fw && y >= *fy && y <= *fh {
-                    Some(file.name.clone())
-                } else {
-                    None
-                }
-            })
+            .with(|files| files.at(x, y).copied())?;
+        self.cluster_state.visible_graph.with(|graph| {
+            graph
+                .graph
+                .node_weight(node_idx)
+                .map(|file| file.name.clone())
+        })
     }
 
     pub fn select_file_hovered_file(&self) {
@@ -60,9 +124,15 @@ pub fn multiselect_files_hovered_file(&self) {
     }
 
     pub fn track_hovered_file(&self, x: f64, y: f64) {
-        self.selection_state
+        let hovered_file = self.get_file_at_position(x, y);
+        // The canvas is drawn again whenever the hovered file changes
+        if self
+            .selection_state
             .hovered_file
-            .set(self.get_file_at_position(x, y));
+            .with_untracked(|file_name| *file_name != hovered_file)
+        {
+            self.selection_state.hovered_file.set(hovered_file);
+        }
     }
 
     pub fn get_hovered_file(&self) -> Option<String> {
@@ -72,4 +142,93 @@ pub fn get_hovered_file(&self) -> Option<String> {
     pub fn get_selected_files(&self) -> HashSet<String> {
         self.selection_state.selected_files.get().clone()
     }
+
+    pub fn is_selected(&self, file_name: &str) -> bool {
+        self.selection_state
+            .selected_files
+            .with(|selected_files| selected_files.contains(file_name))
+    }
+
+    /// Starts dragging an area at `x` and `y` to select the files in it,
+    /// adding them to the selection when `add` is set.
+    pub fn start_area_selection(&self, shape: AreaShape, x: f64, y: f64, add: bool) {
+        self.selection_state.area.set(Some(SelectionArea {
+            shape,
+            points: vec![(x, y)],
+            add,
+        }));
+    }
+
+    /// Drags the area, if one is being dragged, to `x` and `y`.
+    pub fn extend_area_selection(&self, x: f64, y: f64) {
+        if self.selection_state.area.with_untracked(Option::is_none) {
+            return;
+        }
+        self.selection_state.area.update(|area| {
+            let Some(area) = area else {
+                return;
+            };
+            match area.shape {
+                AreaShape::Rectangle => area.points.truncate(1),
+                AreaShape::Lasso => {}
+            }
+            area.points.push((x, y));
+        });
+    }
+
+    /// Selects the files drawn in the dragged area, if one is being dragged.
+    pub fn end_area_selection(&self) {
+        let Some(area) = self.selection_state.area.get_untracked() else {
+            return;
+        };
+        self.selection_state.area.set(None);
+        let in_area: Vec<NodeIndex> =
+            self.canva_state.files.with_untracked(|files| {
+                files
+                    .iter()
+                    .filter(|(_, bounds)| area.selects(*bounds))
+                    .map(|(node_idx, _)| *node_idx)
+                    .collect()
+            });
+        let in_area: Vec<String> =
+            self.cluster_state.visible_graph.with_untracked(|graph| {
+                in_area
+                    .into_iter()
+                    .filter_map(|node_idx| graph.graph.node_weight(node_idx))
+                    .map(|file| file.name.clone())
+                    .collect()
+            });
+        self.selection_state
+            .selected_files
+            .update(|selected_files| {
+                if !area.add {
+                    selected_files.clear();
+                }
+                selected_files.extend(in_area);
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lasso_contains() {
+        // A square with a notch cut in its top edge
+        let lasso = [
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (5.0, 5.0),
+            (6.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+        ];
+        assert!(contains(&lasso, (2.0, 2.0)));
+        assert!(contains(&lasso, (8.0, 8.0)));
+        assert!(!contains(&lasso, (5.0, 2.0)));
+        assert!(!contains(&lasso, (12.0, 5.0)));
+        assert!(!contains(&lasso[..1], (0.0, 0.0)));
+    }
 }
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+/// A rectangle, as its left, top, right and bottom edges.
+pub type Bounds = (f64, f64, f64, f64);
+
+/// Whether two rectangles overlap, touching included.
+pub fn overlaps(a: Bounds, b: Bounds) -> bool {
+    a.2 >= b.0 && a.0 <= b.2 && a.3 >= b.1 && a.1 <= b.3
+}
+
+/// Number of cells past which an entry is not put in the cells it covers,
+/// but checked by every query.
+const MAX_CELLS_PER_ENTRY: i64 = 64;
+
+/// A uniform grid of rectangles, to find the rectangles at a point or in an
+/// area without going through all of them.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<T> {
+    cell_size: f64,
+    entries: Vec<(T, Bounds)>,
+    /// The entries overlapping each cell, in insertion order.
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    /// The entries too large to be put in their cells.
+    large_entries: Vec<usize>,
+}
+
+impl<T> SpatialIndex<T> {
+    /// `cell_size` is best around the size of the entries.
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+            large_entries: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T, bounds: Bounds) {
+        let entry = self.entries.len();
+        self.entries.push((value, bounds));
+
+        let (min_x, min_y) = self.cell_of(bounds.0, bounds.1);
+        let (max_x, max_y) = self.cell_of(bounds.2, bounds.3);
+        if (max_x - min_x + 1) * (max_y - min_y + 1) > MAX_CELLS_PER_ENTRY {
+            self.large_entries.push(entry);
+            return;
+        }
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(entry);
+            }
+        }
+    }
+
+    /// The last inserted entry containing the point, which is the one drawn
+    /// on top.
+    pub fn at(&self, x: f64, y: f64) -> Option<&T> {
+        let cell = self.cells.get(&self.cell_of(x, y)).into_iter().flatten();
+        cell.chain(&self.large_entries)
+            .copied()
+            .filter(|entry| overlaps(self.entries[*entry].1, (x, y, x, y)))
+            .max()
+            .map(|entry| &self.entries[entry].0)
+    }
+
+    /// The entries overlapping `area`, in insertion order.
+    pub fn in_area(&self, area: Bounds) -> Vec<&T> {
+        let (min_x, min_y) = self.cell_of(area.0, area.1);
+        let (max_x, max_y) = self.cell_of(area.2, area.3);
+        let mut entries: Vec<usize> = self.large_entries.clone();
+        if (max_x - min_x + 1) * (max_y - min_y + 1) > self.cells.len() as i64 {
+            // Going through the cells is faster than through the area
+            entries.extend(self.cells.values().flatten());
+        } else {
+            for x in min_x..=max_x {
+                for y in min_y..=max_y {
+                    entries.extend(self.cells.get(&(x, y)).into_iter().flatten());
+                }
+            }
+        }
+        entries.sort_unstable();
+        entries.dedup();
+        entries
+            .into_iter()
+            .filter(|entry| overlaps(self.entries[*entry].1, area))
+            .map(|entry| &self.entries[entry].0)
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(T, Bounds)> {
+        self.entries.iter()
+    }
+
+    fn cell_of(&self, x: f64, y: f64) -> (i64, i64) {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial_index_queries() {
+        let mut index = SpatialIndex::new(10.0);
+        index.insert("a", (0.0, 0.0, 5.0, 5.0));
+        index.insert("b", (4.0, 4.0, 12.0, 12.0));
+        index.insert("c", (-30.0, 50.0, -20.0, 60.0));
+        index.insert("large", (-1000.0, -1000.0, 1000.0, 1000.0));
+
+        assert_eq!(index.at(4.5, 4.5), Some(&"large"));
+        assert_eq!(index.at(-25.0, 55.0), Some(&"large"));
+        assert_eq!(
+            index.in_area((3.0, 3.0, 4.0, 4.0)),
+            vec![&"a", &"b", &"large"]
+        );
+        assert_eq!(index.in_area((-40.0, 40.0, -10.0, 45.0)), vec![&"large"]);
+        assert_eq!(
+            index.in_area((-1e6, -1e6, 1e6, 1e6)),
+            vec![&"a", &"b", &"c", &"large"]
+        );
+
+        let mut index = SpatialIndex::new(10.0);
+        index.insert("a", (0.0, 0.0, 5.0, 5.0));
+        index.insert("b", (4.0, 4.0, 12.0, 12.0));
+        // The last inserted is on top
+        assert_eq!(index.at(4.5, 4.5), Some(&"b"));
+        assert_eq!(index.at(1.0, 1.0), Some(&"a"));
+        assert_eq!(index.at(20.0, 20.0), None);
+    }
+}
@@ -1,19 +1,473 @@
+use std::collections::HashSet;
+
+use super::draw::edge_color;
+use super::highlight_state::EGO_NETWORK_HOPS;
+use super::keymap::CanvasKeymap;
+use super::layout_state::LayoutMode;
+use super::minimap_state::{MINIMAP_HEIGHT, MINIMAP_WIDTH};
+use super::selection_state::AreaShape;
 use super::workspace_layout::WorkspaceLayout;
+use crate::workspace_graph::EdgeKind;
 use floem::{
-    IntoView,
+    AnyView, IntoView, View as _, ViewId,
+    action::show_context_menu,
     event::{Event, EventListener, EventPropagation},
-    prelude::SignalGet as _,
-    views::{Decorators as _, DynamicView, canvas, dyn_view},
+    keyboard::{Key, Modifiers, NamedKey},
+    menu::{Menu, MenuItem},
+    prelude::{SignalGet as _, SignalWith as _, create_effect, palette::css},
+    unit::UnitExt as _,
+    views::{
+        Decorators as _, canvas, dyn_stack, dyn_view, empty, label, stack,
+        stack_from_iter, text_input,
+    },
 };
 
+/// The modifier of the shortcuts of the canvas.
+fn command_modifier() -> Modifiers {
+    if cfg!(target_os = "macos") {
+        Modifiers::META
+    } else {
+        Modifiers::CONTROL
+    }
+}
+
+/// Shows the actions on the selected files, selecting the file under the
+/// pointer first when it is not.
+fn show_selection_menu(layout: &WorkspaceLayout) {
+    if let Some(file_name) = layout.get_hovered_file() {
+        if !layout.is_selected(&file_name) {
+            layout.select_file_hovered_file();
+        }
+    }
+    if layout
+        .selection_state
+        .selected_files
+        .with_untracked(HashSet::is_empty)
+    {
+        return;
+    }
+    let entry = |title: &str, action: fn(&WorkspaceLayout)| {
+        let layout = layout.clone();
+        MenuItem::new(title).action(move || action(&layout))
+    };
+    let menu = Menu::new("")
+        .entry(entry("Open in Split Editors", |layout| {
+            layout.open_selected_files()
+        }))
+        .separator()
+        .entry(entry("Select Dependencies", |layout| {
+            layout.select_linked_files(petgraph::Direction::Outgoing)
+        }))
+        .entry(entry("Select Dependents", |layout| {
+            layout.select_linked_files(petgraph::Direction::Incoming)
+        }))
+        .separator()
+        .entry(entry("Copy Paths", |layout| layout.copy_selected_paths()))
+        .entry(entry(
+            if cfg!(target_os = "macos") {
+                "Reveal in Finder"
+            } else {
+                "Reveal in System File Explorer"
+            },
+            |layout| layout.reveal_selected_files(),
+        ))
+        .separator()
+        .entry(entry("Move to Folder...", |layout| {
+            layout.move_selected_files()
+        }))
+        .entry(entry("Move to Trash...", |layout| {
+            layout.ask_to_trash_selected_files()
+        }));
+    show_context_menu(menu, None);
+}
+
+/// Number of files listed when asking to move files to the trash.
+const LISTED_TRASH_FILES: usize = 8;
+
+/// Asks to confirm moving the files to the trash, listing the first ones.
+fn trash_confirmation(layout: &WorkspaceLayout) -> impl IntoView {
+    let pending_trash = layout.file_actions.pending_trash;
+    let workspace_path = layout.workspace_path.clone();
+    let message = label(move || {
+        pending_trash.with(|paths| {
+            let mut message = match paths.len() {
+                1 => "Move this file to the trash?".to_string(),
+                count => format!("Move these {} files to the trash?", count),
+            };
+            for path in paths.iter().take(LISTED_TRASH_FILES) {
+                let path = path.strip_prefix(&workspace_path).unwrap_or(path);
+                message.push_str(&format!("\n{}", path.display()));
+            }
+            if paths.len() > LISTED_TRASH_FILES {
+                message.push_str(&format!(
+                    "\nand {} more",
+                    paths.len() - LISTED_TRASH_FILES
+                ));
+            }
+            message
+        })
+    });
+    let button = |title: &'static str, action: fn(&WorkspaceLayout)| {
+        let layout = layout.clone();
+        label(move || title)
+            .on_click_stop(move |_| action(&layout))
+            .style(|s| {
+                s.padding_horiz(6.0)
+                    .border_radius(4.0)
+                    .background(css::WHITE.with_alpha(0.15))
+                    .cursor(floem::style::CursorStyle::Pointer)
+            })
+    };
+    let buttons = stack((
+        button("Cancel", |layout| layout.cancel_trash()),
+        button("Move to Trash", |layout| layout.confirm_trash()),
+    ))
+    .style(|s| s.flex_row().gap(8.0));
+
+    stack((message, buttons)).style(move |s| {
+        s.absolute()
+            .inset_top(48.0)
+            .inset_left(50.pct())
+            .margin_left(-200.0)
+            .width(400.0)
+            .flex_col()
+            .gap(8.0)
+            .padding(8.0)
+            .border_radius(4.0)
+            .color(css::WHITE)
+            .background(css::BLACK.with_alpha(0.8))
+            .apply_if(pending_trash.with(Vec::is_empty), |s| s.hide())
+    })
+}
+
+/// A row of toggles showing or hiding the edges of each kind, and bundling
+/// the edges.
+fn edge_kind_filter(layout: &WorkspaceLayout) -> impl IntoView {
+    let kinds = stack_from_iter(EdgeKind::ALL.map(|kind| {
+        let layout = layout.clone();
+        label(move || kind.label())
+            .on_click_stop({
+                let layout = layout.clone();
+                move |_| layout.toggle_edge_kind(kind)
+            })
+            .style(move |s| {
+                let color = if layout.is_edge_kind_visible(kind) {
+                    edge_color(kind)
+                } else {
+                    css::DIM_GRAY
+                };
+                s.color(color)
+                    .padding_horiz(6.0)
+                    .cursor(floem::style::CursorStyle::Pointer)
+            })
+    }))
+    .style(|s| s.flex_row().gap(4.0));
+    let bundling = label(|| "Bundle edges")
+        .on_click_stop({
+            let layout = layout.clone();
+            move |_| layout.toggle_edge_bundling()
+        })
+        .style({
+            let layout = layout.clone();
+            move |s| {
+                let color = if layout.is_edge_bundling() {
+                    css::WHITE
+                } else {
+                    css::DIM_GRAY
+                };
+                s.color(color)
+                    .padding_horiz(6.0)
+                    .cursor(floem::style::CursorStyle::Pointer)
+            }
+        });
+
+    stack((kinds, bundling)).style(|s| {
+        s.absolute()
+            .inset_top(8.0)
+            .inset_left(8.0)
+            .flex_row()
+            .gap(4.0)
+            .padding(4.0)
+            .border_radius(4.0)
+            .background(css::BLACK.with_alpha(0.6))
+    })
+}
+
+/// Buttons switching between the layout modes, and unpinning the nodes
+/// moved by hand.
+fn layout_mode_toggle(layout: &WorkspaceLayout) -> impl IntoView {
+    let modes = stack_from_iter(LayoutMode::ALL.map(|mode| {
+        let layout = layout.clone();
+        label(move || mode.label())
+            .on_click_stop({
+                let layout = layout.clone();
+                move |_| layout.set_layout_mode(mode)
+            })
+            .style(move |s| {
+                let color = if layout.is_layout_mode(mode) {
+                    css::WHITE
+                } else {
+                    css::DIM_GRAY
+                };
+                s.color(color)
+                    .padding_horiz(6.0)
+                    .cursor(floem::style::CursorStyle::Pointer)
+            })
+    }))
+    .style(|s| s.flex_row().gap(4.0));
+    let unpin = label(|| "Unpin all")
+        .on_click_stop({
+            let layout = layout.clone();
+            move |_| layout.unpin_all()
+        })
+        .style({
+            let layout = layout.clone();
+            move |s| {
+                s.color(css::WHITE)
+                    .padding_horiz(6.0)
+                    .cursor(floem::style::CursorStyle::Pointer)
+                    .apply_if(!layout.has_pins(), |s| s.hide())
+            }
+        });
+
+    stack((modes, unpin)).style(|s| {
+        s.absolute()
+            .inset_top(8.0)
+            .inset_right(8.0)
+            .flex_row()
+            .gap(4.0)
+            .padding(4.0)
+            .border_radius(4.0)
+            .background(css::BLACK.with_alpha(0.6))
+    })
+}
+
+/// Buttons showing the whole graph, or only the nodes a few hops around the
+/// selection.
+fn ego_network_toggle(layout: &WorkspaceLayout) -> impl IntoView {
+    let progress = layout.indexing_state.progress;
+    let choices = std::iter::once(None).chain(EGO_NETWORK_HOPS.map(Some));
+    stack_from_iter(choices.map(|hops| {
+        let layout = layout.clone();
+        label(move || match hops {
+            None => "Whole graph".to_string(),
+            Some(1) => "1 hop".to_string(),
+            Some(hops) => format!("{} hops", hops),
+        })
+        .on_click_stop({
+            let layout = layout.clone();
+            move |_| layout.set_ego_hops(hops)
+        })
+        .style(move |s| {
+            let color = if layout.is_ego_hops(hops) {
+                css::WHITE
+            } else {
+                css::DIM_GRAY
+            };
+            s.color(color)
+                .padding_horiz(6.0)
+                .cursor(floem::style::CursorStyle::Pointer)
+        })
+    }))
+    .style(move |s| {
+        s.absolute()
+            .inset_bottom(8.0)
+            .inset_left(8.0)
+            .flex_row()
+            .gap(4.0)
+            .padding(4.0)
+            .border_radius(4.0)
+            .background(css::BLACK.with_alpha(0.6))
+            // Shown in place of the indexing indicator
+            .apply_if(progress.with(|progress| progress.is_some()), |s| s.hide())
+    })
+}
+
+/// The count of indexed files and a progress bar, shown until the graph is
+/// fully fed.
+fn indexing_indicator(layout: &WorkspaceLayout) -> impl IntoView {
+    let progress = layout.indexing_state.progress;
+    stack((
+        label(move || {
+            progress.get().map_or(String::new(), |progress| {
+                format!(
+                    "Indexing {}/{} files",
+                    progress.indexed_files, progress.total_files
+                )
+            })
+        }),
+        empty().style(move |s| {
+            let ratio = progress.with(|progress| {
+                progress.filter(|progress| progress.total_files > 0).map_or(
+                    0.0,
+                    |progress| {
+                        progress.indexed_files as f64 / progress.total_files as f64
+                    },
+                )
+            });
+            s.height(4.0)
+                .width_pct(ratio.min(1.0) * 100.0)
+                .border_radius(2.0)
+                .background(css::CORNFLOWER_BLUE)
+        }),
+    ))
+    .style(move |s| {
+        s.absolute()
+            .inset_bottom(8.0)
+            .inset_left(8.0)
+            .width(240.0)
+            .flex_col()
+            .gap(4.0)
+            .padding(6.0)
+            .border_radius(4.0)
+            .color(css::WHITE)
+            .background(css::BLACK.with_alpha(0.6))
+            .apply_if(progress.with(|progress| progress.is_none()), |s| s.hide())
+    })
+}
+
+/// The whole graph at thumbnail scale, with the part shown on the screen.
+/// Clicking or dragging in it moves the view there.
+fn minimap(layout: &WorkspaceLayout) -> impl IntoView {
+    let positions = layout.layout_state.positions;
+    canvas({
+        let layout = layout.clone();
+        move |cx, size| layout.draw_minimap(cx, size)
+    })
+    .on_event_stop(EventListener::PointerDown, {
+        let layout = layout.clone();
+        move |event| {
+            if let Some(pointer_position) = event.point() {
+                layout.start_minimap_drag(pointer_position.x, pointer_position.y);
+            }
+        }
+    })
+    .on_event_stop(EventListener::PointerMove, {
+        let layout = layout.clone();
+        move |event| {
+            if let Some(pointer_position) = event.point() {
+                layout.minimap_drag(pointer_position.x, pointer_position.y);
+            }
+        }
+    })
+    .on_event_stop(EventListener::PointerUp, {
+        let layout = layout.clone();
+        move |_event| layout.end_minimap_drag()
+    })
+    .on_event_stop(EventListener::PointerLeave, {
+        let layout = layout.clone();
+        move |_event| layout.end_minimap_drag()
+    })
+    .style(move |s| {
+        s.absolute()
+            .inset_bottom(8.0)
+            .inset_right(8.0)
+            .width(MINIMAP_WIDTH)
+            .height(MINIMAP_HEIGHT)
+            .border(1.0)
+            .border_color(css::DIM_GRAY)
+            .border_radius(4.0)
+            .background(css::BLACK.with_alpha(0.6))
+            .cursor(floem::style::CursorStyle::Pointer)
+            .apply_if(positions.with(|positions| positions.is_empty()), |s| {
+                s.hide()
+            })
+    })
+}
+
+/// A field fuzzy searching the files, listing the best matches. The view
+/// flies to the chosen match, Enter selects it and Ctrl+Enter opens it.
+/// Focus goes back to `canvas_id` once the search is closed.
+fn search_overlay(layout: &WorkspaceLayout, canvas_id: ViewId) -> impl IntoView {
+    let search_state = layout.search_state.clone();
+    let open = search_state.open;
+    let selected = search_state.selected;
+
+    let input = text_input(search_state.query).style(|s| s.width_full());
+    let input_id = input.id();
+    create_effect(move |_| {
+        if open.get() {
+            input_id.request_focus();
+        }
+    });
+
+    let matches = dyn_stack(
+        move || {
+            search_state
+                .matches
+                .get()
+                .into_iter()
+                .enumerate()
+                .collect::<Vec<_>>()
+        },
+        |(index, file)| (*index, file.file_name.clone()),
+        {
+            let layout = layout.clone();
+            move |(index, file)| {
+                let layout = layout.clone();
+                label(move || file.path.clone())
+                    .on_click_stop(move |_| layout.select_match(index))
+                    .style(move |s| {
+                        s.padding_horiz(6.0)
+                            .padding_vert(2.0)
+                            .border_radius(4.0)
+                            .cursor(floem::style::CursorStyle::Pointer)
+                            .apply_if(selected.get() == index, |s| {
+                                s.background(css::WHITE.with_alpha(0.15))
+                            })
+                    })
+            }
+        },
+    )
+    .style(|s| s.flex_col());
+
+    stack((input, matches))
+        .on_event(EventListener::KeyDown, {
+            let layout = layout.clone();
+            move |event| {
+                let Event::KeyDown(key_event) = event else {
+                    return EventPropagation::Continue;
+                };
+                match &key_event.key.logical_key {
+                    Key::Named(NamedKey::Escape) => layout.close_search(),
+                    Key::Named(NamedKey::ArrowDown) => {
+                        layout.select_next_match(true)
+                    }
+                    Key::Named(NamedKey::ArrowUp) => layout.select_next_match(false),
+                    Key::Named(NamedKey::Enter) => layout.confirm_match(
+                        key_event.modifiers.contains(command_modifier()),
+                    ),
+                    _ => return EventPropagation::Continue,
+                }
+                if !open.get_untracked() {
+                    canvas_id.request_focus();
+                }
+                EventPropagation::Stop
+            }
+        })
+        .style(move |s| {
+            s.absolute()
+                .inset_top(48.0)
+                .inset_left(50.pct())
+                .margin_left(-200.0)
+                .width(400.0)
+                .flex_col()
+                .gap(4.0)
+                .padding(6.0)
+                .border_radius(4.0)
+                .color(css::WHITE)
+                .background(css::BLACK.with_alpha(0.8))
+                .apply_if(!open.get(), |s| s.hide())
+        })
+}
+
 impl IntoView for WorkspaceLayout {
-    type V = DynamicView;
+    type V = AnyView;
 
     fn into_view(self) -> Self::V {
-        let editor = self.editor.clone();
         let layout = self.clone();
 
-        dyn_view({
+        let graph_view = dyn_view({
             let layout = layout.clone();
             move || {
                 canvas({
@@ -26,9 +480,50 @@ fn into_view(self) -> Self::V {
             }
         })
         .style(move |s| s.size_full())
+        .keyboard_navigable()
+        .on_resize({
+            let layout = layout.clone();
+            move |rect| layout.set_canvas_size(rect.width(), rect.height())
+        })
+        .on_event(EventListener::KeyDown, {
+            let layout = layout.clone();
+            let keymap = CanvasKeymap::load();
+            move |event| {
+                if let Event::KeyDown(key_event) = event {
+                    if let Some(command) = keymap.command(key_event) {
+                        layout.run_canvas_command(command);
+                        return EventPropagation::Stop;
+                    }
+                }
+                EventPropagation::Continue
+            }
+        })
         .on_event(EventListener::PointerDown, {
             let layout = layout.clone();
-            move |_event| {
+            move |event| {
+                if let Event::PointerDown(pointer_event) = event {
+                    // Shift drags a rectangle and Alt a lasso over the files to
+                    // select, instead of moving the view
+                    let modifiers = pointer_event.modifiers;
+                    let shape = if !pointer_event.button.is_primary() {
+                        None
+                    } else if modifiers.shift() {
+                        Some(AreaShape::Rectangle)
+                    } else if modifiers.alt() {
+                        Some(AreaShape::Lasso)
+                    } else {
+                        None
+                    };
+                    if let Some(shape) = shape {
+                        layout.start_area_selection(
+                            shape,
+                            pointer_event.pos.x,
+                            pointer_event.pos.y,
+                            modifiers.contains(command_modifier()),
+                        );
+                        return EventPropagation::Continue;
+                    }
+                }
                 layout.start_mouse_drag();
                 EventPropagation::Continue
             }
@@ -36,6 +531,7 @@ fn into_view(self) -> Self::V {
         .on_event(EventListener::PointerUp, {
             let layout = layout.clone();
             move |_event| {
+                layout.end_area_selection();
                 layout.end_mouse_drag();
                 EventPropagation::Continue
             }
@@ -48,27 +544,25 @@ fn into_view(self) -> Self::V {
                         pointer_position.x,
                         pointer_position.y,
                     );
+                    layout.extend_area_selection(
+                        pointer_position.x,
+                        pointer_position.y,
+                    );
                     layout
                         .track_hovered_file(pointer_position.x, pointer_position.y);
+                    layout.track_hovered_directory(
+                        pointer_position.x,
+                        pointer_position.y,
+                    );
                 }
                 EventPropagation::Continue
             }
         })
         .on_event(EventListener::PinchGesture, {
-            let editor = editor.clone();
             let layout = layout.clone();
             move |event| {
                 if let Event::PinchGesture(pinch_event) = event {
-                    layout.zoom(pinch_event.delta);
-
-                    // When we zoomed at maximum zoom level, we can open the file we are hovering over
-                    if layout.view_state.zoom.get() == 3.5 && pinch_event.delta > 0.0
-                    {
-                        let editor = editor.clone();
-                        layout.get_hovered_file().map(|file_name| {
-                            editor.open_file(&file_name);
-                        });
-                    }
+                    layout.zoom_to_open(pinch_event.delta);
                 }
                 EventPropagation::Continue
             }
@@ -77,31 +571,68 @@ fn into_view(self) -> Self::V {
             let layout = layout.clone();
             move |event| {
                 if let Event::PointerWheel(pointer_wheel_event) = event {
-                    layout.move_(
-                        pointer_wheel_event.delta.x,
-                        pointer_wheel_event.delta.y,
-                    );
+                    let modifiers = pointer_wheel_event.modifiers;
+                    if modifiers.control() || modifiers.meta() {
+                        layout.wheel_zoom(pointer_wheel_event.delta.y);
+                    } else {
+                        layout.move_(
+                            pointer_wheel_event.delta.x,
+                            pointer_wheel_event.delta.y,
+                        );
+                    }
                 }
                 EventPropagation::Continue
             }
         })
         .on_event(EventListener::Click, {
             let layout = layout.clone();
-            move |_event| {
-                layout.select_file_hovered_file();
+            move |event| {
+                if let Event::PointerUp(pointer_event) = event {
+                    let modifiers = pointer_event.modifiers;
+                    // The files were selected with an area
+                    if modifiers.shift() || modifiers.alt() {
+                        return EventPropagation::Continue;
+                    }
+                    if modifiers.contains(command_modifier()) {
+                        layout.multiselect_files_hovered_file();
+                        return EventPropagation::Continue;
+                    }
+                }
+                // Clicking a directory collapses or expands it
+                if !layout.toggle_hovered_directory() {
+                    layout.select_file_hovered_file();
+                }
                 EventPropagation::Continue
             }
         })
+        .on_secondary_click_stop({
+            let layout = layout.clone();
+            move |_event| show_selection_menu(&layout)
+        })
         .on_event(EventListener::DoubleClick, {
-            let editor = editor.clone();
             let layout = layout.clone();
             move |_event| {
-                let editor = editor.clone();
                 layout.get_hovered_file().map(|file_name| {
-                    editor.open_file(&file_name);
+                    layout.open_file(&file_name);
                 });
                 EventPropagation::Continue
             }
-        })
+        });
+
+        let canvas_id = graph_view.id();
+        canvas_id.request_focus();
+
+        stack((
+            graph_view,
+            search_overlay(&layout, canvas_id),
+            trash_confirmation(&layout),
+            edge_kind_filter(&layout),
+            layout_mode_toggle(&layout),
+            ego_network_toggle(&layout),
+            indexing_indicator(&layout),
+            minimap(&layout),
+        ))
+        .style(|s| s.size_full())
+        .into_any()
     }
 }
@@ -1,12 +1,102 @@
-use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _};
+use std::time::{Duration, Instant};
 
+use floem::action::{TimerToken, exec_after};
+use floem::prelude::{RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _};
+use serde::{Deserialize, Serialize};
+
+use super::spatial_index::Bounds;
+use crate::settings::read_settings;
 use crate::workspace_layout::workspace_layout::WorkspaceLayout;
 
+/// How long the view takes to fly to a node.
+const FLIGHT_DURATION: Duration = Duration::from_millis(300);
+
+/// Time between two steps of a flight.
+const FLIGHT_FRAME: Duration = Duration::from_millis(16);
+
+/// Zoom level the view flies to when it is zoomed out farther, at which file
+/// names can be read.
+const FLIGHT_MIN_ZOOM: f64 = 1.0;
+
+/// Largest zoom step of a notch of the mouse wheel.
+const MAX_WHEEL_ZOOM_FACTOR: f64 = 0.5;
+
+/// How far the view zooms, read from the `[amas.view]` table of the
+/// settings file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewSettings {
+    pub min_zoom: f64,
+    pub max_zoom: f64,
+    /// Zoom level from which zooming in further on a file opens it in the
+    /// editor.
+    pub open_zoom: f64,
+    /// Zoom factor of each pixel scrolled with the mouse wheel while holding
+    /// Ctrl.
+    pub wheel_zoom_speed: f64,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            min_zoom: 0.1,
+            max_zoom: 3.5,
+            open_zoom: 3.5,
+            wheel_zoom_speed: 0.002,
+        }
+    }
+}
+
+impl ViewSettings {
+    /// Loads the settings of the user, falling back to the defaults when
+    /// they are missing or do not make sense.
+    pub fn load() -> Self {
+        let Some(settings) = read_settings::<ViewSettings>("view") else {
+            return Self::default();
+        };
+        if !settings.is_valid() {
+            eprintln!("Ignoring the invalid view settings: {:?}", settings);
+            return Self::default();
+        }
+        settings
+    }
+
+    /// Whether the zoom levels are ordered, so that clamping to them cannot
+    /// fail and the open zoom level can be reached.
+    fn is_valid(&self) -> bool {
+        [
+            self.min_zoom,
+            self.max_zoom,
+            self.open_zoom,
+            self.wheel_zoom_speed,
+        ]
+        .iter()
+        .all(|value| value.is_finite())
+            && 0.0 < self.min_zoom
+            && self.min_zoom <= self.open_zoom
+            && self.open_zoom <= self.max_zoom
+            && self.wheel_zoom_speed > 0.0
+    }
+}
+
+/// Where the view is headed, as the point of the canvas in the middle of the
+/// screen and the zoom level.
+#[derive(Debug, Clone, Copy)]
+struct ViewTarget {
+    x: f64,
+    y: f64,
+    zoom: f64,
+}
+
 #[derive(Clone, Debug)]
 pub struct ViewState {
     pub zoom: RwSignal<f64>,
     pub translation_x: RwSignal<f64>,
     pub translation_y: RwSignal<f64>,
+    /// Size of the canvas on the screen, in pixels.
+    pub width: RwSignal<f64>,
+    pub height: RwSignal<f64>,
+    pub settings: RwSignal<ViewSettings>,
 
     // Drag internal states
     drag_started: RwSignal<bool>,
@@ -15,6 +105,8 @@ pub struct ViewState {
     // Mouse tracking internal states
     mouse_position_x: RwSignal<f64>,
     mouse_position_y: RwSignal<f64>,
+    // The timer of the next step of the flight in progress
+    flight: RwSignal<Option<TimerToken>>,
 }
 
 impl ViewState {
@@ -22,6 +114,9 @@ pub fn new() -> Self {
         let zoom = RwSignal::new(1.0);
         let translation_x = RwSignal::new(0.0);
         let translation_y = RwSignal::new(0.0);
+        let width = RwSignal::new(0.0);
+        let height = RwSignal::new(0.0);
+        let settings = RwSignal::new(ViewSettings::load());
 
         let drag_started = RwSignal::new(false);
         let drag_start_x = RwSignal::new(0.0);
@@ -30,16 +125,22 @@ pub fn new() -> Self {
         let mouse_position_x = RwSignal::new(0.0);
         let mouse_position_y = RwSignal::new(0.0);
 
+        let flight = RwSignal::new(None);
+
         Self {
             zoom,
             translation_x,
             translation_y,
+            width,
+            height,
+            settings,
             // Internal states
             drag_started,
             drag_start_x,
             drag_start_y,
             mouse_position_x,
             mouse_position_y,
+            flight,
         }
     }
 }
@@ -53,6 +154,7 @@ pub fn track_mouse_position(&self, x: f64, y: f64) {
     }
 
     pub fn start_mouse_drag(&self) {
+        self.view_state.flight.set(None);
         self.view_state.drag_started.set(true);
         self.view_state
             .drag_start_x
@@ -60,6 +162,8 @@ pub fn start_mouse_drag(&self) {
         self.view_state
             .drag_start_y
             .set(self.view_state.mouse_position_y.get());
+        // Dragging a node moves it instead of the view
+        self.start_node_drag();
     }
 
     fn mouse_drag(&self, current_x: f64, current_y: f64) {
@@ -73,8 +177,10 @@ fn mouse_drag(&self, current_x: f64, current_y: f64) {
         let delta_x = current_x - start_x;
         let delta_y = current_y - start_y;
 
-        self.view_state.translation_x.update(|x| *x += delta_x);
-        self.view_state.translation_y.update(|y| *y += delta_y);
+        if !self.drag_nodes(delta_x, delta_y) {
+            self.view_state.translation_x.update(|x| *x += delta_x);
+            self.view_state.translation_y.update(|y| *y += delta_y);
+        }
 
         // Update the drag start position for the next event
         self.view_state.drag_start_x.set(current_x);
@@ -83,18 +189,68 @@ fn mouse_drag(&self, current_x: f64, current_y: f64) {
 
     pub fn end_mouse_drag(&self) {
         self.view_state.drag_started.set(false);
+        self.end_node_drag();
     }
 
     pub fn zoom(&self, factor: f64) {
         let mouse_x = self.view_state.mouse_position_x.get();
         let mouse_y = self.view_state.mouse_position_y.get();
+        self.zoom_around(factor, mouse_x, mouse_y);
+    }
+
+    /// Zooms around the pointer, opening the hovered file when zooming in
+    /// past the open zoom level.
+    pub fn zoom_to_open(&self, factor: f64) {
+        self.zoom(factor);
+        self.open_when_zoomed_in(factor, self.get_hovered_file());
+    }
+
+    /// Zooms for the mouse wheel scrolled by `delta` pixels, down being
+    /// positive.
+    pub fn wheel_zoom(&self, delta: f64) {
+        let speed = self
+            .view_state
+            .settings
+            .with_untracked(|settings| settings.wheel_zoom_speed);
+        let factor =
+            (-delta * speed).clamp(-MAX_WHEEL_ZOOM_FACTOR, MAX_WHEEL_ZOOM_FACTOR);
+        self.zoom_to_open(factor);
+    }
+
+    /// Opens `file_name` when the view was zoomed in by `factor` up to the
+    /// open zoom level.
+    pub(super) fn open_when_zoomed_in(
+        &self,
+        factor: f64,
+        file_name: Option<String>,
+    ) {
+        let open_zoom = self
+            .view_state
+            .settings
+            .with_untracked(|settings| settings.open_zoom);
+        if factor <= 0.0 || self.view_state.zoom.get_untracked() < open_zoom {
+            return;
+        }
+        if let Some(file_name) = file_name {
+            self.open_file(&file_name);
+        }
+    }
+
+    /// Zooms by `factor`, keeping the point of the screen at `mouse_x` and
+    /// `mouse_y` in place.
+    pub fn zoom_around(&self, factor: f64, mouse_x: f64, mouse_y: f64) {
         let old_zoom = self.view_state.zoom.get();
         let zoom_factor = 1.0 + factor * 1.0;
-        let new_zoom = (old_zoom * zoom_factor).clamp(0.1, 3.5);
+        let (min_zoom, max_zoom) = self
+            .view_state
+            .settings
+            .with_untracked(|settings| (settings.min_zoom, settings.max_zoom));
+        let new_zoom = (old_zoom * zoom_factor).clamp(min_zoom, max_zoom);
 
         if old_zoom == new_zoom {
             return;
         }
+        self.view_state.flight.set(None);
 
         let old_tx = self.view_state.translation_x.get();
         let old_ty = self.view_state.translation_y.get();
@@ -112,8 +268,125 @@ pub fn zoom(&self, factor: f64) {
         self.view_state.translation_y.set(new_ty);
     }
 
+    pub fn set_canvas_size(&self, width: f64, height: f64) {
+        self.view_state.width.set(width);
+        self.view_state.height.set(height);
+    }
+
+    /// The part of the canvas shown on the screen, in canvas units.
+    pub(super) fn viewport(&self) -> Bounds {
+        let zoom = self.view_state.zoom.get();
+        let translation_x = self.view_state.translation_x.get();
+        let translation_y = self.view_state.translation_y.get();
+        (
+            -translation_x / zoom,
+            -translation_y / zoom,
+            (self.view_state.width.get() - translation_x) / zoom,
+            (self.view_state.height.get() - translation_y) / zoom,
+        )
+    }
+
+    /// Animates the view towards the node of `file_name`, zooming in until
+    /// its name can be read.
+    pub fn fly_to_file(&self, file_name: &str) {
+        let position = self.cluster_state.visible_graph.with_untracked(|graph| {
+            let node_idx = graph.find_file(file_name)?;
+            self.layout_state
+                .positions
+                .with_untracked(|positions| positions.get(&node_idx).cloned())
+        });
+        let Some(position) = position else {
+            return;
+        };
+        let zoom = self.view_state.zoom.get_untracked();
+        let max_zoom = self
+            .view_state
+            .settings
+            .with_untracked(|settings| settings.max_zoom);
+        let target = ViewTarget {
+            x: position.x,
+            y: position.y,
+            zoom: zoom.max(FLIGHT_MIN_ZOOM.min(max_zoom)),
+        };
+        self.fly_step(self.view_target(), target, Instant::now());
+    }
+
+    /// The point of the canvas in the middle of the screen, and the zoom.
+    fn view_target(&self) -> ViewTarget {
+        let zoom = self.view_state.zoom.get_untracked();
+        ViewTarget {
+            x: (self.view_state.width.get_untracked() / 2.0
+                - self.view_state.translation_x.get_untracked())
+                / zoom,
+            y: (self.view_state.height.get_untracked() / 2.0
+                - self.view_state.translation_y.get_untracked())
+                / zoom,
+            zoom,
+        }
+    }
+
+    /// Moves the view along the way from `from` to `to`, then schedules the
+    /// next step until the view gets there.
+    fn fly_step(&self, from: ViewTarget, to: ViewTarget, started: Instant) {
+        let progress = (started.elapsed().as_secs_f64()
+            / FLIGHT_DURATION.as_secs_f64())
+        .min(1.0);
+        // Eases in and out
+        let t = progress * progress * (3.0 - 2.0 * progress);
+        let x = from.x + (to.x - from.x) * t;
+        let y = from.y + (to.y - from.y) * t;
+        let zoom = from.zoom + (to.zoom - from.zoom) * t;
+        self.view_state.zoom.set(zoom);
+        self.view_state
+            .translation_x
+            .set(self.view_state.width.get_untracked() / 2.0 - x * zoom);
+        self.view_state
+            .translation_y
+            .set(self.view_state.height.get_untracked() / 2.0 - y * zoom);
+
+        if progress >= 1.0 {
+            self.view_state.flight.set(None);
+            return;
+        }
+        let layout = self.clone();
+        let token = exec_after(FLIGHT_FRAME, move |token| {
+            // Another flight started, or the view was moved by hand
+            if layout.view_state.flight.get_untracked() == Some(token) {
+                layout.fly_step(from, to, started);
+            }
+        });
+        self.view_state.flight.set(Some(token));
+    }
+
     pub fn move_(&self, dx: f64, dy: f64) {
+        self.view_state.flight.set(None);
         self.view_state.translation_x.update(|x| *x += dx);
         self.view_state.translation_y.update(|y| *y += dy);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_settings_validity() {
+        assert!(ViewSettings::default().is_valid());
+        let inverted = ViewSettings {
+            min_zoom: 4.0,
+            max_zoom: 1.0,
+            ..Default::default()
+        };
+        assert!(!inverted.is_valid());
+        let unreachable_open = ViewSettings {
+            open_zoom: 10.0,
+            ..Default::default()
+        };
+        assert!(!unreachable_open.is_valid());
+        let not_a_number = ViewSettings {
+            wheel_zoom_speed: f64::NAN,
+            ..Default::default()
+        };
+        assert!(!not_a_number.is_valid());
+    }
+}
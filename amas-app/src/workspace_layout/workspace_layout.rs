@@ -1,29 +1,113 @@
-use super::view_state::ViewState;
-use super::selection_state::SelectionState;
+use super::bundling_state::BundlingState;
 use super::canva_state::CanvaState;
+use super::cluster_state::ClusterState;
+use super::file_actions::FileActions;
+use super::filter_state::FilterState;
+use super::focus_state::FocusState;
+use super::highlight_state::HighlightState;
+use super::indexing_state::IndexingState;
+use super::layout_state::LayoutState;
+use super::minimap_state::MinimapState;
+use super::pin_state::PinState;
+use super::preview_state::PreviewState;
+use super::search_state::SearchState;
+use super::selection_state::SelectionState;
+use super::view_state::ViewState;
 use crate::editor::Editor;
 use crate::workspace_graph::WorkspaceGraph;
+use floem::prelude::{RwSignal, SignalWith as _};
+use std::path::PathBuf;
 
 #[derive(Clone, Debug)]
 pub struct WorkspaceLayout {
     pub(super) editor: Editor,
-    pub(super) workspace_graph: WorkspaceGraph,
+    pub(super) workspace_graph: RwSignal<WorkspaceGraph>,
+    pub(super) workspace_path: PathBuf,
+    pub(super) file_actions: FileActions,
     pub view_state: ViewState,
     pub selection_state: SelectionState,
     pub canva_state: CanvaState,
+    pub filter_state: FilterState,
+    pub indexing_state: IndexingState,
+    pub layout_state: LayoutState,
+    pub cluster_state: ClusterState,
+    pub preview_state: PreviewState,
+    pub pin_state: PinState,
+    pub minimap_state: MinimapState,
+    pub search_state: SearchState,
+    pub focus_state: FocusState,
+    pub highlight_state: HighlightState,
+    pub bundling_state: BundlingState,
 }
 
 impl WorkspaceLayout {
-    pub fn new(workspace_graph: WorkspaceGraph, editor: Editor) -> Self {
+    pub fn new(
+        workspace_graph: RwSignal<WorkspaceGraph>,
+        editor: Editor,
+        workspace_path: PathBuf,
+    ) -> Self {
         let view_state = ViewState::new();
         let selection_state = SelectionState::new();
         let canva_state = CanvaState::new();
-        Self {
+        let filter_state = FilterState::new();
+        let indexing_state = IndexingState::new();
+        let layout_state = LayoutState::new();
+        let cluster_state = ClusterState::new();
+        let preview_state = PreviewState::new();
+        let pin_state = PinState::new();
+        let minimap_state = MinimapState::new();
+        let search_state = SearchState::new();
+        let focus_state = FocusState::new();
+        let highlight_state = HighlightState::new();
+        let bundling_state = BundlingState::new();
+        let file_actions = FileActions::new();
+        let layout = Self {
             workspace_graph,
+            workspace_path,
             editor,
+            file_actions,
             view_state,
             selection_state,
             canva_state,
+            filter_state,
+            indexing_state,
+            layout_state,
+            cluster_state,
+            preview_state,
+            pin_state,
+            minimap_state,
+            search_state,
+            focus_state,
+            highlight_state,
+            bundling_state,
+        };
+        layout.restore_layout();
+        layout.collapse_on_graph_change();
+        layout.layout_on_graph_change();
+        layout.index_nodes_on_layout_change();
+        layout.index_edges_on_graph_change();
+        layout.bundle_edges_on_layout_change();
+        layout.clear_previews_on_graph_change();
+        layout.save_layout_on_change();
+        layout.search_on_query_change();
+        layout
+    }
+
+    /// The file of the `file_name` node, unless it stands for an external
+    /// package or a collapsed directory that has no file to open.
+    pub(super) fn file_to_open(&self, file_name: &str) -> Option<String> {
+        let visible_graph = self.cluster_state.visible_graph;
+        visible_graph.with_untracked(|workspace_graph| {
+            let node_idx = workspace_graph.find_file(file_name)?;
+            let file = &workspace_graph.graph[node_idx];
+            (!file.external && !file.directory).then(|| file.file_name().to_string())
+        })
+    }
+
+    /// Opens the file of the `file_name` node in the editor, if it has one.
+    pub fn open_file(&self, file_name: &str) {
+        if let Some(file_name) = self.file_to_open(file_name) {
+            self.editor.open_file(&file_name);
         }
     }
 }